@@ -85,6 +85,33 @@ function handleEvent(event) {
         addEventToLog(`⭐ ${event.PlayerSpawned.name} spawnou em (${event.PlayerSpawned.pos.x},${event.PlayerSpawned.pos.y})`);
     }
     
+    if (event.EntitySpawned) {
+        const { name, pos } = event.EntitySpawned;
+        totalEntities++;
+        addEventToLog(`🐾 ${name} surgiu em (${pos.x},${pos.y})`);
+    }
+
+    if (event.EntityDied) {
+        const { name, pos } = event.EntityDied;
+        totalEntities = Math.max(0, totalEntities - 1);
+        addEventToLog(`💀 ${name} morreu em (${pos.x},${pos.y})`);
+    }
+
+    if (event.CaravanDeparted) {
+        const { origin, destination } = event.CaravanDeparted;
+        addEventToLog(`🐫 Caravana partiu de ${origin} para ${destination}`);
+    }
+
+    if (event.CaravanArrived) {
+        const { origin, destination } = event.CaravanArrived;
+        addEventToLog(`📦 Caravana de ${origin} chegou a ${destination}`);
+    }
+
+    if (event.HistoryRecorded) {
+        const { year, description } = event.HistoryRecorded;
+        addEventToLog(`📜 Ano ${year}: ${description}`);
+    }
+
     if (event.WorldTick) {
         const worldTick = document.getElementById('world-tick');
         if (worldTick) {
@@ -201,6 +228,126 @@ function renderPlayerList(players) {
     `).join('');
 }
 
+// Buscar snapshots do mundo
+async function fetchSnapshots() {
+    try {
+        const response = await fetch(`${API_BASE}/admin/snapshots`);
+        const snapshots = await response.json();
+        renderSnapshotList(snapshots);
+    } catch (error) {
+        console.error('Erro ao buscar snapshots:', error);
+    }
+}
+
+// Renderizar lista de snapshots
+function renderSnapshotList(snapshots) {
+    const listEl = document.getElementById('snapshot-list');
+    if (!listEl) return;
+
+    if (snapshots.length === 0) {
+        listEl.innerHTML = '<div class="player-card">Nenhum snapshot capturado</div>';
+        return;
+    }
+
+    listEl.innerHTML = snapshots.map(snapshot => `
+        <div class="player-card">
+            <div><strong>Tick ${snapshot.tick}</strong></div>
+            <div>Entidades: ${snapshot.entities}</div>
+            <div>Eventos: ${snapshot.events}</div>
+            <button class="snapshot-button" onclick="showSnapshotDiff(${snapshot.tick})">Diff</button>
+            <button class="snapshot-button" onclick="rollbackSnapshot(${snapshot.tick})">Rollback</button>
+        </div>
+    `).join('');
+}
+
+// Token da sessão de administrador, pedido no primeiro comando que muda o mundo
+let adminToken = sessionStorage.getItem('adminToken');
+
+async function adminLogin() {
+    const username = prompt('Usuário administrador:');
+    if (!username) return null;
+    const password = prompt('Senha:');
+    const response = await fetch(`${API_BASE.replace(/\/api$/, '')}/auth/login`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ username, password: password || '' })
+    });
+    const login = await response.json();
+    if (!login.success || login.role !== 'Admin') {
+        alert(login.success ? 'Este usuário não é administrador' : login.message);
+        return null;
+    }
+    sessionStorage.setItem('adminToken', login.token);
+    return login.token;
+}
+
+// POST autenticado como administrador; refaz o login se o token expirou
+async function adminPost(path) {
+    for (let attempt = 0; attempt < 2; attempt++) {
+        if (!adminToken) adminToken = await adminLogin();
+        if (!adminToken) return null;
+        const response = await fetch(`${API_BASE}${path}`, {
+            method: 'POST',
+            headers: { 'Authorization': `Bearer ${adminToken}` }
+        });
+        if (response.status !== 401) return response;
+        adminToken = null;
+        sessionStorage.removeItem('adminToken');
+    }
+    return null;
+}
+
+// Capturar o estado atual do mundo
+async function captureSnapshot() {
+    try {
+        const response = await adminPost('/admin/snapshots');
+        if (!response) return;
+        const snapshot = await response.json();
+        addEventToLog(`Snapshot capturado no tick ${snapshot.tick}`);
+        fetchSnapshots();
+    } catch (error) {
+        console.error('Erro ao capturar snapshot:', error);
+    }
+}
+
+// Mostrar o que mudou desde um snapshot
+async function showSnapshotDiff(tick) {
+    const diffEl = document.getElementById('snapshot-diff');
+    try {
+        const response = await fetch(`${API_BASE}/admin/snapshots/${tick}/diff`);
+        const diff = await response.json();
+        if (!response.ok) {
+            diffEl.textContent = diff.ActionResult.message;
+            return;
+        }
+        const pos = p => `(${p.x}, ${p.y})`;
+        const lines = [`Tick ${diff.from_tick} → ${diff.to_tick}`];
+        diff.spawned.forEach(e => lines.push(`+ ${e.name} #${e.id} em ${pos(e.pos)}`));
+        diff.despawned.forEach(e => lines.push(`- ${e.name} #${e.id} em ${pos(e.pos)}`));
+        diff.moved.forEach(m => lines.push(`~ ${m.name} #${m.id}: ${pos(m.from)} → ${pos(m.to)}`));
+        lines.push(`Tiles alterados: ${diff.tiles.length}`);
+        lines.push(`Construções alteradas: ${diff.constructions.length}`);
+        diff.events.forEach(e => lines.push(`📜 ${e.description}`));
+        diffEl.textContent = lines.join('\n');
+    } catch (error) {
+        console.error('Erro ao comparar snapshot:', error);
+    }
+}
+
+// Voltar o mundo a um snapshot
+async function rollbackSnapshot(tick) {
+    if (!confirm(`Voltar o mundo ao tick ${tick}? Tudo o que aconteceu depois será perdido.`)) return;
+    try {
+        const response = await adminPost(`/admin/snapshots/${tick}/rollback`);
+        if (!response) return;
+        const result = await response.json();
+        addEventToLog(result.ActionResult.message);
+        document.getElementById('snapshot-diff').textContent = '';
+    } catch (error) {
+        console.error('Erro ao voltar ao snapshot:', error);
+    }
+}
+
 // Sistema de tabs atualizado
 function switchTab(tabName) {
     // Remove active de todas as tabs
@@ -219,6 +366,8 @@ function switchTab(tabName) {
         fetchWorldMap();
     } else if (tabName === 'players') {
         fetchPlayers();
+    } else if (tabName === 'snapshots') {
+        fetchSnapshots();
     }
 }
 
@@ -249,6 +398,8 @@ function init() {
         });
     });
     
+    document.getElementById('capture-snapshot')?.addEventListener('click', captureSnapshot);
+
     // Conectar WebSocket
     connectWebSocket();
     
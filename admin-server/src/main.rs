@@ -1,4 +1,5 @@
-use futures::{SinkExt, StreamExt};
+use futures::StreamExt;
+use ipc::{WorldClient, WORLD_ADDR};
 use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
@@ -17,6 +18,7 @@ async fn main() {
     tokio::spawn(async move {
         connect_to_game_server(event_tx_clone).await;
     });
+    tokio::spawn(connect_to_backend(event_tx.clone()));
 
     let event_tx_filter = warp::any().map(move || event_tx.clone());
     
@@ -40,7 +42,7 @@ async fn main() {
         .with(cors);  // ← Adiciona CORS
 
     println!("🖥️  Admin Panel: http://127.0.0.1:3030");
-    println!("🔗 Aguardando conexão ao Game Server...");
+    println!("🔗 Aguardando conexão ao Game Server e ao Backend...");
     println!("✅ CORS habilitado");
     
     warp::serve(routes).run(([127, 0, 0, 1], 3030)).await;
@@ -74,6 +76,33 @@ async fn connect_to_game_server(event_tx: EventBroadcaster) {
     }
 }
 
+/// Eventos do mundo (ticks, mortes, caravanas, história), assinados direto
+/// no backend, dono do mundo
+async fn connect_to_backend(event_tx: EventBroadcaster) {
+    loop {
+        if let Err(e) = relay_backend_events(&event_tx).await {
+            println!("❌ Erro ao conectar ao Backend: {}", e);
+        }
+        println!("🔄 Tentando novamente em 5 segundos...");
+        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+    }
+}
+
+/// Repassa os eventos do backend até a conexão cair
+async fn relay_backend_events(event_tx: &EventBroadcaster) -> Result<(), ipc::RpcError> {
+    let backend = WorldClient::connect(WORLD_ADDR).await?;
+    let mut events = backend.subscribe().await?;
+    println!("✅ Admin-Server conectado ao Backend");
+    while let Some(event) = events.next().await {
+        if let Ok(text) = serde_json::to_string(&event) {
+            let tx = event_tx.lock().unwrap();
+            let _ = tx.send(text);
+        }
+    }
+    println!("⚠️  Desconectado do Backend");
+    Ok(())
+}
+
 async fn handle_admin_websocket(
     ws: warp::ws::WebSocket,
     event_tx: EventBroadcaster,
@@ -96,7 +125,7 @@ async fn handle_admin_websocket(
 
     // Task para receber comandos do admin (futuro)
     let recv_task = tokio::spawn(async move {
-        while let Some(_) = ws_rx.next().await {
+        while ws_rx.next().await.is_some() {
             // Admin pode enviar comandos aqui no futuro
         }
     });
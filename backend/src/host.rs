@@ -0,0 +1,708 @@
+use common::*;
+use ipc::{
+    ActReply, Acted, AdminCommand, AdminReply, EventHub, GameEvent, HistoryParams, LoreReply, MapView, MarketEntry,
+    Service, SharedEventHub, SiteLore, TileView, ViewportView, WorldRequest, WorldResponse,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use world::{
+    HistoryQuery, InputOutcome, Replay, Snapshots, Stockpile, World, WorldDiff, WorldStore,
+    TICKS_PER_YEAR,
+};
+
+/// Ticks entre snapshots; entre eles, só o diário cresce
+pub const SNAPSHOT_INTERVAL: u64 = 1000;
+/// Ticks que um gateway sem conexão tem para voltar antes de os seus
+/// jogadores saírem do mundo
+pub const GATEWAY_GRACE: u64 = 30;
+/// Maior raio de visão que um gateway pode pedir
+pub const MAX_VIEW_RADIUS: i32 = 30;
+
+/// Dono do mundo: simula, persiste e atende os pedidos dos gateways
+pub struct Host {
+    pub world: World,
+    store: WorldStore,
+    /// Eventos do mundo, repassados a quem assinar
+    events: SharedEventHub,
+    /// Personagens dos jogadores, com o gateway que os criou
+    players: HashMap<u32, u64>,
+    /// Gateways sem conexão, com os ticks que ainda têm para voltar
+    detached: HashMap<u64, u64>,
+    /// Ações dos jogadores desde o início do processo, para reproduzir
+    /// bugs (`AdminCommand::Replay`)
+    replay: Replay,
+    /// Snapshots em memória capturados pelo painel de admin
+    snapshots: Snapshots,
+}
+
+pub type SharedHost = Arc<Mutex<Host>>;
+
+/// Consulta à história pedida pelo painel de admin
+pub fn history_query(params: &HistoryParams) -> HistoryQuery {
+    let near = match (params.x, params.y) {
+        (Some(x), Some(y)) => Some((Position::new(x, y), params.radius.unwrap_or(0))),
+        _ => None,
+    };
+    HistoryQuery {
+        participant: params.participant,
+        faction: params.faction,
+        near,
+        event_type: params.event_type,
+        from_tick: params.from_tick,
+        to_tick: params.to_tick,
+        offset: params.offset.unwrap_or(0),
+        limit: params.limit,
+    }
+}
+
+fn describe_goods(goods: &Stockpile) -> String {
+    let parts: Vec<String> = Resource::ALL
+        .into_iter()
+        .filter(|r| goods.get(*r) > 0)
+        .map(|r| format!("{} {}", goods.get(r), r.name()))
+        .collect();
+    if parts.is_empty() {
+        "nothing".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+impl Host {
+    /// Assume o mundo; o diário dele deve estar ligado a `store`
+    pub fn new(mut world: World, store: WorldStore) -> Self {
+        let events: SharedEventHub = Arc::new(Mutex::new(EventHub::new()));
+        // Mortes, caravanas e história do mundo vão para quem assinar
+        let hub = events.clone();
+        world.forward_events_to(move |event| hub.lock().unwrap().broadcast(event));
+        Self {
+            replay: Replay::from_world(&world),
+            world,
+            store,
+            events,
+            players: HashMap::new(),
+            detached: HashMap::new(),
+            snapshots: Snapshots::default(),
+        }
+    }
+
+    /// Avança o mundo um tick. O diário vai ao disco a cada tick; o
+    /// snapshot, de tempos em tempos.
+    pub fn tick(&mut self) {
+        self.world.tick();
+        self.drop_lost_gateways();
+        let tick = self.world.get_current_tick();
+        let persisted = if tick.is_multiple_of(SNAPSHOT_INTERVAL) {
+            self.store.compact(&mut self.world)
+        } else {
+            self.world.commit_journal()
+        };
+        if let Err(e) = persisted {
+            eprintln!("⚠️  {}", e);
+        }
+        self.events.lock().unwrap().broadcast(GameEvent::WorldTick {
+            tick,
+            active_players: self.players.len(),
+            total_entities: self.world.entity_count(),
+        });
+    }
+
+    /// Os jogadores de gateways que não voltaram a tempo saem do mundo
+    fn drop_lost_gateways(&mut self) {
+        let mut lost = Vec::new();
+        for (gateway, left) in self.detached.iter_mut() {
+            *left = left.saturating_sub(1);
+            if *left == 0 {
+                lost.push(*gateway);
+            }
+        }
+        lost.sort_unstable();
+        for gateway in lost {
+            self.detached.remove(&gateway);
+            let mut gone: Vec<u32> = self
+                .players
+                .iter()
+                .filter(|(_, g)| **g == gateway)
+                .map(|(id, _)| *id)
+                .collect();
+            gone.sort_unstable();
+            for entity_id in gone {
+                self.players.remove(&entity_id);
+                self.world.despawn_entity(entity_id);
+            }
+        }
+    }
+
+    /// Leva ao disco as mutações feitas pelos jogadores até aqui
+    fn persist(&mut self) {
+        if let Err(e) = self.world.commit_journal() {
+            eprintln!("⚠️  {}", e);
+        }
+    }
+
+    /// Salva o mundo. Sessões não sobrevivem ao reinício, então os
+    /// personagens dos jogadores saem do mundo antes.
+    pub fn save(&mut self) -> Result<(), String> {
+        for (entity_id, _) in self.players.drain() {
+            self.world.despawn_entity(entity_id);
+        }
+        self.store.compact(&mut self.world)
+    }
+
+    fn spawn_player(&mut self, gateway: u64, name: String) -> WorldResponse {
+        let entity = self
+            .world
+            .spawn_player(name.clone())
+            .and_then(|id| self.world.get_entity(id).cloned());
+        if let Some(entity) = &entity {
+            let tick = self.world.get_current_tick();
+            self.replay
+                .record(tick, entity.id, &ClientMessage::Login { player_name: name });
+            self.players.insert(entity.id, gateway);
+            self.persist();
+        }
+        WorldResponse::Spawned { entity }
+    }
+
+    /// Gateways só agem pelos jogadores que eles mesmos criaram
+    fn check_player(&self, gateway: u64, entity_id: u32) -> Result<(), String> {
+        match self.players.get(&entity_id) {
+            Some(owner) if *owner == gateway => Ok(()),
+            _ => Err(format!("A entidade {} não é um jogador deste gateway", entity_id)),
+        }
+    }
+
+    fn act(&mut self, gateway: u64, entity_id: u32, message: &ClientMessage) -> Result<WorldResponse, String> {
+        self.check_player(gateway, entity_id)?;
+        let position = |world: &World| world.get_entity(entity_id).map(|e| e.pos).ok_or("Unknown entity");
+        let from = position(&self.world)?;
+
+        let tick = self.world.get_current_tick();
+        self.replay.record(tick, entity_id, message);
+        let reply = match message {
+            ClientMessage::ReadHistory => Some(ActReply::Lore(self.read_lore(entity_id))),
+            ClientMessage::Move { .. } => {
+                let _ = self.world.apply_input(entity_id, message);
+                None
+            }
+            _ => self.act_on_caravan(entity_id, message).map(ActReply::Message),
+        };
+        self.persist();
+        Ok(WorldResponse::Acted(Acted {
+            from,
+            to: position(&self.world)?,
+            reply,
+        }))
+    }
+
+    /// Ações do jogador sobre a caravana mais próxima
+    pub fn act_on_caravan(&mut self, entity_id: u32, message: &ClientMessage) -> Option<ServerMessage> {
+        if !matches!(
+            message,
+            ClientMessage::EscortCaravan | ClientMessage::RobCaravan | ClientMessage::TradeWithCaravan { .. }
+        ) {
+            return None;
+        }
+        let result = match (self.world.apply_input(entity_id, message), message) {
+            (Ok(InputOutcome::Escorting { .. }), _) => Ok("You join the caravan as its escort".to_string()),
+            (Ok(InputOutcome::Robbed { loot, .. }), _) => {
+                Ok(format!("You rob the caravan of {}", describe_goods(&loot)))
+            }
+            (Ok(InputOutcome::Traded { paid, .. }), &ClientMessage::TradeWithCaravan { give, take, amount }) => Ok(
+                format!("You trade {} {} for {} {}", paid, give.name(), amount, take.name()),
+            ),
+            // Uma ação que não é sobre caravanas não chega aqui, mas um
+            // gateway não derruba o backend se chegar
+            (Ok(_), _) => Err("Nothing happens".to_string()),
+            (Err(e), _) => Err(e),
+        };
+        Some(match result {
+            Ok(message) => ServerMessage::ActionResult { success: true, message },
+            Err(message) => ServerMessage::ActionResult {
+                success: false,
+                message,
+            },
+        })
+    }
+
+    /// História do local onde a entidade está, ano a ano
+    pub fn read_lore(&self, entity_id: u32) -> LoreReply {
+        let lore = self
+            .world
+            .get_entity(entity_id)
+            .and_then(|entity| self.world.site_at(entity.pos))
+            .map(|site| SiteLore {
+                site: site.name.clone(),
+                state: site.state,
+                entries: self
+                    .world
+                    .site_history(site.id)
+                    .iter()
+                    .map(|e| format!("Year {}: {}", e.tick / TICKS_PER_YEAR, e.description))
+                    .collect(),
+            });
+        LoreReply { lore }
+    }
+
+    fn tile_view(&self, pos: Position) -> Option<TileView> {
+        Some(TileView {
+            pos,
+            tile: self.world.get_tile(pos)?.clone(),
+            construction: self.world.get_construction(pos).cloned(),
+            surface: self.world.get_surface(pos).map(str::to_string),
+            items: self.world.get_items(pos).to_vec(),
+        })
+    }
+
+    /// Camadas ao redor de uma entidade, num raio de até `MAX_VIEW_RADIUS`
+    pub fn viewport(&self, entity_id: u32, radius: i32) -> Option<ViewportView> {
+        let radius = radius.clamp(0, MAX_VIEW_RADIUS);
+        let center = self.world.get_entity(entity_id)?.pos;
+        let tiles = ((center.y - radius)..=(center.y + radius))
+            .flat_map(|y| ((center.x - radius)..=(center.x + radius)).map(move |x| Position::new(x, y)))
+            .filter_map(|pos| self.tile_view(pos))
+            .collect();
+        Some(ViewportView {
+            tick: self.world.get_current_tick(),
+            center,
+            radius,
+            tiles,
+            entities: self
+                .world
+                .get_entities_in_region(center, radius)
+                .into_iter()
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Replay de tudo o que os jogadores fizeram, com o estado atual como
+    /// checkpoint final
+    pub fn export_replay(&self) -> Replay {
+        let mut replay = self.replay.clone();
+        replay.checkpoint(&self.world);
+        replay
+    }
+
+    /// O que mudou desde o snapshot `from` até o snapshot `to` (ou até agora)
+    pub fn diff_snapshots(&self, from: u64, to: Option<u64>) -> Result<WorldDiff, String> {
+        let missing = |tick: u64| format!("Não há snapshot do tick {}", tick);
+        let from = self.snapshots.get(from).ok_or_else(|| missing(from))?;
+        Ok(match to {
+            Some(to) => from.diff(self.snapshots.get(to).ok_or_else(|| missing(to))?),
+            None => from.diff(&self.world.to_save()),
+        })
+    }
+
+    /// Volta o mundo ao snapshot do tick dado. Os jogadores conectados
+    /// ficam onde estão; o novo estado vai para o disco na hora.
+    pub fn rollback(&mut self, tick: u64) -> Result<(), String> {
+        let save = self
+            .snapshots
+            .get(tick)
+            .ok_or_else(|| format!("Não há snapshot do tick {}", tick))?;
+        let mut connected: Vec<u32> = self.players.keys().copied().collect();
+        connected.sort_unstable();
+        self.world.restore(save, &connected)?;
+        self.store.compact(&mut self.world)?;
+        // Ações gravadas antes do salto não se aplicam mais ao mundo
+        self.replay = Replay::from_world(&self.world);
+        Ok(())
+    }
+
+    fn admin(&mut self, command: AdminCommand) -> WorldResponse {
+        let reply = match command {
+            AdminCommand::Sites => AdminReply::Sites(self.world.get_sites().into_iter().cloned().collect()),
+            AdminCommand::Market { site_id } => {
+                let market: Vec<MarketEntry> = self
+                    .world
+                    .get_site(site_id)
+                    .map(|site| Resource::ALL.into_iter().map(|r| MarketEntry::new(site, r)).collect())
+                    .unwrap_or_default();
+                AdminReply::Market(market)
+            }
+            AdminCommand::History(params) => AdminReply::History(self.world.query_history(&history_query(&params))),
+            AdminCommand::Replay => AdminReply::Replay(Box::new(self.export_replay())),
+            AdminCommand::Systems => AdminReply::Systems(self.world.system_timings().to_vec()),
+            AdminCommand::Snapshots => AdminReply::Snapshots(self.snapshots.list()),
+            AdminCommand::CaptureSnapshot => {
+                let tick = self.snapshots.capture(&self.world);
+                AdminReply::Snapshot(self.snapshots.list().into_iter().find(|s| s.tick == tick))
+            }
+            AdminCommand::SnapshotDiff { from, to } => match self.diff_snapshots(from, to) {
+                Ok(diff) => AdminReply::Diff(diff),
+                Err(e) => return WorldResponse::Error(e),
+            },
+            AdminCommand::Rollback { tick } => {
+                return match self.rollback(tick) {
+                    Ok(()) => WorldResponse::Done,
+                    Err(e) => WorldResponse::Error(e),
+                }
+            }
+        };
+        WorldResponse::Admin(reply)
+    }
+}
+
+/// Pedidos dos gateways, atendidos por `ipc::serve`
+impl Service for Host {
+    fn handle(&mut self, gateway: u64, request: WorldRequest) -> WorldResponse {
+        match request {
+            WorldRequest::Hello => WorldResponse::Welcome {
+                raws: Box::new(self.world.raws().clone()),
+            },
+            WorldRequest::SpawnPlayer { name } => self.spawn_player(gateway, name),
+            WorldRequest::DespawnPlayer { entity_id } => {
+                if let Err(e) = self.check_player(gateway, entity_id) {
+                    return WorldResponse::Error(e);
+                }
+                self.players.remove(&entity_id);
+                self.world.despawn_entity(entity_id);
+                self.persist();
+                WorldResponse::Done
+            }
+            WorldRequest::Act { entity_id, message } => match self.act(gateway, entity_id, &message) {
+                Ok(response) => response,
+                Err(e) => WorldResponse::Error(e),
+            },
+            WorldRequest::Viewport { entity_id, radius } => match self.check_player(gateway, entity_id) {
+                Ok(()) => WorldResponse::Viewport(self.viewport(entity_id, radius)),
+                Err(e) => WorldResponse::Error(e),
+            },
+            // Ficam de fora as entidades que não são jogadores deste gateway
+            WorldRequest::Viewports { entity_ids, radius } => WorldResponse::Viewports(
+                entity_ids
+                    .into_iter()
+                    .filter(|id| self.check_player(gateway, *id).is_ok())
+                    .filter_map(|id| Some((id, self.viewport(id, radius)?)))
+                    .collect(),
+            ),
+            WorldRequest::Map => {
+                let (width, height) = self.world.dimensions();
+                let tiles = (0..height)
+                    .flat_map(|y| (0..width).map(move |x| Position::new(x, y)))
+                    .filter_map(|pos| self.tile_view(pos))
+                    .collect();
+                WorldResponse::Map(MapView { width, height, tiles })
+            }
+            WorldRequest::Entities => {
+                let mut entities: Vec<Entity> = self.world.get_all_entities().into_iter().cloned().collect();
+                entities.sort_by_key(|e| e.id);
+                WorldResponse::Entities(entities)
+            }
+            WorldRequest::Admin(command) => self.admin(command),
+            // `ipc::serve` apresenta os gateways e abre os fluxos de
+            // eventos sem passar por aqui
+            WorldRequest::Attach { .. } | WorldRequest::Subscribe => {
+                WorldResponse::Error("Pedido atendido pela conexão".to_string())
+            }
+        }
+    }
+
+    fn events(&self) -> broadcast::Receiver<GameEvent> {
+        self.events.lock().unwrap().subscribe()
+    }
+
+    fn attach(&mut self, gateway: u64) {
+        self.detached.remove(&gateway);
+    }
+
+    /// Um gateway perdeu a conexão. Os personagens dos seus jogadores
+    /// esperam `GATEWAY_GRACE` ticks por ele antes de sair do mundo.
+    fn detach(&mut self, gateway: u64) {
+        self.detached.insert(gateway, GATEWAY_GRACE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipc::WorldClient;
+    use tokio::net::TcpListener;
+
+    /// Anfitrião com um mundo pequeno, salvo numa pasta temporária
+    fn host(name: &str, world: World) -> (Host, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("mm-host-{}-{}", name, std::process::id()));
+        let store = WorldStore::new(dir.join("world.json"));
+        let mut world = world;
+        store.compact(&mut world).unwrap();
+        (Host::new(world, store), dir)
+    }
+
+    fn spawn(host: &mut Host, connection: u64, name: &str) -> u32 {
+        match host.handle(connection, WorldRequest::SpawnPlayer { name: name.to_string() }) {
+            WorldResponse::Spawned { entity: Some(entity) } => entity.id,
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_history_query_from_params() {
+        let params = HistoryParams {
+            faction: Some(2),
+            x: Some(10),
+            y: Some(12),
+            radius: Some(4),
+            event_type: Some(EventType::Death),
+            limit: Some(5),
+            ..Default::default()
+        };
+        let query = history_query(&params);
+        assert_eq!(query.faction, Some(2));
+        assert_eq!(query.near, Some((Position::new(10, 12), 4)));
+        assert_eq!(query.event_type, Some(EventType::Death));
+        assert_eq!(query.limit, Some(5));
+        assert_eq!(query.offset, 0);
+    }
+
+    #[test]
+    fn test_read_lore_inside_site() {
+        let mut world = World::new(40, 40);
+        let site = world.found_site(
+            "Ashford".to_string(),
+            world::SiteKind::Town,
+            None,
+            Position::new(10, 10),
+            30,
+        );
+        world.burn_site(site, None);
+        let inside = world
+            .spawn_entity("Hero".to_string(), Position::new(10, 10), EntityType::Player)
+            .unwrap();
+        let outside = world
+            .spawn_entity("Wanderer".to_string(), Position::new(30, 10), EntityType::Player)
+            .unwrap();
+        let (host, dir) = host("lore", world);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let lore = host.read_lore(inside).lore.unwrap();
+        assert_eq!(lore.site, "Ashford");
+        assert_eq!(lore.entries, vec!["Year 0: Ashford burned to the ground".to_string()]);
+        assert!(host.read_lore(outside).lore.is_none());
+    }
+
+    #[test]
+    fn test_players_act_only_through_their_gateway() {
+        let (mut host, dir) = host("act", World::new(40, 40));
+        let player = spawn(&mut host, 1, "Hero");
+
+        let robbed = host.handle(
+            1,
+            WorldRequest::Act {
+                entity_id: player,
+                message: ClientMessage::RobCaravan,
+            },
+        );
+        let WorldResponse::Acted(Acted {
+            reply: Some(ActReply::Message(ServerMessage::ActionResult { success, message })),
+            ..
+        }) = robbed
+        else {
+            panic!("unexpected response: {:?}", robbed);
+        };
+        assert_eq!((success, message.as_str()), (false, "There is no caravan nearby"));
+
+        let stolen = host.handle(
+            2,
+            WorldRequest::Act {
+                entity_id: player,
+                message: ClientMessage::Move { dx: 1, dy: 0 },
+            },
+        );
+        assert!(matches!(stolen, WorldResponse::Error(_)));
+
+        // O gateway caiu e voltou a tempo: o jogador continua no mundo
+        host.detach(1);
+        for _ in 1..GATEWAY_GRACE {
+            host.tick();
+        }
+        host.attach(1);
+        host.tick();
+        assert!(host.world.get_entity(player).is_some());
+
+        // Caiu e não voltou: o jogador sai
+        host.detach(1);
+        for _ in 0..GATEWAY_GRACE {
+            host.tick();
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(host.world.get_entity(player).is_none());
+    }
+
+    #[test]
+    fn test_exported_replay_reproduces_players() {
+        let (mut host, dir) = host("replay", World::new(30, 30));
+        let player = spawn(&mut host, 1, "Hero");
+        for message in [
+            ClientMessage::Move { dx: 1, dy: 0 },
+            ClientMessage::Move { dx: 0, dy: 1 },
+        ] {
+            host.handle(
+                1,
+                WorldRequest::Act {
+                    entity_id: player,
+                    message,
+                },
+            );
+            host.tick();
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let replayed = host.export_replay().run().unwrap();
+        assert_eq!(
+            replayed.get_entity(player).map(|e| e.pos),
+            host.world.get_entity(player).map(|e| e.pos)
+        );
+        assert_eq!(replayed.state_hash(), host.world.state_hash());
+    }
+
+    #[test]
+    fn test_rollback_keeps_connected_players() {
+        let (mut host, dir) = host("rollback", World::new(30, 30));
+        let tick = host.snapshots.capture(&host.world);
+
+        let player = spawn(&mut host, 1, "Hero");
+        for _ in 0..10 {
+            host.tick();
+        }
+        assert_eq!(host.diff_snapshots(tick, None).unwrap().spawned.len(), 1);
+        assert!(host.diff_snapshots(tick + 1, None).is_err());
+
+        host.rollback(tick).unwrap();
+        let recovered = WorldStore::new(dir.join("world.json")).recover().unwrap().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(host.world.get_current_tick(), tick);
+        assert!(host.world.get_entity(player).is_some());
+        assert_eq!(recovered.state_hash(), host.world.state_hash());
+    }
+
+    #[test]
+    fn test_save_leaves_connected_players_out() {
+        let (mut host, dir) = host("save", World::new(30, 30));
+        let player = spawn(&mut host, 1, "Hero");
+
+        host.save().unwrap();
+        let loaded = WorldStore::new(dir.join("world.json")).recover().unwrap().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(host.players.is_empty());
+        assert!(loaded.get_entity(player).is_none());
+        assert_eq!(loaded.entity_count(), host.world.entity_count());
+    }
+
+    #[tokio::test]
+    async fn test_gateway_drives_the_world_over_tcp() {
+        let (host, dir) = host("tcp", World::new(30, 30));
+        let host: SharedHost = Arc::new(Mutex::new(host));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(ipc::serve(listener, host.clone()));
+
+        let mut events = WorldClient::connect(&addr).await.unwrap().subscribe().await.unwrap();
+        let gateway = WorldClient::connect(&addr).await.unwrap();
+        let hero = gateway.spawn_player("Hero".to_string()).await.unwrap().unwrap();
+        let moved = gateway
+            .act(hero.id, ClientMessage::Move { dx: 1, dy: 0 })
+            .await
+            .unwrap();
+        assert_eq!((moved.from, moved.to), (hero.pos, hero.pos.moved(1, 0)));
+
+        host.lock().unwrap().tick();
+        assert!(matches!(
+            events.next().await,
+            Some(GameEvent::WorldTick {
+                tick: 1,
+                active_players: 1,
+                ..
+            })
+        ));
+        let viewport = gateway.viewport(hero.id, 2).await.unwrap().unwrap();
+        assert_eq!((viewport.tick, viewport.tiles.len()), (1, 25));
+        assert!(viewport.entities.iter().any(|e| e.id == hero.id));
+        let views = gateway.viewports(vec![hero.id, 9999], 2).await.unwrap();
+        assert_eq!(views, vec![(hero.id, viewport)]);
+        let wide = gateway.viewport(hero.id, 1000).await.unwrap().unwrap();
+        assert_eq!(wide.radius, MAX_VIEW_RADIUS);
+
+        // Outro gateway não enxerga pelos jogadores deste
+        let stranger = WorldClient::connect(&addr).await.unwrap();
+        assert!(matches!(stranger.viewport(hero.id, 2).await, Err(ipc::RpcError::Refused(_))));
+        assert_eq!(stranger.viewports(vec![hero.id], 2).await.unwrap(), vec![]);
+
+        // Comandos de admin chegam tipados ao gateway
+        assert_eq!(&gateway.hello().await.unwrap(), host.lock().unwrap().world.raws());
+        let captured = gateway.capture_snapshot().await.unwrap().unwrap();
+        assert_eq!(gateway.snapshots().await.unwrap(), vec![captured.clone()]);
+        assert_eq!(gateway.snapshot_diff(captured.tick, None).await.unwrap().from_tick, captured.tick);
+        assert_eq!(gateway.rollback(captured.tick).await, Ok(()));
+        assert!(matches!(gateway.rollback(9999).await, Err(ipc::RpcError::Refused(_))));
+
+        // O gateway encerrou: passado o prazo, o personagem sai do mundo
+        drop(gateway);
+        for _ in 0..100 {
+            if !host.lock().unwrap().detached.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        for _ in 0..GATEWAY_GRACE {
+            host.lock().unwrap().tick();
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(host.lock().unwrap().world.get_entity(hero.id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_player_survives_a_dropped_connection() {
+        let (host, dir) = host("blip", World::new(30, 30));
+        let host: SharedHost = Arc::new(Mutex::new(host));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend = listener.local_addr().unwrap();
+        tokio::spawn(ipc::serve(listener, host.clone()));
+
+        // Um repasse entre o gateway e o backend, para derrubar a conexão
+        let relay = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = relay.local_addr().unwrap().to_string();
+        let links: Arc<Mutex<Vec<tokio::task::JoinHandle<()>>>> = Arc::default();
+        let open_links = links.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut inbound, _) = relay.accept().await.unwrap();
+                let mut outbound = tokio::net::TcpStream::connect(backend).await.unwrap();
+                open_links.lock().unwrap().push(tokio::spawn(async move {
+                    let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                }));
+            }
+        });
+
+        let gateway = WorldClient::connect(&addr).await.unwrap();
+        let hero = gateway.spawn_player("Hero".to_string()).await.unwrap().unwrap();
+        for link in links.lock().unwrap().drain(..) {
+            link.abort();
+        }
+        for _ in 0..100 {
+            if !host.lock().unwrap().detached.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        // A próxima chamada reconecta com a mesma identidade
+        let mut moved = None;
+        for _ in 0..10 {
+            if let Ok(acted) = gateway.act(hero.id, ClientMessage::Move { dx: 1, dy: 0 }).await {
+                moved = Some(acted);
+                break;
+            }
+        }
+        assert_eq!(moved.map(|acted| acted.to), Some(hero.pos.moved(1, 0)));
+        for _ in 0..GATEWAY_GRACE {
+            host.lock().unwrap().tick();
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(host.lock().unwrap().world.get_entity(hero.id).is_some());
+        assert!(host.lock().unwrap().detached.is_empty());
+    }
+}
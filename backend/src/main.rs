@@ -1,36 +1,204 @@
-use world::World;
+mod host;
+
+use host::{Host, SharedHost};
+use ipc::WORLD_ADDR;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::net::TcpListener;
+use world::{HistoryGenConfig, ModReport, Raws, Replay, World, WorldStore, MODS_DIR, RAWS_DIR};
+
+/// Dimensões do mundo simulado
+const WORLD_SIZE: (i32, i32) = (100, 100);
+/// Snapshot do mundo entre execuções; o diário fica ao lado (`world.journal`)
+const SAVE_PATH: &str = "saves/world.json";
+
+/// Cria o mundo e executa a fase de geração de história
+fn create_world(seed: u64) -> World {
+    let mut world = World::with_seed(WORLD_SIZE.0, WORLD_SIZE.1, seed);
+    world.generate_history(&HistoryGenConfig::default());
+    world
+}
+
+/// `backend export-legends [arquivo] [semente]`: gera o mundo, exporta
+/// as lendas e sai sem iniciar a simulação
+fn export_legends(args: &[String]) -> Result<(), String> {
+    let path = args.first().map(String::as_str).unwrap_or("legends.json");
+    let seed = match args.get(1) {
+        Some(seed) => seed.parse().map_err(|_| format!("Semente inválida: {}", seed))?,
+        None => world::DEFAULT_SEED,
+    };
+
+    let world = create_world(seed);
+    let json = world.export_legends().to_json().map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("Erro ao escrever {}: {}", path, e))?;
+
+    println!("📜 Lendas exportadas para {} (semente {})", path, seed);
+    println!("   Fações: {} | Eventos: {} | Figuras históricas: {}",
+        world.get_factions().len(),
+        world.get_historical_events(usize::MAX).len(),
+        world.get_historical_figures().len()
+    );
+    Ok(())
+}
+
+/// `backend replay <arquivo> [--every N]`: reexecuta uma sessão gravada
+/// (por exemplo, a de `GET /api/replay` do servidor de jogo) e confere os
+/// checkpoints. Com `--every`, imprime o hash do estado a cada N ticks,
+/// para comparar duas execuções e achar onde divergem.
+fn run_replay(args: &[String]) -> Result<(), String> {
+    let path = args.first().ok_or("Uso: backend replay <arquivo> [--every N]")?;
+    let every = match (args.get(1).map(String::as_str), args.get(2)) {
+        (Some("--every"), Some(n)) => n.parse().ok().filter(|&n: &u64| n > 0).ok_or(format!("Intervalo inválido: {}", n))?,
+        (None, _) => 0,
+        _ => return Err("Uso: backend replay <arquivo> [--every N]".to_string()),
+    };
+
+    let json = std::fs::read_to_string(path).map_err(|e| format!("Erro ao ler {}: {}", path, e))?;
+    let replay = Replay::from_json(&json)?;
+    println!("🔁 Reproduzindo {} ações e {} checkpoints até o tick {}",
+        replay.inputs.len(),
+        replay.checkpoints.len(),
+        replay.end_tick
+    );
+
+    let world = replay.run_observed(|world| {
+        let tick = world.get_current_tick();
+        if every > 0 && tick.is_multiple_of(every) {
+            println!("   tick {:>8}  {:016x}", tick, world.state_hash());
+        }
+    })?;
+    println!("✅ Reprodução idêntica: tick {}, estado {:016x}", world.get_current_tick(), world.state_hash());
+    Ok(())
+}
+
+/// `backend mods`: lista os mods ativos, em ordem de carga, e os
+/// conflitos entre eles
+fn print_mods(report: &ModReport) {
+    if report.order.is_empty() {
+        println!("Nenhum mod em {}/", MODS_DIR);
+    }
+    for (i, m) in report.order.iter().enumerate() {
+        println!("{:>3}. {}", i + 1, m);
+    }
+    if report.conflicts.is_empty() {
+        println!("✅ Nenhum conflito");
+    }
+}
+
+/// Subcomando que roda no lugar da simulação
+type Subcommand = fn(&[String]) -> Result<(), String>;
+
+/// Retoma o mundo salvo (snapshot + diário), se houver; senão gera um
+/// novo. Um save ilegível interrompe a inicialização em vez de ser
+/// sobrescrito. O mundo sai com o diário ligado a um snapshot recente.
+fn load_or_create_world(store: &WorldStore) -> Result<World, String> {
+    let mut world = match store.recover()? {
+        Some(world) => {
+            println!("💾 Mundo carregado de {} (tick {})", SAVE_PATH, world.get_current_tick());
+            world
+        }
+        None => {
+            println!("📜 Gerando {} anos de história...", HistoryGenConfig::default().years);
+            create_world(world::DEFAULT_SEED)
+        }
+    };
+    store.compact(&mut world)?;
+    Ok(world)
+}
 
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    // Terrenos, criaturas, itens e povos vêm dos raws, com os mods por cima
+    let report = match Raws::load_with_mods(RAWS_DIR, MODS_DIR) {
+        Ok((raws, report)) => {
+            Raws::install(raws);
+            report
+        }
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
+    for conflict in &report.conflicts {
+        println!("⚠️  {}", conflict);
+    }
+    let command: Option<Subcommand> = match args.get(1).map(String::as_str) {
+        Some("export-legends") => Some(export_legends),
+        Some("replay") => Some(run_replay),
+        Some("mods") => {
+            print_mods(&report);
+            return;
+        }
+        _ => None,
+    };
+    if let Some(command) = command {
+        if let Err(e) = command(&args[2..]) {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     println!("🌍 MM World Simulator Starting...");
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!();
-    println!("Este processo simula o mundo continuamente:");
+    println!("Este processo é o dono do mundo e o simula continuamente:");
     println!("  • NPCs com IA");
-    println!("  • Eventos aleatórios");
     println!("  • Crescimento de facções");
     println!("  • História emergente");
     println!("  • Mundo persiste mesmo sem jogadores");
+    println!("  • Jogadores chegam pelo game server, via {}", WORLD_ADDR);
     println!();
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!();
 
-    let mut world = World::new(100, 100);
+    let store = WorldStore::new(SAVE_PATH);
+    let world = match load_or_create_world(&store) {
+        Ok(world) => world,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
+    let listener = match TcpListener::bind(WORLD_ADDR).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("❌ Não foi possível atender em {}: {}", WORLD_ADDR, e);
+            std::process::exit(1);
+        }
+    };
     let mut tick_counter = 0u64;
 
-    println!("✅ Mundo criado: 100x100 tiles");
+    println!("✅ Mundo criado: {}x{} tiles", WORLD_SIZE.0, WORLD_SIZE.1);
+    println!("   Fações: {} | Eventos: {} | Figuras históricas: {}",
+        world.get_factions().len(),
+        world.get_historical_events(usize::MAX).len(),
+        world.get_historical_figures().len()
+    );
+    println!("🔌 Atendendo gateways em {}", WORLD_ADDR);
     println!("🎯 Iniciando loop de simulação (1 tick/segundo)...");
     println!();
 
+    let host: SharedHost = Arc::new(Mutex::new(Host::new(world, store)));
+    tokio::spawn(ipc::serve(listener, host.clone()));
+
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
+
     loop {
-        tokio::time::sleep(Duration::from_secs(1)).await;
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+        }
         
-        world.tick();
+        let mut host = host.lock().unwrap();
+        host.tick();
+        let world = &host.world;
         tick_counter += 1;
 
         // Log a cada 10 ticks
-        if tick_counter % 10 == 0 {
+        if tick_counter.is_multiple_of(10) {
             println!("🔄 Tick #{:4} | Entidades: {:3}", 
                 tick_counter,
                 world.entity_count()
@@ -38,7 +206,7 @@ async fn main() {
         }
 
         // Eventos históricos a cada 100 ticks
-        if tick_counter % 100 == 0 {
+        if tick_counter.is_multiple_of(100) {
             let events = world.get_historical_events(5);
             if !events.is_empty() {
                 println!("📜 Últimos eventos:");
@@ -49,7 +217,7 @@ async fn main() {
         }
 
         // Status detalhado a cada 1000 ticks (~16 minutos)
-        if tick_counter % 1000 == 0 {
+        if tick_counter.is_multiple_of(1000) {
             println!();
             println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
             println!("📊 STATUS DO MUNDO (Tick {})", tick_counter);
@@ -58,15 +226,37 @@ async fn main() {
             println!("   Dimensões: {}x{}", w, h);
             println!("   Entidades ativas: {}", world.entity_count());
             println!("   Eventos registrados: {}", world.get_historical_events(99999).len());
+            println!("   Sistemas (média / pior, µs):");
+            for timing in world.system_timings() {
+                println!("     {:<10} {:?}: {:>6} / {:>6} em {} execuções",
+                    timing.name,
+                    timing.phase,
+                    timing.average_us(),
+                    timing.max_us,
+                    timing.runs
+                );
+            }
             println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
             println!();
         }
     }
+
+    println!();
+    println!("🛑 Encerrando: salvando o mundo em {}...", SAVE_PATH);
+    let mut host = host.lock().unwrap();
+    match host.save() {
+        Ok(()) => println!("💾 Mundo salvo (tick {})", host.world.get_current_tick()),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use common::{EntityType, Position};
 
     #[test]
     fn test_world_integration() {
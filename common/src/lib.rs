@@ -28,25 +28,146 @@ pub struct Tile {
     pub walkable: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
-pub enum TerrainType {
-    Grass,
-    Water,
-    Stone,
-    Sand,
+/// Terreno de um tile: o ID de uma definição de terreno dos raws. O motor
+/// só usa diretamente os terrenos das constantes; os raws podem
+/// acrescentar outros.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct TerrainType(String);
+
+impl TerrainType {
+    pub const GRASS: &'static str = "grass";
+    pub const WATER: &'static str = "water";
+    pub const STONE: &'static str = "stone";
+    pub const ROAD: &'static str = "road";
+
+    pub fn new(id: &str) -> Self {
+        Self(id.to_string())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<&str> for TerrainType {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
 }
 
 impl Tile {
-    pub fn new(terrain: TerrainType, walkable: bool) -> Self {
-        Self { terrain, walkable }
+    pub fn new(terrain: &str, walkable: bool) -> Self {
+        Self {
+            terrain: TerrainType::new(terrain),
+            walkable,
+        }
     }
 
     pub fn grass() -> Self {
-        Self::new(TerrainType::Grass, true)
+        Self::new(TerrainType::GRASS, true)
     }
 
     pub fn water() -> Self {
-        Self::new(TerrainType::Water, false)
+        Self::new(TerrainType::WATER, false)
+    }
+
+    pub fn road() -> Self {
+        Self::new(TerrainType::ROAD, true)
+    }
+}
+
+/// Representa uma construção sobre o terreno (camada de construções)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Construction {
+    pub kind: ConstructionType,
+    pub walkable: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ConstructionType {
+    Wall,
+    Door,
+    Floor,
+    Ruin,
+}
+
+impl Construction {
+    pub fn new(kind: ConstructionType, walkable: bool) -> Self {
+        Self { kind, walkable }
+    }
+
+    pub fn wall() -> Self {
+        Self::new(ConstructionType::Wall, false)
+    }
+
+    pub fn door() -> Self {
+        Self::new(ConstructionType::Door, true)
+    }
+
+    pub fn floor() -> Self {
+        Self::new(ConstructionType::Floor, true)
+    }
+
+    pub fn ruin() -> Self {
+        Self::new(ConstructionType::Ruin, true)
+    }
+}
+
+/// Objeto largado no mundo (camada de itens)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Item {
+    pub id: u32,
+    pub name: String,
+    pub kind: ItemKind,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ItemKind {
+    Food,
+    Tool,
+    Weapon,
+    Treasure,
+    /// Restos mortais de uma entidade
+    Corpse { of: u32 },
+}
+
+impl Item {
+    pub fn new(id: u32, name: String, kind: ItemKind) -> Self {
+        Self { id, name, kind }
+    }
+}
+
+/// Recursos da economia dos assentamentos
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Resource {
+    Food,
+    Wood,
+    Stone,
+    Metal,
+}
+
+impl Resource {
+    /// Todos os recursos, em ordem de urgência
+    pub const ALL: [Resource; 4] = [Resource::Food, Resource::Wood, Resource::Metal, Resource::Stone];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Resource::Food => "food",
+            Resource::Wood => "wood",
+            Resource::Stone => "stone",
+            Resource::Metal => "metal",
+        }
+    }
+
+    /// Preço de referência quando oferta e demanda se equilibram
+    pub fn base_price(self) -> u32 {
+        match self {
+            Resource::Food => 2,
+            Resource::Wood => 3,
+            Resource::Stone => 4,
+            Resource::Metal => 10,
+        }
     }
 }
 
@@ -57,6 +178,9 @@ pub struct Entity {
     pub name: String,
     pub pos: Position,
     pub entity_type: EntityType,
+    /// Criatura nos raws do mundo; sem ela, vale a padrão do tipo
+    #[serde(default)]
+    pub creature: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
@@ -73,6 +197,7 @@ impl Entity {
             name,
             pos,
             entity_type,
+            creature: None,
         }
     }
 }
@@ -88,13 +213,25 @@ pub struct Faction {
     pub relations: HashMap<u32, Relation>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
-pub enum FactionType {
-    Human,
-    Dwarf,
-    Elf,
-    Goblin,
-    Wildlife,
+/// Povo de uma fação: o ID de uma definição de fação dos raws
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct FactionType(String);
+
+impl FactionType {
+    pub fn new(id: &str) -> Self {
+        Self(id.to_string())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<&str> for FactionType {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
@@ -144,11 +281,14 @@ pub struct HistoricalEvent {
     pub tick: u64,
     pub event_type: EventType,
     pub participants: Vec<u32>,
+    /// Fações envolvidas no evento
+    #[serde(default)]
+    pub factions: Vec<u32>,
     pub location: Position,
     pub description: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EventType {
     Birth,
     Death,
@@ -157,6 +297,28 @@ pub enum EventType {
     TerritoryConquered,
     Alliance,
     War,
+    Theft,
+    Migration,
+    Peace,
+    Succession,
+    Abandonment,
+    SiteBurned,
+    SiteRuined,
+    Raid,
+    Ambush,
+    RoadBuilt,
+}
+
+/// Figura histórica: personagem nomeado que a história lembra,
+/// mesmo depois de morto
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoricalFigure {
+    pub id: u32,
+    pub name: String,
+    pub entity_type: EntityType,
+    pub faction_id: Option<u32>,
+    pub birth_tick: u64,
+    pub death_tick: Option<u64>,
 }
 
 /// Mensagens do servidor para o cliente
@@ -178,6 +340,63 @@ pub enum ClientMessage {
     Move { dx: i32, dy: i32 },
     /// Login inicial
     Login { player_name: String },
+    /// Ler a história do local onde o jogador está
+    ReadHistory,
+    /// Escoltar a caravana mais próxima
+    EscortCaravan,
+    /// Roubar a carga da caravana mais próxima
+    RobCaravan,
+    /// Trocar com a caravana mais próxima: paga em `give`, leva `amount` de `take`
+    TradeWithCaravan { give: Resource, take: Resource, amount: u32 },
+}
+
+/// Eventos que acontecem no jogo, repassados ao painel de admin
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum GameEvent {
+    PlayerConnected { 
+        name: String, 
+        id: String,
+    },
+    PlayerDisconnected { 
+        name: String, 
+        id: String,
+    },
+    PlayerMoved { 
+        name: String, 
+        from: Position, 
+        to: Position,
+    },
+    PlayerSpawned {
+        name: String,
+        pos: Position,
+    },
+    WorldTick { 
+        tick: u64,
+        active_players: usize,
+        total_entities: usize,
+    },
+    /// Criatura ou NPC que surgiu no mundo
+    EntitySpawned {
+        name: String,
+        pos: Position,
+    },
+    EntityDied {
+        name: String,
+        pos: Position,
+    },
+    CaravanDeparted {
+        origin: String,
+        destination: String,
+    },
+    CaravanArrived {
+        origin: String,
+        destination: String,
+    },
+    /// Acontecimento que entrou para a história do mundo
+    HistoryRecorded {
+        year: u64,
+        description: String,
+    },
 }
 
 /// Sistema de Autenticação
@@ -246,11 +465,18 @@ mod tests {
     fn test_tile_creation() {
         let grass = Tile::grass();
         assert!(grass.walkable);
-        assert_eq!(grass.terrain, TerrainType::Grass);
+        assert_eq!(grass.terrain, TerrainType::GRASS);
 
         let water = Tile::water();
         assert!(!water.walkable);
-        assert_eq!(water.terrain, TerrainType::Water);
+        assert_eq!(water.terrain, TerrainType::WATER);
+    }
+
+    #[test]
+    fn test_construction_creation() {
+        assert!(!Construction::wall().walkable);
+        assert!(Construction::door().walkable);
+        assert_eq!(Construction::ruin().kind, ConstructionType::Ruin);
     }
 
     #[test]
@@ -1,34 +1,15 @@
-use serde::{Deserialize, Serialize};
+pub mod protocol;
+pub mod rpc;
+
 use tokio::sync::broadcast;
 use std::sync::{Arc, Mutex};
-use common::Position;
 
-/// Eventos que acontecem no jogo
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub enum GameEvent {
-    PlayerConnected { 
-        name: String, 
-        id: String,
-    },
-    PlayerDisconnected { 
-        name: String, 
-        id: String,
-    },
-    PlayerMoved { 
-        name: String, 
-        from: Position, 
-        to: Position,
-    },
-    PlayerSpawned {
-        name: String,
-        pos: Position,
-    },
-    WorldTick { 
-        tick: u64,
-        active_players: usize,
-        total_entities: usize,
-    },
-}
+pub use common::GameEvent;
+pub use protocol::{
+    ActReply, Acted, AdminCommand, AdminReply, Frame, HistoryParams, LoreReply, MapView, MarketEntry, SiteLore,
+    TileView, ViewportView, WorldRequest, WorldResponse,
+};
+pub use rpc::{serve, EventStream, RpcError, Service, WorldClient, CALL_TIMEOUT, WORLD_ADDR};
 
 /// Hub central de eventos
 pub struct EventHub {
@@ -50,6 +31,12 @@ impl EventHub {
     }
 }
 
+impl Default for EventHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub type SharedEventHub = Arc<Mutex<EventHub>>;
 
 #[cfg(test)]
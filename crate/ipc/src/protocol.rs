@@ -0,0 +1,232 @@
+use crate::GameEvent;
+use common::*;
+use serde::{Deserialize, Serialize};
+use world::{HistoryPage, Raws, Replay, Site, SiteState, SnapshotInfo, SystemTiming, WorldDiff};
+
+/// Pedido de um gateway (o servidor de jogo) ao backend, dono do mundo
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum WorldRequest {
+    /// Primeiro pedido de cada conexão, enviado pelo `WorldClient`. Um
+    /// gateway que reconecta apresenta a identidade que já tinha, com o
+    /// segredo recebido junto com ela, e segue dono dos seus jogadores; uma
+    /// identidade desconhecida, ou com o segredo errado, é trocada por uma
+    /// nova.
+    Attach {
+        gateway: Option<u64>,
+        token: Option<String>,
+    },
+    /// Apresentação do gateway; o backend responde com os raws em uso
+    Hello,
+    SpawnPlayer {
+        name: String,
+    },
+    /// O jogador saiu: o personagem deixa o mundo
+    DespawnPlayer {
+        entity_id: u32,
+    },
+    /// Ação de um jogador sobre o mundo
+    Act {
+        entity_id: u32,
+        message: ClientMessage,
+    },
+    /// Camadas ao redor de uma entidade
+    Viewport {
+        entity_id: u32,
+        radius: i32,
+    },
+    /// Camadas ao redor de várias entidades de uma vez, para atualizar
+    /// todos os jogadores a cada tick; as que não existem ficam de fora
+    Viewports {
+        entity_ids: Vec<u32>,
+        radius: i32,
+    },
+    /// Camadas de todos os tiles do mundo
+    Map,
+    Entities,
+    Admin(AdminCommand),
+    /// Abre um fluxo com os eventos do mundo, que chegam como
+    /// `Frame::Event` com o id deste pedido até o fluxo ser cancelado
+    Subscribe,
+}
+
+/// Consultas e comandos do painel de admin
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum AdminCommand {
+    Sites,
+    Market {
+        site_id: u32,
+    },
+    History(HistoryParams),
+    /// Sessão gravada, para reproduzir com `backend replay`
+    Replay,
+    Systems,
+    Snapshots,
+    CaptureSnapshot,
+    /// Diferenças entre dois snapshots, ou entre um e o mundo atual
+    SnapshotDiff {
+        from: u64,
+        to: Option<u64>,
+    },
+    Rollback {
+        tick: u64,
+    },
+}
+
+/// Filtros de uma consulta à história. Ausentes não restringem.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct HistoryParams {
+    pub participant: Option<u32>,
+    pub faction: Option<u32>,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub radius: Option<i32>,
+    pub event_type: Option<EventType>,
+    pub from_tick: Option<u64>,
+    pub to_tick: Option<u64>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// Resposta do backend a um `WorldRequest`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum WorldResponse {
+    /// Identidade do gateway nesta conexão e o segredo para voltar a ela
+    Attached {
+        gateway: u64,
+        token: String,
+    },
+    /// Raws em uso pelo mundo, para o gateway desenhar as camadas
+    Welcome {
+        raws: Box<Raws>,
+    },
+    /// Personagem criado; `None` quando não há onde pô-lo
+    Spawned {
+        entity: Option<Entity>,
+    },
+    Acted(Acted),
+    Viewport(Option<ViewportView>),
+    Viewports(Vec<(u32, ViewportView)>),
+    Map(MapView),
+    Entities(Vec<Entity>),
+    /// Resultado de um comando de admin
+    Admin(AdminReply),
+    Done,
+    /// O pedido foi recusado
+    Error(String),
+}
+
+/// Resultado de um `AdminCommand`, um tipo por comando. `Rollback`
+/// responde com `WorldResponse::Done`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum AdminReply {
+    Sites(Vec<Site>),
+    Market(Vec<MarketEntry>),
+    History(HistoryPage),
+    Replay(Box<Replay>),
+    Systems(Vec<SystemTiming>),
+    Snapshots(Vec<SnapshotInfo>),
+    /// O snapshot capturado por `CaptureSnapshot`
+    Snapshot(Option<SnapshotInfo>),
+    Diff(WorldDiff),
+}
+
+/// Linha do mercado de um local em `AdminCommand::Market`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MarketEntry {
+    pub resource: Resource,
+    pub stock: u32,
+    pub demand: u32,
+    pub price: u32,
+}
+
+impl MarketEntry {
+    pub fn new(site: &Site, resource: Resource) -> Self {
+        Self {
+            resource,
+            stock: site.stockpile.get(resource),
+            demand: site.demand(resource),
+            price: site.price(resource),
+        }
+    }
+}
+
+/// Onde a entidade estava e está, e o que responder ao jogador
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Acted {
+    pub from: Position,
+    pub to: Position,
+    pub reply: Option<ActReply>,
+}
+
+/// Resposta a uma ação, repassada ao cliente do jogador
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ActReply {
+    Lore(LoreReply),
+    Message(ServerMessage),
+}
+
+/// Resposta a `ClientMessage::ReadHistory`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LoreReply {
+    /// `None` quando o jogador não está dentro de nenhum local
+    pub lore: Option<SiteLore>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SiteLore {
+    pub site: String,
+    pub state: SiteState,
+    pub entries: Vec<String>,
+}
+
+/// Camadas de todos os tiles do mundo
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MapView {
+    pub width: i32,
+    pub height: i32,
+    pub tiles: Vec<TileView>,
+}
+
+/// As camadas de um tile, sem nada de apresentação
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TileView {
+    pub pos: Position,
+    pub tile: Tile,
+    pub construction: Option<Construction>,
+    pub surface: Option<String>,
+    pub items: Vec<Item>,
+}
+
+/// O que uma entidade vê ao seu redor
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ViewportView {
+    pub tick: u64,
+    pub center: Position,
+    pub radius: i32,
+    pub tiles: Vec<TileView>,
+    pub entities: Vec<Entity>,
+}
+
+/// Unidade de transporte entre processos. Pedidos e respostas levam o
+/// mesmo `id`, escolhido por quem pede; assim várias chamadas e fluxos
+/// dividem uma conexão.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Frame {
+    Request {
+        id: u64,
+        request: WorldRequest,
+    },
+    Response {
+        id: u64,
+        response: WorldResponse,
+    },
+    /// Evento do fluxo aberto pelo pedido `id`
+    Event {
+        id: u64,
+        event: GameEvent,
+    },
+    /// Quem pediu o fluxo `id` não quer mais os eventos
+    Cancel {
+        id: u64,
+    },
+}
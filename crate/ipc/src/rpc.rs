@@ -0,0 +1,748 @@
+use crate::{
+    Acted, AdminCommand, AdminReply, Frame, GameEvent, HistoryParams, MapView, MarketEntry, ViewportView, WorldRequest,
+    WorldResponse,
+};
+use common::{ClientMessage, Entity};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::task::JoinHandle;
+use world::{HistoryPage, Raws, Replay, Site, SnapshotInfo, SystemTiming, WorldDiff};
+
+/// Endereço em que o backend atende os gateways
+pub const WORLD_ADDR: &str = "127.0.0.1:7070";
+/// Quanto uma chamada espera pela resposta
+pub const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+/// Maior quadro aceito; acima disso a conexão é dada como corrompida
+const MAX_FRAME: usize = 64 * 1024 * 1024;
+/// Eventos de um fluxo à espera de quem o lê; além disso, são descartados
+const STREAM_BUFFER: usize = 1000;
+/// Id do `WorldRequest::Attach` de cada conexão; as chamadas começam em 1
+const ATTACH_ID: u64 = 0;
+
+/// Por que uma chamada falhou
+#[derive(Debug, Clone, PartialEq)]
+pub enum RpcError {
+    /// O backend recebeu o pedido e o recusou
+    Refused(String),
+    /// O pedido não teve resposta: conexão caída, tempo esgotado ou
+    /// resposta de outro tipo
+    Transport(String),
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RpcError::Refused(message) | RpcError::Transport(message) => f.write_str(message),
+        }
+    }
+}
+
+fn unexpected(response: WorldResponse) -> RpcError {
+    match response {
+        WorldResponse::Error(message) => RpcError::Refused(message),
+        other => RpcError::Transport(format!("Resposta inesperada do backend: {:?}", other)),
+    }
+}
+
+fn unexpected_admin(reply: AdminReply) -> RpcError {
+    RpcError::Transport(format!("Resposta inesperada do backend: {:?}", reply))
+}
+
+/// Escreve um quadro: o tamanho em 4 bytes (big-endian) seguido do JSON
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, frame: &Frame) -> Result<(), String> {
+    let body = serde_json::to_vec(frame).map_err(|e| e.to_string())?;
+    if body.len() > MAX_FRAME {
+        return Err(format!("Quadro grande demais: {} bytes", body.len()));
+    }
+    let mut bytes = Vec::with_capacity(4 + body.len());
+    bytes.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&body);
+    writer
+        .write_all(&bytes)
+        .await
+        .map_err(|e| format!("Erro ao enviar: {}", e))
+}
+
+/// Lê o próximo quadro; `None` quando a outra ponta fecha a conexão
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<Frame>, String> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(format!("Erro ao receber: {}", e)),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME {
+        return Err(format!("Quadro grande demais: {} bytes", len));
+    }
+    let mut body = vec![0; len];
+    reader
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| format!("Erro ao receber: {}", e))?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| format!("Quadro inválido: {}", e))
+}
+
+/// Envia a uma tarefa própria tudo o que for posto na fila
+fn spawn_writer<W: AsyncWrite + Unpin + Send + 'static>(mut writer: W) -> mpsc::UnboundedSender<Frame> {
+    let (outgoing, mut queue) = mpsc::unbounded_channel::<Frame>();
+    tokio::spawn(async move {
+        while let Some(frame) = queue.recv().await {
+            if write_frame(&mut writer, &frame).await.is_err() {
+                break;
+            }
+        }
+    });
+    outgoing
+}
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<WorldResponse>>>>;
+type Streams = Arc<Mutex<HashMap<u64, mpsc::Sender<GameEvent>>>>;
+/// Identidade dada pelo backend e o segredo para voltar a ela; `None`
+/// enquanto ele não respondeu
+type Identity = Arc<Mutex<Option<(u64, String)>>>;
+
+/// Uma conexão aberta com o backend. Uma tarefa escreve os pedidos; outra
+/// lê os quadros e os entrega a quem espera por eles.
+#[derive(Clone)]
+struct Connection {
+    outgoing: mpsc::UnboundedSender<Frame>,
+    pending: Pending,
+    streams: Streams,
+    open: Arc<AtomicBool>,
+}
+
+impl Connection {
+    /// Abre a conexão e apresenta o gateway. A resposta do backend chega
+    /// antes da de qualquer chamada feita por ela e atualiza `identity`.
+    async fn open(addr: &str, identity: Identity) -> Result<Self, RpcError> {
+        let stream = TcpStream::connect(addr)
+            .await
+            .map_err(|e| RpcError::Transport(format!("Backend indisponível em {}: {}", addr, e)))?;
+        let (mut reader, writer) = stream.into_split();
+        let connection = Self {
+            outgoing: spawn_writer(writer),
+            pending: Pending::default(),
+            streams: Streams::default(),
+            open: Arc::new(AtomicBool::new(true)),
+        };
+        let (gateway, token) = identity.lock().unwrap().clone().unzip();
+        let _ = connection.outgoing.send(Frame::Request {
+            id: ATTACH_ID,
+            request: WorldRequest::Attach { gateway, token },
+        });
+
+        let (pending, streams, open) = (
+            connection.pending.clone(),
+            connection.streams.clone(),
+            connection.open.clone(),
+        );
+        tokio::spawn(async move {
+            while let Ok(Some(frame)) = read_frame(&mut reader).await {
+                match frame {
+                    Frame::Response {
+                        id: ATTACH_ID,
+                        response: WorldResponse::Attached { gateway, token },
+                    } => *identity.lock().unwrap() = Some((gateway, token)),
+                    Frame::Response { id, response } => {
+                        if let Some(waiting) = pending.lock().unwrap().remove(&id) {
+                            let _ = waiting.send(response);
+                        }
+                    }
+                    Frame::Event { id, event } => {
+                        let mut streams = streams.lock().unwrap();
+                        let closed = match streams.get(&id) {
+                            Some(stream) => matches!(stream.try_send(event), Err(mpsc::error::TrySendError::Closed(_))),
+                            None => false,
+                        };
+                        if closed {
+                            streams.remove(&id);
+                        }
+                    }
+                    Frame::Request { .. } | Frame::Cancel { .. } => {}
+                }
+            }
+            // A conexão caiu: quem espera resposta ou evento fica sabendo
+            open.store(false, Ordering::SeqCst);
+            pending.lock().unwrap().clear();
+            streams.lock().unwrap().clear();
+        });
+        Ok(connection)
+    }
+
+    fn is_open(&self) -> bool {
+        self.open.load(Ordering::SeqCst) && !self.outgoing.is_closed()
+    }
+}
+
+/// Cliente do backend. As chamadas podem ser feitas de várias tarefas ao
+/// mesmo tempo, cada uma com o seu id; se a conexão cair, a próxima
+/// chamada a reabre.
+///
+/// Os jogadores criados pelo cliente pertencem ao gateway, não à conexão:
+/// ao reabri-la ele se apresenta com a mesma identidade. Se o gateway não
+/// voltar, o backend tira os seus jogadores do mundo.
+pub struct WorldClient {
+    addr: String,
+    timeout: Duration,
+    connection: tokio::sync::Mutex<Option<Connection>>,
+    next_id: AtomicU64,
+    identity: Identity,
+}
+
+impl WorldClient {
+    pub async fn connect(addr: &str) -> Result<Self, RpcError> {
+        let identity = Identity::default();
+        let connection = Connection::open(addr, identity.clone()).await?;
+        Ok(Self {
+            addr: addr.to_string(),
+            timeout: CALL_TIMEOUT,
+            connection: tokio::sync::Mutex::new(Some(connection)),
+            next_id: AtomicU64::new(ATTACH_ID + 1),
+            identity,
+        })
+    }
+
+    /// Identidade do gateway no backend, quando já conhecida
+    pub fn gateway(&self) -> Option<u64> {
+        self.identity.lock().unwrap().as_ref().map(|(gateway, _)| *gateway)
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// A conexão em uso, reaberta se tiver caído
+    async fn connection(&self) -> Result<Connection, RpcError> {
+        let mut current = self.connection.lock().await;
+        match current.as_ref() {
+            Some(connection) if connection.is_open() => Ok(connection.clone()),
+            _ => {
+                let connection = Connection::open(&self.addr, self.identity.clone()).await?;
+                *current = Some(connection.clone());
+                Ok(connection)
+            }
+        }
+    }
+
+    async fn request(
+        &self,
+        connection: &Connection,
+        id: u64,
+        request: WorldRequest,
+    ) -> Result<WorldResponse, RpcError> {
+        let (waiting, response) = oneshot::channel();
+        connection.pending.lock().unwrap().insert(id, waiting);
+        if connection.outgoing.send(Frame::Request { id, request }).is_err() {
+            connection.pending.lock().unwrap().remove(&id);
+            return Err(RpcError::Transport("A conexão com o backend caiu".to_string()));
+        }
+        match tokio::time::timeout(self.timeout, response).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(RpcError::Transport("A conexão com o backend caiu".to_string())),
+            Err(_) => {
+                connection.pending.lock().unwrap().remove(&id);
+                Err(RpcError::Transport(format!(
+                    "O backend não respondeu em {:?}",
+                    self.timeout
+                )))
+            }
+        }
+    }
+
+    /// Envia um pedido e espera a resposta, qualquer que seja
+    pub async fn call(&self, request: WorldRequest) -> Result<WorldResponse, RpcError> {
+        let connection = self.connection().await?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.request(&connection, id, request).await
+    }
+
+    /// Raws em uso pelo mundo
+    pub async fn hello(&self) -> Result<Raws, RpcError> {
+        match self.call(WorldRequest::Hello).await? {
+            WorldResponse::Welcome { raws } => Ok(*raws),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn spawn_player(&self, name: String) -> Result<Option<Entity>, RpcError> {
+        match self.call(WorldRequest::SpawnPlayer { name }).await? {
+            WorldResponse::Spawned { entity } => Ok(entity),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn despawn_player(&self, entity_id: u32) -> Result<(), RpcError> {
+        match self.call(WorldRequest::DespawnPlayer { entity_id }).await? {
+            WorldResponse::Done => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn act(&self, entity_id: u32, message: ClientMessage) -> Result<Acted, RpcError> {
+        match self.call(WorldRequest::Act { entity_id, message }).await? {
+            WorldResponse::Acted(acted) => Ok(acted),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn viewport(&self, entity_id: u32, radius: i32) -> Result<Option<ViewportView>, RpcError> {
+        match self.call(WorldRequest::Viewport { entity_id, radius }).await? {
+            WorldResponse::Viewport(view) => Ok(view),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn viewports(&self, entity_ids: Vec<u32>, radius: i32) -> Result<Vec<(u32, ViewportView)>, RpcError> {
+        match self.call(WorldRequest::Viewports { entity_ids, radius }).await? {
+            WorldResponse::Viewports(views) => Ok(views),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn map(&self) -> Result<MapView, RpcError> {
+        match self.call(WorldRequest::Map).await? {
+            WorldResponse::Map(map) => Ok(map),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn entities(&self) -> Result<Vec<Entity>, RpcError> {
+        match self.call(WorldRequest::Entities).await? {
+            WorldResponse::Entities(entities) => Ok(entities),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn admin(&self, command: AdminCommand) -> Result<AdminReply, RpcError> {
+        match self.call(WorldRequest::Admin(command)).await? {
+            WorldResponse::Admin(reply) => Ok(reply),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn sites(&self) -> Result<Vec<Site>, RpcError> {
+        match self.admin(AdminCommand::Sites).await? {
+            AdminReply::Sites(sites) => Ok(sites),
+            other => Err(unexpected_admin(other)),
+        }
+    }
+
+    /// Estoque e preços de um local; vazio se o local não existe
+    pub async fn market(&self, site_id: u32) -> Result<Vec<MarketEntry>, RpcError> {
+        match self.admin(AdminCommand::Market { site_id }).await? {
+            AdminReply::Market(market) => Ok(market),
+            other => Err(unexpected_admin(other)),
+        }
+    }
+
+    pub async fn history(&self, params: HistoryParams) -> Result<HistoryPage, RpcError> {
+        match self.admin(AdminCommand::History(params)).await? {
+            AdminReply::History(page) => Ok(page),
+            other => Err(unexpected_admin(other)),
+        }
+    }
+
+    /// Sessão gravada, para reproduzir com `backend replay`
+    pub async fn replay(&self) -> Result<Replay, RpcError> {
+        match self.admin(AdminCommand::Replay).await? {
+            AdminReply::Replay(replay) => Ok(*replay),
+            other => Err(unexpected_admin(other)),
+        }
+    }
+
+    pub async fn systems(&self) -> Result<Vec<SystemTiming>, RpcError> {
+        match self.admin(AdminCommand::Systems).await? {
+            AdminReply::Systems(timings) => Ok(timings),
+            other => Err(unexpected_admin(other)),
+        }
+    }
+
+    pub async fn snapshots(&self) -> Result<Vec<SnapshotInfo>, RpcError> {
+        match self.admin(AdminCommand::Snapshots).await? {
+            AdminReply::Snapshots(snapshots) => Ok(snapshots),
+            other => Err(unexpected_admin(other)),
+        }
+    }
+
+    pub async fn capture_snapshot(&self) -> Result<Option<SnapshotInfo>, RpcError> {
+        match self.admin(AdminCommand::CaptureSnapshot).await? {
+            AdminReply::Snapshot(snapshot) => Ok(snapshot),
+            other => Err(unexpected_admin(other)),
+        }
+    }
+
+    pub async fn snapshot_diff(&self, from: u64, to: Option<u64>) -> Result<WorldDiff, RpcError> {
+        match self.admin(AdminCommand::SnapshotDiff { from, to }).await? {
+            AdminReply::Diff(diff) => Ok(diff),
+            other => Err(unexpected_admin(other)),
+        }
+    }
+
+    pub async fn rollback(&self, tick: u64) -> Result<(), RpcError> {
+        match self.call(WorldRequest::Admin(AdminCommand::Rollback { tick })).await? {
+            WorldResponse::Done => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Abre um fluxo com os eventos do mundo, na mesma conexão das chamadas
+    pub async fn subscribe(&self) -> Result<EventStream, RpcError> {
+        let connection = self.connection().await?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (stream, events) = mpsc::channel(STREAM_BUFFER);
+        connection.streams.lock().unwrap().insert(id, stream);
+        match self.request(&connection, id, WorldRequest::Subscribe).await {
+            Ok(WorldResponse::Done) => Ok(EventStream {
+                id,
+                events,
+                outgoing: connection.outgoing,
+            }),
+            response => {
+                connection.streams.lock().unwrap().remove(&id);
+                Err(response.map_or_else(|e| e, unexpected))
+            }
+        }
+    }
+}
+
+/// Eventos do mundo enviados pelo backend. Descartá-lo cancela o fluxo.
+pub struct EventStream {
+    id: u64,
+    events: mpsc::Receiver<GameEvent>,
+    outgoing: mpsc::UnboundedSender<Frame>,
+}
+
+impl EventStream {
+    /// Próximo evento; `None` quando a conexão cai
+    pub async fn next(&mut self) -> Option<GameEvent> {
+        self.events.recv().await
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        let _ = self.outgoing.send(Frame::Cancel { id: self.id });
+    }
+}
+
+/// Quem atende os pedidos: o dono do mundo. Os pedidos chegam com a
+/// identidade do gateway que os fez, a mesma em todas as conexões dele.
+pub trait Service: Send + 'static {
+    fn handle(&mut self, gateway: u64, request: WorldRequest) -> WorldResponse;
+    /// Eventos do mundo, para os fluxos abertos com `WorldRequest::Subscribe`
+    fn events(&self) -> broadcast::Receiver<GameEvent>;
+    /// O gateway abriu a primeira conexão, ou voltou depois de cair
+    fn attach(&mut self, gateway: u64);
+    /// A última conexão do gateway fechou; ele ainda pode voltar
+    fn detach(&mut self, gateway: u64);
+}
+
+/// Identidades dadas aos gateways, com o segredo de cada uma, e quantas
+/// conexões cada um tem abertas
+#[derive(Default)]
+struct Gateways {
+    issued: u64,
+    tokens: HashMap<u64, String>,
+    open: HashMap<u64, usize>,
+}
+
+impl Gateways {
+    /// Conta mais uma conexão do gateway. Uma identidade que não foi dada
+    /// por aqui (de antes de o backend reiniciar), ou apresentada sem o seu
+    /// segredo, é trocada por uma nova. Devolve a identidade, o segredo e se
+    /// é a primeira conexão aberta dela.
+    fn join(&mut self, requested: Option<u64>, token: Option<&str>) -> (u64, String, bool) {
+        let gateway = match requested {
+            Some(id) if token.is_some() && self.tokens.get(&id).map(String::as_str) == token => id,
+            _ => {
+                self.issued += 1;
+                self.tokens.insert(self.issued, uuid::Uuid::new_v4().to_string());
+                self.issued
+            }
+        };
+        let open = self.open.entry(gateway).or_default();
+        *open += 1;
+        (gateway, self.tokens[&gateway].clone(), *open == 1)
+    }
+
+    /// Desconta uma conexão; `true` se era a última do gateway
+    fn leave(&mut self, gateway: u64) -> bool {
+        let Some(open) = self.open.get_mut(&gateway) else {
+            return false;
+        };
+        *open -= 1;
+        if *open > 0 {
+            return false;
+        }
+        self.open.remove(&gateway);
+        true
+    }
+}
+
+/// Atende as conexões, cada uma numa tarefa própria. O serviço fica
+/// travado só enquanto cada pedido é atendido.
+pub async fn serve<S: Service>(listener: TcpListener, service: Arc<Mutex<S>>) {
+    let gateways = Arc::new(Mutex::new(Gateways::default()));
+    let mut connections = 0u64;
+    loop {
+        match listener.accept().await {
+            Ok((stream, addr)) => {
+                connections += 1;
+                println!("🔗 Conexão {} de {}", connections, addr);
+                tokio::spawn(serve_connection(stream, connections, service.clone(), gateways.clone()));
+            }
+            Err(e) => eprintln!("⚠️  Erro ao aceitar conexão: {}", e),
+        }
+    }
+}
+
+/// Identidade da conexão: a pedida no `Attach` ou, se o primeiro pedido
+/// for outro ou o segredo não conferir, uma nova
+fn join_gateway<S: Service>(
+    gateway: &mut Option<(u64, String)>,
+    requested: Option<u64>,
+    token: Option<&str>,
+    service: &Mutex<S>,
+    gateways: &Mutex<Gateways>,
+) -> (u64, String) {
+    if let Some(gateway) = gateway {
+        return gateway.clone();
+    }
+    let (joined, token, first) = gateways.lock().unwrap().join(requested, token);
+    if first {
+        service.lock().unwrap().attach(joined);
+    }
+    *gateway = Some((joined, token.clone()));
+    (joined, token)
+}
+
+async fn serve_connection<S: Service>(
+    stream: TcpStream,
+    connection: u64,
+    service: Arc<Mutex<S>>,
+    gateways: Arc<Mutex<Gateways>>,
+) {
+    let (mut reader, writer) = stream.into_split();
+    let outgoing = spawn_writer(writer);
+    let mut streams: HashMap<u64, JoinHandle<()>> = HashMap::new();
+    let mut gateway = None;
+    loop {
+        let frame = match read_frame(&mut reader).await {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("⚠️  Conexão {}: {}", connection, e);
+                break;
+            }
+        };
+        match frame {
+            Frame::Request {
+                id,
+                request: WorldRequest::Subscribe,
+            } => {
+                let mut events = service.lock().unwrap().events();
+                let _ = outgoing.send(Frame::Response {
+                    id,
+                    response: WorldResponse::Done,
+                });
+                let outgoing = outgoing.clone();
+                let forward = tokio::spawn(async move {
+                    loop {
+                        match events.recv().await {
+                            Ok(event) => {
+                                if outgoing.send(Frame::Event { id, event }).is_err() {
+                                    break;
+                                }
+                            }
+                            Err(RecvError::Lagged(_)) => continue,
+                            Err(RecvError::Closed) => break,
+                        }
+                    }
+                });
+                streams.insert(id, forward);
+            }
+            Frame::Request {
+                id,
+                request: WorldRequest::Attach { gateway: requested, token },
+            } => {
+                let (joined, token) = join_gateway(&mut gateway, requested, token.as_deref(), &service, &gateways);
+                let _ = outgoing.send(Frame::Response {
+                    id,
+                    response: WorldResponse::Attached { gateway: joined, token },
+                });
+            }
+            Frame::Request { id, request } => {
+                let (joined, _) = join_gateway(&mut gateway, None, None, &service, &gateways);
+                let response = service.lock().unwrap().handle(joined, request);
+                let _ = outgoing.send(Frame::Response { id, response });
+            }
+            Frame::Cancel { id } => {
+                if let Some(forward) = streams.remove(&id) {
+                    forward.abort();
+                }
+            }
+            Frame::Response { .. } | Frame::Event { .. } => {
+                eprintln!("⚠️  Conexão {}: quadro inesperado", connection);
+            }
+        }
+    }
+    for forward in streams.values() {
+        forward.abort();
+    }
+    if let Some((gateway, _)) = gateway {
+        if gateways.lock().unwrap().leave(gateway) {
+            service.lock().unwrap().detach(gateway);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EventHub;
+
+    /// Serviço de teste: anota os gateways que caíram e recusa o mapa
+    #[derive(Default)]
+    struct Echo {
+        hub: EventHub,
+        detached: Vec<u64>,
+    }
+
+    impl Service for Echo {
+        fn handle(&mut self, gateway: u64, request: WorldRequest) -> WorldResponse {
+            match request {
+                WorldRequest::Entities => WorldResponse::Entities(vec![]),
+                WorldRequest::DespawnPlayer { entity_id } if entity_id == gateway as u32 => WorldResponse::Done,
+                _ => WorldResponse::Error("não suportado".to_string()),
+            }
+        }
+
+        fn events(&self) -> broadcast::Receiver<GameEvent> {
+            self.hub.subscribe()
+        }
+
+        fn attach(&mut self, _gateway: u64) {}
+
+        fn detach(&mut self, gateway: u64) {
+            self.detached.push(gateway);
+        }
+    }
+
+    async fn start(service: Arc<Mutex<Echo>>) -> (String, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        (addr, tokio::spawn(serve(listener, service)))
+    }
+
+    fn tick(tick: u64) -> GameEvent {
+        GameEvent::WorldTick {
+            tick,
+            active_players: 0,
+            total_entities: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_calls_and_stream_share_a_connection() {
+        let service = Arc::new(Mutex::new(Echo::default()));
+        let (addr, _server) = start(service.clone()).await;
+        let client = WorldClient::connect(&addr).await.unwrap();
+
+        let mut events = client.subscribe().await.unwrap();
+        let (entities, refused, done) = tokio::join!(client.entities(), client.map(), client.despawn_player(1),);
+        assert_eq!(entities, Ok(vec![]));
+        assert_eq!(refused, Err(RpcError::Refused("não suportado".to_string())));
+        assert_eq!(done, Ok(()));
+
+        service.lock().unwrap().hub.broadcast(tick(7));
+        assert!(matches!(
+            events.next().await,
+            Some(GameEvent::WorldTick { tick: 7, .. })
+        ));
+
+        // Um fluxo descartado deixa de receber; a conexão continua
+        drop(events);
+        let mut again = client.subscribe().await.unwrap();
+        service.lock().unwrap().hub.broadcast(tick(8));
+        assert!(matches!(again.next().await, Some(GameEvent::WorldTick { tick: 8, .. })));
+        assert!(service.lock().unwrap().detached.is_empty());
+        assert_eq!(client.gateway(), Some(1));
+    }
+
+    /// Apresenta-se ao backend numa conexão crua e devolve a identidade dada
+    async fn attach(addr: &str, gateway: Option<u64>, token: Option<String>) -> (u64, String) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = WorldRequest::Attach { gateway, token };
+        write_frame(&mut stream, &Frame::Request { id: ATTACH_ID, request }).await.unwrap();
+        match read_frame(&mut stream).await.unwrap() {
+            Some(Frame::Response {
+                response: WorldResponse::Attached { gateway, token },
+                ..
+            }) => (gateway, token),
+            other => panic!("unexpected frame: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_identity_needs_its_token() {
+        let service = Arc::new(Mutex::new(Echo::default()));
+        let (addr, _server) = start(service).await;
+        let client = WorldClient::connect(&addr).await.unwrap();
+        client.entities().await.unwrap();
+        let (gateway, token) = client.identity.lock().unwrap().clone().unwrap();
+
+        // Quem adivinha o id, sem o segredo ou com outro, recebe uma
+        // identidade nova
+        let (guessed, _) = attach(&addr, Some(gateway), None).await;
+        let (forged, _) = attach(&addr, Some(gateway), Some("segredo".to_string())).await;
+        assert!(guessed != gateway && forged != gateway && guessed != forged);
+
+        let (again, same) = attach(&addr, Some(gateway), Some(token.clone())).await;
+        assert_eq!((again, same), (gateway, token));
+    }
+
+    #[tokio::test]
+    async fn test_call_times_out_and_reconnects() {
+        // Um backend que aceita e nunca responde, depois cai
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let silent = tokio::spawn(async move {
+            let (_stream, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+        let client = WorldClient::connect(&addr.to_string())
+            .await
+            .unwrap()
+            .with_timeout(Duration::from_millis(100));
+        assert!(matches!(client.entities().await, Err(RpcError::Transport(_))));
+
+        silent.abort();
+        let _ = silent.await;
+        assert!(matches!(client.entities().await, Err(RpcError::Transport(_))));
+
+        // O backend volta no mesmo endereço: a próxima chamada reconecta
+        let service = Arc::new(Mutex::new(Echo::default()));
+        let _server = tokio::spawn(serve(TcpListener::bind(addr).await.unwrap(), service));
+        assert_eq!(client.entities().await, Ok(vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frame_is_rejected() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(&(MAX_FRAME as u32 + 1).to_be_bytes()).await.unwrap();
+        assert!(read_frame(&mut server).await.is_err());
+        drop(client);
+        assert!(read_frame(&mut server).await.unwrap().is_none());
+    }
+}
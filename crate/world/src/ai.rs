@@ -0,0 +1,146 @@
+use crate::lod::{region_of, Region};
+use crate::World;
+use common::*;
+use std::collections::{BTreeMap, HashSet};
+
+/// Abaixo disso, abrir threads custa mais do que planejar em série
+const PARALLEL_THRESHOLD: usize = 512;
+
+/// Passo que uma entidade quer dar neste tick
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AiIntent {
+    entity_id: u32,
+    dx: i32,
+    dy: i32,
+}
+
+impl World {
+    /// IA das entidades. As intenções são planejadas em paralelo, região
+    /// por região, lendo o mundo sem alterá-lo; depois são aplicadas em
+    /// série, por ordem de ID. Como cada intenção depende só da própria
+    /// entidade, o resultado é idêntico ao de planejar tudo em série.
+    /// Só as regiões detalhadas entram; as demais ficam com `simulate_coarse`.
+    pub(crate) fn update_ai(&mut self) {
+        let detailed = self.detailed_regions();
+        if detailed.is_empty() {
+            return;
+        }
+        let workers = if self.ai_behaviors.len() < PARALLEL_THRESHOLD {
+            1
+        } else {
+            std::thread::available_parallelism().map_or(1, |n| n.get())
+        };
+        for intent in self.plan_ai(workers, &detailed) {
+            self.move_entity(intent.entity_id, intent.dx, intent.dy);
+        }
+    }
+
+    /// Intenções das entidades com IA nas regiões dadas, ordenadas por ID
+    fn plan_ai(&self, workers: usize, detailed: &HashSet<Region>) -> Vec<AiIntent> {
+        // Regiões em ordem fixa; dentro delas, entidades por ID
+        let mut regions: BTreeMap<Region, Vec<u32>> = BTreeMap::new();
+        for id in self.ai_behaviors.keys() {
+            if let Some(entity) = self.entity_layer.get(id) {
+                let region = region_of(entity.pos);
+                if detailed.contains(&region) {
+                    regions.entry(region).or_default().push(*id);
+                }
+            }
+        }
+        let regions: Vec<Vec<u32>> = regions.into_values().collect();
+
+        let mut intents: Vec<AiIntent> = if workers <= 1 || regions.len() <= 1 {
+            regions.iter().flatten().filter_map(|id| self.ai_intent(*id)).collect()
+        } else {
+            let per_worker = regions.len().div_ceil(workers);
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = regions
+                    .chunks(per_worker)
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            chunk
+                                .iter()
+                                .flatten()
+                                .filter_map(|id| self.ai_intent(*id))
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+            })
+        };
+        intents.sort_unstable_by_key(|i| i.entity_id);
+        intents
+    }
+
+    /// O que a entidade quer fazer, a partir do seu objetivo
+    fn ai_intent(&self, entity_id: u32) -> Option<AiIntent> {
+        let behavior = self.ai_behaviors.get(&entity_id)?;
+        let (dx, dy) = match behavior.current_goal {
+            AIGoal::Wander => {
+                // Movimento aleatório
+                let dx = (self.current_tick % 3) as i32 - 1;
+                let dy = ((self.current_tick / 3) % 3) as i32 - 1;
+                (dx, dy)
+            }
+            AIGoal::Patrol { start, end } => {
+                // Patrulha entre dois pontos
+                let entity = self.entity_layer.get(&entity_id)?;
+                let target = if (self.current_tick / 50).is_multiple_of(2) {
+                    start
+                } else {
+                    end
+                };
+                ((target.x - entity.pos.x).signum(), (target.y - entity.pos.y).signum())
+            }
+            _ => return None,
+        };
+        Some(AiIntent { entity_id, dx, dy })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_plan_matches_serial() {
+        let mut world = World::new(200, 200);
+        for i in 0..2000 {
+            let pos = Position::new(3 + (i * 7) % 194, 3 + (i * 13) % 194);
+            let Some(id) = world.spawn_entity(format!("Goblin {}", i), pos, EntityType::NPC) else {
+                continue;
+            };
+            let current_goal = if i % 2 == 0 {
+                AIGoal::Wander
+            } else {
+                AIGoal::Patrol {
+                    start: pos,
+                    end: Position::new(100, 100),
+                }
+            };
+            world.ai_behaviors.insert(
+                id,
+                AIBehavior {
+                    current_goal,
+                    memory: Vec::new(),
+                    personality: Personality {
+                        aggression: 0.5,
+                        curiosity: 0.5,
+                        sociability: 0.5,
+                    },
+                },
+            );
+        }
+
+        let everywhere: HashSet<Region> = (0..7).flat_map(|x| (0..7).map(move |y| (x, y))).collect();
+        for _ in 0..60 {
+            world.current_tick += 1;
+            let serial = world.plan_ai(1, &everywhere);
+            assert_eq!(world.plan_ai(8, &everywhere), serial);
+            for intent in serial {
+                world.move_entity(intent.entity_id, intent.dx, intent.dy);
+            }
+        }
+    }
+}
@@ -0,0 +1,475 @@
+use crate::economy::Stockpile;
+use crate::history::Happening;
+use crate::journal::Mutation;
+use crate::raws;
+use crate::{World, WorldEvent};
+use common::*;
+use serde::{Deserialize, Serialize};
+
+/// Animais de carga que acompanham cada caravana
+const PACK_ANIMALS: usize = 2;
+/// Chance, a cada passo em território hostil, de a caravana ser emboscada
+const AMBUSH_CHANCE: f32 = 0.05;
+/// Distância (Chebyshev) para um jogador interagir com uma caravana
+pub const CARAVAN_REACH: i32 = 2;
+/// Distância máxima da escolta para continuar protegendo a caravana
+const ESCORT_RANGE: i32 = 5;
+/// Parte da carga dada à escolta na chegada
+const ESCORT_SHARE: u32 = 10;
+
+/// Caravana de comércio levando carga de um local para outro
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Caravan {
+    pub id: u32,
+    /// Fação que enviou a caravana
+    pub owner: Option<u32>,
+    pub origin: u32,
+    pub destination: u32,
+    /// Mestre da caravana (NPC)
+    pub leader: u32,
+    pub pack_animals: Vec<u32>,
+    pub cargo: Stockpile,
+    /// Caminho até o destino e quantos passos já foram dados
+    pub route: Vec<Position>,
+    pub progress: usize,
+    /// Jogador que escolta a caravana
+    pub escort: Option<u32>,
+    pub departed_tick: u64,
+}
+
+impl Caravan {
+    pub fn has_arrived(&self) -> bool {
+        self.progress >= self.route.len()
+    }
+}
+
+/// Distância em passos (diagonais contam como um)
+pub(crate) fn distance(a: Position, b: Position) -> i32 {
+    (a.x - b.x).abs().max((a.y - b.y).abs())
+}
+
+impl World {
+    /// Envia uma caravana com a carga de um local a outro; retorna `None`
+    /// se não há caminho entre eles
+    pub fn dispatch_caravan(&mut self, origin: u32, destination: u32, cargo: Stockpile) -> Option<u32> {
+        let from = self.sites.get(&origin)?;
+        let to = self.sites.get(&destination)?;
+        let (owner, start, goal) = (from.owner, from.center, to.center);
+        let route = self.find_path(start, goal)?;
+
+        let leader_name = format!("Caravan master of {}", from.name);
+        // Criatura do líder: a do povo dono da caravana
+        let leader_creature = owner
+            .and_then(|id| self.factions.get(&id))
+            .and_then(|f| self.raws.faction(f.faction_type.id()))
+            .map(|f| f.creature.clone())
+            .unwrap_or_else(|| raws::VILLAGER_CREATURE.to_string());
+        let leader = self.spawn_creature(&leader_creature, leader_name, start)?;
+        let pack_animals = (0..PACK_ANIMALS)
+            .filter_map(|_| self.spawn_creature(raws::PACK_CREATURE, "Pack mule".to_string(), start))
+            .collect();
+
+        let id = self.next_caravan_id;
+        self.next_caravan_id += 1;
+        self.caravans.insert(id, Caravan {
+            id,
+            owner,
+            origin,
+            destination,
+            leader,
+            pack_animals,
+            cargo,
+            route,
+            progress: 0,
+            escort: None,
+            departed_tick: self.current_tick,
+        });
+        self.publish(WorldEvent::CaravanDeparted {
+            caravan: id,
+            origin,
+            destination,
+        });
+        Some(id)
+    }
+
+    /// Avança todas as caravanas `ticks` ticks: andam pela rota, podem ser
+    /// emboscadas em território hostil e descarregam ao chegar
+    pub(crate) fn update_caravans(&mut self, ticks: u64) {
+        let mut ids: Vec<u32> = self.caravans.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            self.advance_caravan(id, ticks);
+        }
+    }
+
+    /// Avança uma caravana o equivalente a `ticks` ticks de viagem. Com
+    /// mais de um tick (simulação grossa), a emboscada é sorteada uma vez,
+    /// com a chance acumulada do trecho.
+    pub(crate) fn advance_caravan(&mut self, id: u32, ticks: u64) {
+        let Some(caravan) = self.caravans.get(&id) else {
+            return;
+        };
+        let mut progress = caravan.progress;
+        for _ in 0..ticks {
+            // Na estrada, a caravana anda dois passos por vez
+            let on_road = |i: usize| caravan.route.get(i).is_some_and(|p| self.is_road(*p));
+            let steps = if on_road(progress) && on_road(progress + 1) { 2 } else { 1 };
+            progress = (progress + steps).min(caravan.route.len());
+        }
+        let Some(caravan) = self.caravans.get_mut(&id) else {
+            return;
+        };
+        caravan.progress = progress;
+        let caravan = caravan.clone();
+
+        // O mestre vai à frente e os animais seguem pela trilha
+        let trail = |behind: usize| {
+            caravan
+                .progress
+                .checked_sub(behind + 1)
+                .and_then(|i| caravan.route.get(i))
+                .copied()
+        };
+        let members = std::iter::once(caravan.leader).chain(caravan.pack_animals.iter().copied());
+        for (behind, member) in members.enumerate() {
+            if let (Some(pos), Some(entity)) = (trail(behind), self.entity_layer.get_mut(&member)) {
+                let old_pos = std::mem::replace(&mut entity.pos, pos);
+                self.record(|| Mutation::Move { entity_id: member, to: pos });
+                self.touch(old_pos);
+                self.touch(pos);
+            }
+        }
+
+        if let Some(escort) = caravan.escort {
+            let close = self.entity_layer.get(&escort).is_some_and(|e| {
+                trail(0).is_some_and(|pos| distance(e.pos, pos) <= ESCORT_RANGE)
+            });
+            if !close {
+                if let Some(caravan) = self.caravans.get_mut(&id) {
+                    caravan.escort = None;
+                }
+            }
+        }
+
+        if caravan.has_arrived() {
+            self.unload_caravan(id);
+        } else {
+            self.consider_ambush(id, ticks);
+        }
+    }
+
+    /// Em território de uma fação hostil, a caravana pode ser atacada
+    fn consider_ambush(&mut self, id: u32, ticks: u64) {
+        let Some(caravan) = self.caravans.get(&id) else {
+            return;
+        };
+        let (Some(owner), Some(pos)) = (caravan.owner, caravan.route.get(caravan.progress.saturating_sub(1))) else {
+            return;
+        };
+        let Some(attacker) = self.territory_owner(*pos) else {
+            return;
+        };
+        let hostile = self
+            .factions
+            .get(&owner)
+            .and_then(|f| f.relations.get(&attacker))
+            .is_some_and(|r| matches!(r, Relation::Hostile | Relation::War));
+        let escort = caravan.escort;
+        let mut chance = if escort.is_some() { AMBUSH_CHANCE / 2.0 } else { AMBUSH_CHANCE };
+        if ticks > 1 {
+            chance = 1.0 - (1.0 - chance).powi(ticks.min(i32::MAX as u64) as i32);
+        }
+        if !hostile || !self.rng.chance(chance) {
+            return;
+        }
+        self.ambush_caravan(id, attacker);
+    }
+
+    /// Uma fação ataca a caravana: com escolta, o ataque é repelido; sem
+    /// ela, a carga vai para o agressor e o mestre da caravana morre. As
+    /// relações entre as fações pioram em qualquer caso.
+    pub fn ambush_caravan(&mut self, id: u32, attacker: u32) {
+        let Some(caravan) = self.caravans.get(&id).cloned() else {
+            return;
+        };
+        let location = self
+            .entity_layer
+            .get(&caravan.leader)
+            .map(|e| e.pos)
+            .unwrap_or_else(|| self.sites.get(&caravan.origin).map(|s| s.center).unwrap_or(Position::new(0, 0)));
+
+        let repelled = caravan.escort.is_some();
+        self.report_happening(Happening::Ambush {
+            attacker,
+            caravan_owner: caravan.owner,
+            leader: caravan.leader,
+            escort: caravan.escort,
+            repelled,
+            location,
+        });
+
+        if let Some(owner) = caravan.owner {
+            self.worsen_relation(owner, attacker, location);
+        }
+        if repelled {
+            return;
+        }
+
+        // O mestre tomba na estrada; os animais são levados com a carga
+        self.caravans.remove(&id);
+        self.store_in_home(attacker, &caravan.cargo);
+        self.kill_entity(caravan.leader, None);
+        for animal in caravan.pack_animals {
+            self.despawn_entity(animal);
+        }
+    }
+
+    /// Um passo a menos de amizade; chegar à guerra é declarado
+    fn worsen_relation(&mut self, victim: u32, aggressor: u32, location: Position) {
+        let Some(relation) = self.factions.get(&victim).and_then(|f| f.relations.get(&aggressor)).copied() else {
+            return;
+        };
+        let worse = match relation {
+            Relation::Allied => Relation::Friendly,
+            Relation::Friendly => Relation::Neutral,
+            Relation::Neutral => Relation::Hostile,
+            Relation::Hostile | Relation::War => Relation::War,
+        };
+        if worse == relation {
+            return;
+        }
+        self.set_relation(victim, aggressor, worse);
+        if worse == Relation::War {
+            self.report_happening(Happening::WarDeclared {
+                aggressor: victim,
+                defender: aggressor,
+                location,
+            });
+        }
+    }
+
+    /// A caravana chega: a carga entra no estoque do destino, a escolta
+    /// recebe sua parte e os membros se recolhem ao local
+    fn unload_caravan(&mut self, id: u32) {
+        let Some(mut caravan) = self.caravans.remove(&id) else {
+            return;
+        };
+        let delivered = Resource::ALL.into_iter().map(|r| caravan.cargo.get(r)).sum();
+        self.record_trade(caravan.origin, caravan.destination, delivered);
+
+        if let Some(escort) = caravan.escort {
+            let pack = self.packs.entry(escort).or_default();
+            for resource in Resource::ALL {
+                let share = caravan.cargo.get(resource) / ESCORT_SHARE;
+                pack.add(resource, caravan.cargo.take(resource, share));
+            }
+        }
+        if let Some(site) = self.sites.get_mut(&caravan.destination) {
+            for resource in Resource::ALL {
+                site.stockpile.add(resource, caravan.cargo.get(resource));
+            }
+        }
+        for member in std::iter::once(caravan.leader).chain(caravan.pack_animals) {
+            self.despawn_entity(member);
+        }
+        self.publish(WorldEvent::CaravanArrived {
+            caravan: id,
+            origin: caravan.origin,
+            destination: caravan.destination,
+        });
+    }
+
+    /// Retorna caravana por ID
+    pub fn get_caravan(&self, id: u32) -> Option<&Caravan> {
+        self.caravans.get(&id)
+    }
+
+    /// Retorna todas as caravanas ordenadas por ID
+    pub fn get_caravans(&self) -> Vec<&Caravan> {
+        let mut caravans: Vec<&Caravan> = self.caravans.values().collect();
+        caravans.sort_by_key(|c| c.id);
+        caravans
+    }
+
+    /// Caravana mais próxima de uma posição, ao alcance de uma interação
+    pub fn caravan_near(&self, pos: Position) -> Option<u32> {
+        self.get_caravans()
+            .into_iter()
+            .filter_map(|c| {
+                let leader = self.entity_layer.get(&c.leader)?;
+                let d = distance(leader.pos, pos);
+                (d <= CARAVAN_REACH).then_some((d, c.id))
+            })
+            .min()
+            .map(|(_, id)| id)
+    }
+
+    /// Recursos carregados por uma entidade
+    pub fn get_pack(&self, entity_id: u32) -> Option<&Stockpile> {
+        self.packs.get(&entity_id)
+    }
+
+    /// Verifica se a entidade está ao alcance da caravana
+    fn reach_caravan(&self, entity_id: u32, id: u32) -> Result<&Caravan, String> {
+        let caravan = self.caravans.get(&id).ok_or("There is no such caravan")?;
+        let entity = self.entity_layer.get(&entity_id).ok_or("Unknown entity")?;
+        let leader = self.entity_layer.get(&caravan.leader).ok_or("The caravan has no master")?;
+        if distance(entity.pos, leader.pos) > CARAVAN_REACH {
+            return Err("The caravan is too far away".to_string());
+        }
+        Ok(caravan)
+    }
+
+    /// A entidade passa a escoltar a caravana até o destino
+    pub fn escort_caravan(&mut self, entity_id: u32, id: u32) -> Result<(), String> {
+        self.reach_caravan(entity_id, id)?;
+        if let Some(caravan) = self.caravans.get_mut(&id) {
+            caravan.escort = Some(entity_id);
+        }
+        Ok(())
+    }
+
+    /// A entidade rouba toda a carga da caravana; retorna o que levou
+    pub fn rob_caravan(&mut self, entity_id: u32, id: u32) -> Result<Stockpile, String> {
+        let caravan = self.reach_caravan(entity_id, id)?;
+        let (leader, location) = (caravan.leader, self.entity_layer[&caravan.leader].pos);
+        let origin = self.site_name(caravan.origin);
+
+        let Some(caravan) = self.caravans.get_mut(&id) else {
+            return Ok(Stockpile::default());
+        };
+        let loot = std::mem::take(&mut caravan.cargo);
+        if caravan.escort == Some(entity_id) {
+            caravan.escort = None;
+        }
+        let pack = self.packs.entry(entity_id).or_default();
+        for resource in Resource::ALL {
+            pack.add(resource, loot.get(resource));
+        }
+
+        self.report_happening(Happening::Theft {
+            thief: entity_id,
+            victim: leader,
+            item: format!("the goods of a caravan from {}", origin),
+            location,
+        });
+        Ok(loot)
+    }
+
+    /// Troca com a caravana pelos preços do destino: a entidade paga com
+    /// `give` e leva `amount` de `take`. Retorna quanto foi pago.
+    pub fn trade_with_caravan(
+        &mut self,
+        entity_id: u32,
+        id: u32,
+        give: Resource,
+        take: Resource,
+        amount: u32,
+    ) -> Result<u32, String> {
+        let caravan = self.reach_caravan(entity_id, id)?;
+        if give == take || amount == 0 {
+            return Err("Nothing to trade".to_string());
+        }
+        if caravan.cargo.get(take) < amount {
+            return Err(format!("The caravan does not carry that much {}", take.name()));
+        }
+        let market = self.sites.get(&caravan.destination).ok_or("The caravan has nowhere to go")?;
+        let cost = (amount * market.price(take)).div_ceil(market.price(give));
+        let carried = self.packs.get(&entity_id).map(|p| p.get(give)).unwrap_or(0);
+        if carried < cost {
+            return Err(format!("You need {} {} for that", cost, give.name()));
+        }
+
+        let pack = self.packs.entry(entity_id).or_default();
+        pack.take(give, cost);
+        pack.add(take, amount);
+        if let Some(caravan) = self.caravans.get_mut(&id) {
+            caravan.cargo.take(take, amount);
+            caravan.cargo.add(give, cost);
+        }
+        Ok(cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SiteKind;
+
+    fn trading_world() -> (World, u32, u32, u32) {
+        let mut world = World::new(60, 60);
+        let faction = world.add_faction("The River Kingdom", FactionType::new("human"));
+        let origin = world.found_site("Ashford".to_string(), SiteKind::Town, Some(faction), Position::new(10, 10), 40);
+        let destination = world.found_site("Deephold".to_string(), SiteKind::Town, Some(faction), Position::new(10, 40), 40);
+        (world, faction, origin, destination)
+    }
+
+    fn cargo_of_food(amount: u32) -> Stockpile {
+        Stockpile { food: amount, ..Default::default() }
+    }
+
+    #[test]
+    fn test_caravan_travels_and_unloads() {
+        let (mut world, _, origin, destination) = trading_world();
+        let before = world.get_site(destination).unwrap().stockpile.food;
+        let id = world.dispatch_caravan(origin, destination, cargo_of_food(100)).unwrap();
+        let caravan = world.get_caravan(id).unwrap().clone();
+        assert_eq!(world.get_entity(caravan.leader).map(|e| e.entity_type), Some(EntityType::NPC));
+
+        for _ in 0..caravan.route.len() {
+            world.update_caravans(1);
+        }
+
+        assert!(world.get_caravan(id).is_none());
+        assert!(world.get_entity(caravan.leader).is_none());
+        assert_eq!(world.get_site(destination).unwrap().stockpile.food, before + 100);
+    }
+
+    #[test]
+    fn test_ambush_takes_cargo_and_sours_relations() {
+        let (mut world, faction, origin, destination) = trading_world();
+        let raiders = world.add_faction("The Gnashing Warband", FactionType::new("goblin"));
+        let camp = world.found_site("Mudden".to_string(), SiteKind::Camp, Some(raiders), Position::new(40, 10), 30);
+        world.set_relation(faction, raiders, Relation::Hostile);
+        let food_before = world.get_site(camp).unwrap().stockpile.food;
+
+        let id = world.dispatch_caravan(origin, destination, cargo_of_food(100)).unwrap();
+        world.update_caravans(1);
+        world.ambush_caravan(id, raiders);
+
+        assert!(world.get_caravan(id).is_none());
+        assert_eq!(world.get_site(camp).unwrap().stockpile.food, food_before + 100);
+        assert_eq!(world.get_faction(faction).unwrap().relations[&raiders], Relation::War);
+        let events = world.get_historical_events(10);
+        assert!(events.iter().any(|e| e.event_type == EventType::Ambush));
+        assert!(events.iter().any(|e| e.event_type == EventType::War));
+    }
+
+    #[test]
+    fn test_player_escorts_trades_and_robs() {
+        let (mut world, _, origin, destination) = trading_world();
+        let id = world.dispatch_caravan(origin, destination, cargo_of_food(100)).unwrap();
+        world.update_caravans(1);
+        let leader_pos = world.get_entity(world.get_caravan(id).unwrap().leader).unwrap().pos;
+        let player = world.spawn_entity("Hero".to_string(), leader_pos, EntityType::Player).unwrap();
+        let far_player = world.spawn_entity("Lost".to_string(), Position::new(50, 50), EntityType::Player).unwrap();
+
+        assert_eq!(world.caravan_near(leader_pos), Some(id));
+        assert!(world.escort_caravan(far_player, id).is_err());
+        assert!(world.escort_caravan(player, id).is_ok());
+        assert!(world.trade_with_caravan(player, id, Resource::Metal, Resource::Food, 10).is_err());
+
+        let loot = world.rob_caravan(player, id).unwrap();
+        assert_eq!(loot.food, 100);
+        assert_eq!(world.get_pack(player).unwrap().food, 100);
+        assert_eq!(world.get_caravan(id).unwrap().escort, None);
+        assert_eq!(world.get_historical_events(1)[0].event_type, EventType::Theft);
+
+        world.packs.get_mut(&player).unwrap().metal = 50;
+        world.caravans.get_mut(&id).unwrap().cargo.wood = 20;
+        let paid = world.trade_with_caravan(player, id, Resource::Metal, Resource::Wood, 20).unwrap();
+        assert!(paid > 0);
+        assert_eq!(world.get_pack(player).unwrap().wood, 20);
+        assert_eq!(world.get_pack(player).unwrap().metal, 50 - paid);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::lod::region_of;
+use crate::{Happening, World};
+use common::*;
+
+/// Agressividade a partir da qual uma fera sem fação ataca quem chegar
+/// perto
+pub const BEAST_AGGRESSION: f32 = 0.8;
+
+impl World {
+    /// Lutas entre inimigos lado a lado, nas regiões detalhadas. Cada
+    /// entidade com IA ataca no máximo uma vez por tick, em ordem de ID; o
+    /// perdedor morre. Jogadores não são atacados pela simulação.
+    pub(crate) fn resolve_combat(&mut self) {
+        let detailed = self.detailed_regions();
+        if detailed.is_empty() {
+            return;
+        }
+        let mut attackers: Vec<u32> = self
+            .ai_behaviors
+            .keys()
+            .copied()
+            .filter(|id| self.entity_layer.get(id).is_some_and(|e| detailed.contains(&region_of(e.pos))))
+            .collect();
+        attackers.sort_unstable();
+
+        for attacker in attackers {
+            // Pode ter morrido numa luta anterior do mesmo tick
+            let Some(pos) = self.entity_layer.get(&attacker).map(|e| e.pos) else {
+                continue;
+            };
+            let defender = self
+                .get_entities_in_region(pos, 1)
+                .into_iter()
+                .filter(|e| e.id != attacker && self.is_enemy(attacker, e))
+                .map(|e| e.id)
+                .min();
+            if let Some(defender) = defender {
+                self.fight(attacker, defender);
+            }
+        }
+    }
+
+    /// Se `attacker` ataca `defender` ao encontrá-lo
+    pub(crate) fn is_enemy(&self, attacker: u32, defender: &Entity) -> bool {
+        if defender.entity_type == EntityType::Player {
+            return false;
+        }
+        let (Some(behavior), Some(entity)) = (self.ai_behaviors.get(&attacker), self.entity_layer.get(&attacker)) else {
+            return false;
+        };
+        let faction = |id: u32| self.historical_figures.get(&id).and_then(|f| f.faction_id);
+        match (faction(attacker), faction(defender.id)) {
+            (Some(a), Some(b)) => self
+                .factions
+                .get(&a)
+                .and_then(|f| f.relations.get(&b))
+                .is_some_and(|r| matches!(r, Relation::Hostile | Relation::War)),
+            _ => match behavior.current_goal {
+                AIGoal::Hunt => defender.creature != entity.creature,
+                _ => {
+                    entity.entity_type == EntityType::Animal
+                        && behavior.personality.aggression >= BEAST_AGGRESSION
+                        && defender.entity_type != EntityType::Animal
+                }
+            },
+        }
+    }
+
+    /// Uma luta: vence o maior com mais chance, e o perdedor morre
+    pub(crate) fn fight(&mut self, attacker: u32, defender: u32) {
+        let size = |world: &World, id: u32| {
+            world.entity_layer.get(&id).map_or(1, |e| world.raws.creature_of(e).size.max(1))
+        };
+        let (attacker_size, defender_size) = (size(self, attacker), size(self, defender));
+        let location = self.entity_layer[&defender].pos;
+        self.report_happening(Happening::Fight {
+            attacker,
+            defender,
+            location,
+        });
+        let odds = attacker_size as f32 / (attacker_size + defender_size) as f32;
+        let (winner, loser) = if self.rng.chance(odds) {
+            (attacker, defender)
+        } else {
+            (defender, attacker)
+        };
+        self.kill_entity(loser, Some(winner));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn give_behavior(world: &mut World, id: u32, current_goal: AIGoal, aggression: f32) {
+        world.ai_behaviors.insert(
+            id,
+            AIBehavior {
+                current_goal,
+                memory: Vec::new(),
+                personality: Personality {
+                    aggression,
+                    curiosity: 0.5,
+                    sociability: 0.5,
+                },
+            },
+        );
+    }
+
+    #[test]
+    fn test_hunter_fights_its_neighbor_near_players() {
+        let mut world = World::new(40, 40);
+        world.set_history_recorder(crate::HistoryRecorder::new(0));
+        let wolf = world.spawn_creature("wolf", "Wolf".to_string(), Position::new(30, 30)).unwrap();
+        give_behavior(&mut world, wolf, AIGoal::Hunt, 0.5);
+        let villager = world.spawn_creature("villager", "Urist".to_string(), Position::new(31, 30)).unwrap();
+        let pack = world.spawn_creature("wolf", "Packmate".to_string(), Position::new(29, 30)).unwrap();
+
+        // Longe de jogadores a região não é detalhada e ninguém luta
+        world.resolve_combat();
+        assert!(world.get_entity(villager).is_some() && world.get_entity(wolf).is_some());
+
+        world.spawn_entity("Hero".to_string(), Position::new(33, 33), EntityType::Player).unwrap();
+        world.resolve_combat();
+        let survivors = [wolf, villager].iter().filter(|id| world.get_entity(**id).is_some()).count();
+        assert_eq!(survivors, 1);
+        // Lobos não caçam lobos
+        assert!(world.get_entity(pack).is_some());
+        let events = world.get_historical_events(2);
+        assert_eq!(events[0].event_type, EventType::Combat);
+        assert_eq!(events[0].participants, vec![wolf, villager]);
+        assert_eq!(events[1].event_type, EventType::Death);
+    }
+
+    #[test]
+    fn test_beasts_attack_but_players_are_spared() {
+        let mut world = World::new(40, 40);
+        let beast = world.spawn_entity("Fangmaw".to_string(), Position::new(30, 30), EntityType::Animal).unwrap();
+        give_behavior(&mut world, beast, AIGoal::Guard { pos: Position::new(30, 30) }, 0.9);
+        let hero = world.spawn_entity("Hero".to_string(), Position::new(31, 30), EntityType::Player).unwrap();
+
+        for _ in 0..10 {
+            world.resolve_combat();
+        }
+        assert!(world.get_entity(hero).is_some());
+
+        let villager = world.spawn_creature("villager", "Urist".to_string(), Position::new(30, 31)).unwrap();
+        world.resolve_combat();
+        assert!(world.get_entity(villager).is_none() || world.get_entity(beast).is_none());
+    }
+}
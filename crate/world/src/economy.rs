@@ -0,0 +1,436 @@
+use crate::history::Happening;
+use crate::sites::{Site, SITE_CLAIM_RADIUS};
+use crate::World;
+use common::*;
+use serde::{Deserialize, Serialize};
+
+/// Intervalo, em ticks, entre ciclos de produção e consumo
+pub const ECONOMY_INTERVAL: u64 = 100;
+/// Estoque máximo de cada recurso em um local
+pub const STOCKPILE_CAP: u32 = 2000;
+/// Ciclos de consumo que um local quer ter guardados
+const RESERVE_CYCLES: u32 = 10;
+/// Menor população que a fome deixa em um local
+const STARVATION_FLOOR: usize = 5;
+
+/// Estoque de recursos de um local
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Stockpile {
+    pub food: u32,
+    pub wood: u32,
+    pub stone: u32,
+    pub metal: u32,
+}
+
+impl Stockpile {
+    /// Estoque inicial de um local recém-fundado
+    pub fn for_population(population: usize) -> Self {
+        let population = population as u32;
+        Self {
+            food: population * 3,
+            wood: population,
+            stone: population,
+            metal: population / 2,
+        }
+    }
+
+    pub fn get(&self, resource: Resource) -> u32 {
+        match resource {
+            Resource::Food => self.food,
+            Resource::Wood => self.wood,
+            Resource::Stone => self.stone,
+            Resource::Metal => self.metal,
+        }
+    }
+
+    fn slot(&mut self, resource: Resource) -> &mut u32 {
+        match resource {
+            Resource::Food => &mut self.food,
+            Resource::Wood => &mut self.wood,
+            Resource::Stone => &mut self.stone,
+            Resource::Metal => &mut self.metal,
+        }
+    }
+
+    /// Guarda recursos, respeitando o limite do estoque
+    pub fn add(&mut self, resource: Resource, amount: u32) {
+        let slot = self.slot(resource);
+        *slot = slot.saturating_add(amount).min(STOCKPILE_CAP);
+    }
+
+    /// Retira até `amount`; retorna quanto foi de fato retirado
+    pub fn take(&mut self, resource: Resource, amount: u32) -> u32 {
+        let slot = self.slot(resource);
+        let taken = amount.min(*slot);
+        *slot -= taken;
+        taken
+    }
+}
+
+impl Site {
+    /// Quanto a população consome de um recurso por ciclo
+    pub fn upkeep(&self, resource: Resource) -> u32 {
+        let population = self.population as u32;
+        match resource {
+            Resource::Food => population.div_ceil(2),
+            Resource::Wood => population / 10,
+            Resource::Stone | Resource::Metal => 0,
+        }
+    }
+
+    /// Reserva que o local quer manter de um recurso
+    pub fn demand(&self, resource: Resource) -> u32 {
+        match resource {
+            Resource::Food | Resource::Wood => self.upkeep(resource) * RESERVE_CYCLES,
+            // Pedra e metal servem a obras e ferramentas
+            Resource::Stone | Resource::Metal => self.population as u32 * 2,
+        }
+    }
+
+    /// Preço local: sobe com a escassez e cai com a fartura
+    pub fn price(&self, resource: Resource) -> u32 {
+        let base = resource.base_price();
+        let demand = self.demand(resource);
+        let stock = self.stockpile.get(resource).max(1);
+        (base * demand / stock).clamp((base / 4).max(1), base * 4)
+    }
+
+    /// Falta grave: o estoque cobre menos de um quarto da reserva desejada
+    pub fn is_short_of(&self, resource: Resource) -> bool {
+        self.is_inhabited() && self.stockpile.get(resource) * 4 < self.demand(resource)
+    }
+
+    /// Quanto falta para completar a reserva
+    pub fn need(&self, resource: Resource) -> u32 {
+        self.demand(resource).saturating_sub(self.stockpile.get(resource))
+    }
+
+    /// Quanto sobra além da reserva
+    pub fn surplus(&self, resource: Resource) -> u32 {
+        self.stockpile.get(resource).saturating_sub(self.demand(resource))
+    }
+
+    /// Recurso em falta mais urgente
+    pub fn worst_shortage(&self) -> Option<Resource> {
+        Resource::ALL.into_iter().find(|r| self.is_short_of(*r))
+    }
+}
+
+/// Decisão de um local diante da escassez
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EconomicIntent {
+    /// Comprar de um local amigo com sobra
+    Trade { partner: u32, resource: Resource },
+    /// Tomar à força de um local inimigo
+    Raid { target: u32, resource: Resource },
+    /// Parte dos moradores parte em busca de terras melhores
+    Migrate,
+}
+
+impl World {
+    /// Ciclos econômicos: cada assentamento habitado produz a partir do
+    /// terreno ao redor e consome conforme a população; a fome reduz a
+    /// população. Vários ciclos podem ser resolvidos de uma vez.
+    pub(crate) fn run_economy(&mut self, cycles: u32) {
+        let mut site_ids: Vec<u32> = self
+            .sites
+            .values()
+            .filter(|s| s.is_inhabited() && s.owner.is_some())
+            .map(|s| s.id)
+            .collect();
+        site_ids.sort_unstable();
+
+        for site_id in site_ids {
+            let (center, population) = {
+                let site = &self.sites[&site_id];
+                (site.center, site.population)
+            };
+            let production = self.production_around(center, population);
+
+            let Some(site) = self.sites.get_mut(&site_id) else {
+                continue;
+            };
+            let mut hungry = 0;
+            for (resource, produced) in production {
+                let upkeep = site.upkeep(resource);
+                if produced >= upkeep {
+                    site.stockpile.add(resource, (produced - upkeep) * cycles);
+                    continue;
+                }
+                let missing = (upkeep - produced) * cycles;
+                let eaten = site.stockpile.take(resource, missing);
+                if resource == Resource::Food {
+                    hungry = (missing - eaten) / cycles.max(1);
+                }
+            }
+            if hungry > 0 {
+                // Cada unidade de comida alimenta duas pessoas; um décimo dos famintos perece ou parte
+                let lost = (hungry as usize * 2 / 10).max(1) * cycles as usize;
+                if site.population > STARVATION_FLOOR {
+                    site.population = site.population.saturating_sub(lost).max(STARVATION_FLOOR);
+                }
+            }
+            let owner = site.owner;
+            self.refresh_member_count(owner);
+        }
+    }
+
+    /// Produção do território de um local; o rendimento cresce com a
+    /// mão de obra até haver uma pessoa por tile
+    fn production_around(&self, center: Position, population: usize) -> Vec<(Resource, u32)> {
+        let tiles = self.positions_around(center, SITE_CLAIM_RADIUS);
+        let workers = population.min(tiles.len()) as u32;
+        let mut tenths = [0u32; 4];
+        for pos in &tiles {
+            let Some(tile) = self.terrain_layer.get(pos) else {
+                continue;
+            };
+            // Rendimento do terreno nos raws, em décimos de unidade
+            let Some(def) = self.raws.terrain(tile.terrain.id()) else {
+                continue;
+            };
+            for (resource, amount) in &def.yields {
+                tenths[*resource as usize] += amount;
+            }
+        }
+
+        [Resource::Food, Resource::Wood, Resource::Stone, Resource::Metal]
+            .into_iter()
+            .map(|r| (r, tenths[r as usize] * workers / tiles.len().max(1) as u32 / 10))
+            .collect()
+    }
+
+    /// Decide o que um local faz diante da sua falta mais urgente
+    pub fn economic_intent(&self, site_id: u32) -> Option<EconomicIntent> {
+        let site = self.sites.get(&site_id)?;
+        let owner = site.owner?;
+        let resource = site.worst_shortage()?;
+        // Uma caravana já está a caminho: espera por ela
+        if self.caravans.values().any(|c| c.destination == site_id) {
+            return None;
+        }
+
+        let relation = |other: Option<u32>| match other {
+            Some(other) if other == owner => Some(Relation::Allied),
+            Some(other) => self.factions.get(&owner).and_then(|f| f.relations.get(&other).copied()),
+            None => None,
+        };
+        let candidates = self.get_sites().into_iter().filter(|s| s.id != site_id && s.is_inhabited());
+
+        let partner = candidates
+            .clone()
+            .filter(|s| matches!(relation(s.owner), Some(Relation::Allied | Relation::Friendly | Relation::Neutral)))
+            .filter(|s| s.surplus(resource) > 0)
+            .max_by_key(|s| (s.surplus(resource), std::cmp::Reverse(s.id)));
+        if let Some(partner) = partner {
+            if self.best_payment(site, resource).is_some() {
+                return Some(EconomicIntent::Trade { partner: partner.id, resource });
+            }
+        }
+
+        // Só se saqueia o que falta para viver, e contra neutros só a fome
+        // leva ao ataque
+        let target = candidates
+            .filter(|s| {
+                matches!(
+                    (relation(s.owner), resource),
+                    (Some(Relation::War | Relation::Hostile), Resource::Food | Resource::Wood)
+                        | (Some(Relation::Neutral), Resource::Food)
+                )
+            })
+            .filter(|s| s.stockpile.get(resource) > 0)
+            .max_by_key(|s| (s.stockpile.get(resource), std::cmp::Reverse(s.id)));
+        if let Some(target) = target {
+            return Some(EconomicIntent::Raid { target: target.id, resource });
+        }
+
+        (resource == Resource::Food).then_some(EconomicIntent::Migrate)
+    }
+
+    /// Recurso com o qual o local paga uma compra (o de maior sobra)
+    fn best_payment(&self, site: &Site, wanted: Resource) -> Option<Resource> {
+        Resource::ALL
+            .into_iter()
+            .filter(|r| *r != wanted && site.surplus(*r) > 0)
+            .max_by_key(|r| site.surplus(*r) * r.base_price())
+    }
+
+    /// Cada local em falta age conforme sua decisão econômica
+    pub(crate) fn resolve_shortages(&mut self) {
+        let mut site_ids: Vec<u32> = self.sites.values().filter(|s| s.is_inhabited()).map(|s| s.id).collect();
+        site_ids.sort_unstable();
+
+        for site_id in site_ids {
+            match self.economic_intent(site_id) {
+                Some(EconomicIntent::Trade { partner, resource }) => self.trade(site_id, partner, resource),
+                Some(EconomicIntent::Raid { target, resource }) => self.raid(site_id, target, resource),
+                Some(EconomicIntent::Migrate) => {
+                    let Some(site) = self.sites.get(&site_id) else {
+                        continue;
+                    };
+                    let (Some(owner), settlers) = (site.owner, site.population / 3) else {
+                        continue;
+                    };
+                    if let Some(colony) = self.found_colony(owner, site_id, settlers) {
+                        let location = self.sites[&colony].center;
+                        self.report_happening(Happening::Migration {
+                            faction_id: owner,
+                            leader: None,
+                            location,
+                        });
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Compra do parceiro o que falta, pagando em valor pelos preços dele;
+    /// o pagamento é feito na hora e a mercadoria segue por caravana
+    pub fn trade(&mut self, buyer: u32, seller: u32, resource: Resource) {
+        let (Some(buyer_site), Some(seller_site)) = (self.sites.get(&buyer), self.sites.get(&seller)) else {
+            return;
+        };
+        let Some(payment) = self.best_payment(buyer_site, resource) else {
+            return;
+        };
+        let price = seller_site.price(resource);
+        let payment_price = seller_site.price(payment);
+        let affordable = buyer_site.surplus(payment) * payment_price / price;
+        let amount = buyer_site.need(resource).min(seller_site.surplus(resource)).min(affordable);
+        if amount == 0 {
+            return;
+        }
+        let paid = (amount * price).div_ceil(payment_price);
+
+        let mut cargo = Stockpile::default();
+        if let Some(site) = self.sites.get_mut(&seller) {
+            cargo.add(resource, site.stockpile.take(resource, amount));
+        }
+        if self.dispatch_caravan(seller, buyer, cargo.clone()).is_none() {
+            // Sem caminho entre os dois, a mercadoria volta ao estoque
+            if let Some(site) = self.sites.get_mut(&seller) {
+                site.stockpile.add(resource, cargo.get(resource));
+            }
+            return;
+        }
+        if let Some(site) = self.sites.get_mut(&buyer) {
+            site.stockpile.take(payment, paid);
+        }
+        if let Some(site) = self.sites.get_mut(&seller) {
+            site.stockpile.add(payment, paid);
+        }
+    }
+
+    /// Saqueia metade do estoque de um local; a vítima passa a ser hostil
+    pub fn raid(&mut self, raider_site: u32, target: u32, resource: Resource) {
+        let (Some(raider), Some(victim)) = (self.sites.get(&raider_site), self.sites.get(&target)) else {
+            return;
+        };
+        let (Some(raider_faction), Some(victim_faction)) = (raider.owner, victim.owner) else {
+            return;
+        };
+        let amount = raider.need(resource).min(victim.stockpile.get(resource).div_ceil(2));
+        let location = victim.center;
+
+        if let Some(site) = self.sites.get_mut(&target) {
+            site.stockpile.take(resource, amount);
+        }
+        if let Some(site) = self.sites.get_mut(&raider_site) {
+            site.stockpile.add(resource, amount);
+        }
+        if let Some(faction) = self.factions.get(&victim_faction) {
+            if !matches!(faction.relations.get(&raider_faction), Some(Relation::War)) {
+                self.set_relation(raider_faction, victim_faction, Relation::Hostile);
+            }
+        }
+        self.report_happening(Happening::Raid {
+            raider: raider_faction,
+            victim: victim_faction,
+            site_id: target,
+            resource,
+            location,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SiteKind;
+
+    fn world_with_factions() -> (World, u32, u32) {
+        let mut world = World::new(60, 60);
+        let a = world.add_faction("The River Kingdom", FactionType::new("human"));
+        let b = world.add_faction("The Copper Brotherhood", FactionType::new("dwarf"));
+        (world, a, b)
+    }
+
+    #[test]
+    fn test_prices_follow_scarcity() {
+        let mut world = World::new(40, 40);
+        let id = world.found_site("Ashford".to_string(), SiteKind::Town, None, Position::new(10, 10), 40);
+        let site = world.get_site(id).unwrap().clone();
+
+        let mut scarce = site.clone();
+        scarce.stockpile.food = 10;
+        let mut plenty = site;
+        plenty.stockpile.food = 1000;
+        assert!(scarce.price(Resource::Food) > plenty.price(Resource::Food));
+        assert!(scarce.is_short_of(Resource::Food));
+        assert!(!plenty.is_short_of(Resource::Food));
+    }
+
+    #[test]
+    fn test_grasslands_feed_a_town() {
+        let (mut world, owner, _) = world_with_factions();
+        let id = world.found_site("Ashford".to_string(), SiteKind::Town, Some(owner), Position::new(10, 10), 40);
+        let before = world.get_site(id).unwrap().stockpile.clone();
+
+        for _ in 0..ECONOMY_INTERVAL * 5 {
+            world.tick();
+        }
+
+        let site = world.get_site(id).unwrap();
+        assert!(site.stockpile.food > before.food);
+        assert!(site.stockpile.wood > before.wood);
+        assert_eq!(site.stockpile.metal, before.metal);
+        assert_eq!(site.population, 40);
+    }
+
+    #[test]
+    fn test_hunger_leads_to_trade_or_raid() {
+        let (mut world, a, b) = world_with_factions();
+        let hungry = world.found_site("Ashford".to_string(), SiteKind::Town, Some(a), Position::new(10, 10), 40);
+        let rich = world.found_site("Deephold".to_string(), SiteKind::Fortress, Some(b), Position::new(40, 40), 40);
+        world.sites.get_mut(&hungry).unwrap().stockpile = Stockpile::default();
+        world.sites.get_mut(&rich).unwrap().stockpile.food = 1500;
+
+        world.set_relation(a, b, Relation::Friendly);
+        // Sem nada para pagar não há comércio, e contra amigos não há saque
+        assert_eq!(world.economic_intent(hungry), Some(EconomicIntent::Migrate));
+
+        world.sites.get_mut(&hungry).unwrap().stockpile.metal = 500;
+        assert_eq!(
+            world.economic_intent(hungry),
+            Some(EconomicIntent::Trade { partner: rich, resource: Resource::Food })
+        );
+        world.resolve_shortages();
+        assert!(world.get_site(rich).unwrap().stockpile.metal > 20);
+        assert_eq!(world.get_caravans().len(), 1);
+        assert_eq!(world.economic_intent(hungry), None, "espera a caravana chegar");
+        while !world.get_caravans().is_empty() {
+            world.update_caravans(1);
+        }
+        assert!(world.get_site(hungry).unwrap().stockpile.food > 0);
+
+        world.sites.get_mut(&hungry).unwrap().stockpile = Stockpile::default();
+        world.set_relation(a, b, Relation::Neutral);
+        world.resolve_shortages();
+        assert!(world.get_site(hungry).unwrap().stockpile.food > 0);
+        assert_eq!(world.get_faction(a).unwrap().relations[&b], Relation::Hostile);
+        let raid = world.get_historical_events(1).pop().unwrap();
+        assert_eq!(raid.event_type, EventType::Raid);
+        assert_eq!(raid.description, "The River Kingdom raided Deephold for food");
+    }
+}
@@ -0,0 +1,203 @@
+use crate::caravans::distance;
+use crate::{World, TICKS_PER_YEAR};
+use common::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Raio em que entidades com IA presenciam uma morte
+pub const WITNESS_RADIUS: i32 = 8;
+/// Lembranças guardadas por entidade; as mais antigas são esquecidas
+pub const MAX_MEMORIES: usize = 20;
+
+/// Acontecimento publicado pelos sistemas do mundo
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum WorldEvent {
+    EntitySpawned {
+        entity_id: u32,
+        name: String,
+        pos: Position,
+        entity_type: EntityType,
+    },
+    EntityDied {
+        entity_id: u32,
+        name: String,
+        killer: Option<u32>,
+        location: Position,
+    },
+    CaravanDeparted {
+        caravan: u32,
+        origin: u32,
+        destination: u32,
+    },
+    CaravanArrived {
+        caravan: u32,
+        origin: u32,
+        destination: u32,
+    },
+    /// Um acontecimento entrou para a história
+    HistoryRecorded { event_id: u64, description: String },
+}
+
+/// Reação de um sistema a um evento
+type Subscriber = fn(&mut World, &WorldEvent);
+
+/// Assinantes, na ordem em que reagem a cada evento
+const SUBSCRIBERS: [Subscriber; 2] = [count_faction_losses, remember_death];
+
+/// Fila de eventos do mundo. Durante um tick os eventos se acumulam e são
+/// entregues na fase de despacho, no fim do tick; fora dele, na hora.
+#[derive(Default)]
+pub struct EventBus {
+    queue: VecDeque<WorldEvent>,
+    deferred: bool,
+    /// Destino dos eventos repassados ao painel de admin
+    forward: Option<Forward>,
+}
+
+/// Quem recebe os eventos do mundo vistos de fora dele
+type Forward = Box<dyn Fn(GameEvent) + Send + Sync>;
+
+impl World {
+    /// Repassa os eventos relevantes do mundo, já como `GameEvent`
+    pub fn forward_events_to(&mut self, forward: impl Fn(GameEvent) + Send + Sync + 'static) {
+        self.events.forward = Some(Box::new(forward));
+    }
+
+    /// Uma entidade com IA guarda uma lembrança; sem IA, não lembra de
+    /// nada
+    pub(crate) fn remember(&mut self, entity_id: u32, event: String) {
+        let tick = self.current_tick;
+        if let Some(behavior) = self.ai_behaviors.get_mut(&entity_id) {
+            if behavior.memory.len() == MAX_MEMORIES {
+                behavior.memory.remove(0);
+            }
+            behavior.memory.push(Memory { event, tick });
+        }
+    }
+
+    /// Publica um evento para os assinantes
+    pub(crate) fn publish(&mut self, event: WorldEvent) {
+        self.events.queue.push_back(event);
+        if !self.events.deferred {
+            self.dispatch_events();
+        }
+    }
+
+    /// Segura os eventos até a próxima fase de despacho
+    pub(crate) fn defer_events(&mut self) {
+        self.events.deferred = true;
+    }
+
+    /// Fase de despacho: entrega os eventos pendentes, inclusive os
+    /// publicados pelos próprios assinantes
+    pub(crate) fn dispatch_events(&mut self) {
+        self.events.deferred = true;
+        while let Some(event) = self.events.queue.pop_front() {
+            for subscriber in SUBSCRIBERS {
+                subscriber(self, &event);
+            }
+            if let (Some(forward), Some(forwarded)) = (&self.events.forward, self.to_game_event(&event)) {
+                forward(forwarded);
+            }
+        }
+        self.events.deferred = false;
+    }
+
+    /// Evento visto de fora do mundo, para os eventos que interessam ao
+    /// painel de admin
+    fn to_game_event(&self, event: &WorldEvent) -> Option<GameEvent> {
+        match event {
+            WorldEvent::EntitySpawned {
+                name,
+                pos,
+                entity_type,
+                ..
+            } if *entity_type != EntityType::Player => Some(GameEvent::EntitySpawned {
+                name: name.clone(),
+                pos: *pos,
+            }),
+            WorldEvent::EntityDied { name, location, .. } => Some(GameEvent::EntityDied {
+                name: name.clone(),
+                pos: *location,
+            }),
+            WorldEvent::CaravanDeparted { origin, destination, .. } => Some(GameEvent::CaravanDeparted {
+                origin: self.site_name(*origin),
+                destination: self.site_name(*destination),
+            }),
+            WorldEvent::CaravanArrived { origin, destination, .. } => Some(GameEvent::CaravanArrived {
+                origin: self.site_name(*origin),
+                destination: self.site_name(*destination),
+            }),
+            WorldEvent::HistoryRecorded { description, .. } => Some(GameEvent::HistoryRecorded {
+                year: self.current_tick / TICKS_PER_YEAR,
+                description: description.clone(),
+            }),
+            WorldEvent::EntitySpawned { .. } => None,
+        }
+    }
+}
+
+/// Um membro morto dentro de um local da sua fação deixa a população
+fn count_faction_losses(world: &mut World, event: &WorldEvent) {
+    let WorldEvent::EntityDied { entity_id, location, .. } = event else {
+        return;
+    };
+    let Some(faction) = world.historical_figures.get(entity_id).and_then(|f| f.faction_id) else {
+        return;
+    };
+    let Some(site) = world.site_at(*location).filter(|s| s.owner == Some(faction)).map(|s| s.id) else {
+        return;
+    };
+    if let Some(site) = world.sites.get_mut(&site) {
+        site.population = site.population.saturating_sub(1);
+    }
+    world.refresh_member_count(Some(faction));
+}
+
+/// Entidades com IA por perto se lembram da morte
+fn remember_death(world: &mut World, event: &WorldEvent) {
+    let WorldEvent::EntityDied { name, location, .. } = event else {
+        return;
+    };
+    let mut witnesses: Vec<u32> = world
+        .ai_behaviors
+        .keys()
+        .filter(|id| {
+            world
+                .entity_layer
+                .get(id)
+                .is_some_and(|e| distance(e.pos, *location) <= WITNESS_RADIUS)
+        })
+        .copied()
+        .collect();
+    witnesses.sort_unstable();
+
+    for id in witnesses {
+        world.remember(id, format!("Saw {} die", name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_death_reaches_witnesses_and_admin_stream() {
+        let mut world = World::new(40, 40);
+        let forwarded = Arc::new(Mutex::new(Vec::new()));
+        let sink = forwarded.clone();
+        world.forward_events_to(move |event| sink.lock().unwrap().push(event));
+
+        let victim = world.spawn_entity("Urist".to_string(), Position::new(6, 6), EntityType::NPC).unwrap();
+        world.kill_entity(victim, None);
+
+        // NPC_0 está em (5, 5), bem perto
+        let witness = world.ai_behaviors.values().find(|b| !b.memory.is_empty()).unwrap();
+        assert_eq!(witness.memory[0].event, "Saw Urist die");
+
+        let forwarded = forwarded.lock().unwrap();
+        assert!(matches!(&forwarded[0], GameEvent::EntitySpawned { name, .. } if name == "Urist"));
+        assert!(forwarded.iter().any(|e| matches!(e, GameEvent::EntityDied { name, .. } if name == "Urist")));
+    }
+}
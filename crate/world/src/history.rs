@@ -0,0 +1,562 @@
+use crate::journal::Mutation;
+use crate::{SiteState, World, WorldEvent};
+use common::*;
+
+/// Acontecimento notável reportado por um sistema do mundo.
+///
+/// Sistemas não escrevem `HistoricalEvent` diretamente: reportam o que
+/// aconteceu e o `HistoryRecorder` decide se entra para a história.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Happening {
+    Death {
+        victim: u32,
+        killer: Option<u32>,
+        location: Position,
+    },
+    Fight {
+        attacker: u32,
+        defender: u32,
+        location: Position,
+    },
+    Founding {
+        faction_id: u32,
+        founder: Option<u32>,
+        location: Position,
+    },
+    Conquest {
+        conqueror_faction: u32,
+        defeated_faction: u32,
+        location: Position,
+    },
+    /// Batalha entre fações por um local, resolvida sem simular cada
+    /// combatente
+    Battle {
+        attacker: u32,
+        defender: u32,
+        site_id: u32,
+        attacker_won: bool,
+        location: Position,
+    },
+    Theft {
+        thief: u32,
+        victim: u32,
+        item: String,
+        location: Position,
+    },
+    Migration {
+        faction_id: u32,
+        leader: Option<u32>,
+        location: Position,
+    },
+    WarDeclared {
+        aggressor: u32,
+        defender: u32,
+        location: Position,
+    },
+    Peace {
+        faction_a: u32,
+        faction_b: u32,
+        location: Position,
+    },
+    Alliance {
+        faction_a: u32,
+        faction_b: u32,
+        location: Position,
+    },
+    Succession {
+        faction_id: u32,
+        heir: u32,
+        location: Position,
+    },
+    Abandonment {
+        faction_id: u32,
+        site_id: u32,
+        location: Position,
+    },
+    Burning {
+        site_id: u32,
+        faction: Option<u32>,
+        location: Position,
+    },
+    Ruination {
+        site_id: u32,
+        location: Position,
+    },
+    Ambush {
+        attacker: u32,
+        caravan_owner: Option<u32>,
+        leader: u32,
+        escort: Option<u32>,
+        repelled: bool,
+        location: Position,
+    },
+    Raid {
+        raider: u32,
+        victim: u32,
+        site_id: u32,
+        resource: Resource,
+        location: Position,
+    },
+    RoadBuilt {
+        faction: Option<u32>,
+        from_site: u32,
+        to_site: u32,
+        location: Position,
+    },
+}
+
+impl Happening {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            Happening::Death { .. } => EventType::Death,
+            Happening::Fight { .. } => EventType::Combat,
+            Happening::Founding { .. } => EventType::FactionFounded,
+            Happening::Conquest { .. } => EventType::TerritoryConquered,
+            Happening::Battle { .. } => EventType::Combat,
+            Happening::Theft { .. } => EventType::Theft,
+            Happening::Migration { .. } => EventType::Migration,
+            Happening::WarDeclared { .. } => EventType::War,
+            Happening::Peace { .. } => EventType::Peace,
+            Happening::Alliance { .. } => EventType::Alliance,
+            Happening::Succession { .. } => EventType::Succession,
+            Happening::Abandonment { .. } => EventType::Abandonment,
+            Happening::Burning { .. } => EventType::SiteBurned,
+            Happening::Ruination { .. } => EventType::SiteRuined,
+            Happening::Raid { .. } => EventType::Raid,
+            Happening::Ambush { .. } => EventType::Ambush,
+            Happening::RoadBuilt { .. } => EventType::RoadBuilt,
+        }
+    }
+
+    pub fn location(&self) -> Position {
+        match self {
+            Happening::Death { location, .. }
+            | Happening::Fight { location, .. }
+            | Happening::Founding { location, .. }
+            | Happening::Conquest { location, .. }
+            | Happening::Battle { location, .. }
+            | Happening::Theft { location, .. }
+            | Happening::Migration { location, .. }
+            | Happening::WarDeclared { location, .. }
+            | Happening::Peace { location, .. }
+            | Happening::Alliance { location, .. }
+            | Happening::Succession { location, .. }
+            | Happening::Abandonment { location, .. }
+            | Happening::Burning { location, .. }
+            | Happening::Ruination { location, .. }
+            | Happening::Raid { location, .. }
+            | Happening::Ambush { location, .. }
+            | Happening::RoadBuilt { location, .. } => *location,
+        }
+    }
+
+    /// Entidades envolvidas (fações não entram como participantes)
+    pub fn participants(&self) -> Vec<u32> {
+        match self {
+            Happening::Death { victim, killer, .. } => {
+                let mut ids = vec![*victim];
+                ids.extend(killer);
+                ids
+            }
+            Happening::Fight { attacker, defender, .. } => vec![*attacker, *defender],
+            Happening::Founding { founder, .. } => founder.iter().copied().collect(),
+            Happening::Theft { thief, victim, .. } => vec![*thief, *victim],
+            Happening::Migration { leader, .. } => leader.iter().copied().collect(),
+            Happening::Succession { heir, .. } => vec![*heir],
+            Happening::Ambush { leader, escort, .. } => {
+                let mut ids = vec![*leader];
+                ids.extend(escort);
+                ids
+            }
+            Happening::Conquest { .. }
+            | Happening::Battle { .. }
+            | Happening::WarDeclared { .. }
+            | Happening::Peace { .. }
+            | Happening::Alliance { .. }
+            | Happening::Abandonment { .. }
+            | Happening::Burning { .. }
+            | Happening::Ruination { .. }
+            | Happening::Raid { .. }
+            | Happening::RoadBuilt { .. } => Vec::new(),
+        }
+    }
+
+    /// Fações citadas diretamente pelo acontecimento
+    pub fn factions(&self) -> Vec<u32> {
+        match self {
+            Happening::Founding { faction_id, .. }
+            | Happening::Migration { faction_id, .. }
+            | Happening::Succession { faction_id, .. }
+            | Happening::Abandonment { faction_id, .. } => vec![*faction_id],
+            Happening::Conquest { conqueror_faction, defeated_faction, .. } => {
+                vec![*conqueror_faction, *defeated_faction]
+            }
+            Happening::Burning { faction, .. } | Happening::RoadBuilt { faction, .. } => {
+                faction.iter().copied().collect()
+            }
+            Happening::WarDeclared { aggressor, defender, .. } | Happening::Battle { attacker: aggressor, defender, .. } => {
+                vec![*aggressor, *defender]
+            }
+            Happening::Raid { raider, victim, .. } => vec![*raider, *victim],
+            Happening::Ambush { attacker, caravan_owner, .. } => {
+                let mut ids = vec![*attacker];
+                ids.extend(caravan_owner);
+                ids
+            }
+            Happening::Peace { faction_a, faction_b, .. }
+            | Happening::Alliance { faction_a, faction_b, .. } => vec![*faction_a, *faction_b],
+            Happening::Death { .. }
+            | Happening::Fight { .. }
+            | Happening::Theft { .. }
+            | Happening::Ruination { .. } => Vec::new(),
+        }
+    }
+
+    /// Peso base do tipo de acontecimento
+    fn base_significance(&self) -> u32 {
+        match self {
+            Happening::Conquest { .. } => 80,
+            Happening::Battle { .. } => 50,
+            Happening::Founding { .. } => 70,
+            Happening::WarDeclared { .. } => 70,
+            Happening::Peace { .. } => 50,
+            Happening::Alliance { .. } => 50,
+            Happening::Migration { .. } => 40,
+            Happening::Succession { .. } => 30,
+            Happening::Abandonment { .. } => 40,
+            Happening::Burning { .. } => 60,
+            Happening::Ruination { .. } => 35,
+            Happening::Raid { .. } => 45,
+            Happening::Ambush { .. } => 45,
+            Happening::RoadBuilt { .. } => 30,
+            Happening::Death { .. } => 40,
+            Happening::Fight { .. } => 15,
+            Happening::Theft { .. } => 15,
+        }
+    }
+}
+
+/// Filtra acontecimentos por relevância antes de virarem história
+#[derive(Debug, Clone)]
+pub struct HistoryRecorder {
+    /// Relevância mínima para um acontecimento ser registrado
+    pub min_significance: u32,
+}
+
+impl HistoryRecorder {
+    pub const DEFAULT_MIN_SIGNIFICANCE: u32 = 30;
+
+    pub fn new(min_significance: u32) -> Self {
+        Self { min_significance }
+    }
+}
+
+impl Default for HistoryRecorder {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MIN_SIGNIFICANCE)
+    }
+}
+
+/// Peso extra de um participante conforme o tipo de entidade
+fn participant_weight(entity_type: EntityType) -> u32 {
+    match entity_type {
+        EntityType::Player => 30,
+        EntityType::NPC => 10,
+        EntityType::Animal => 0,
+    }
+}
+
+/// Peso extra de participantes que são figuras históricas
+const FIGURE_WEIGHT: u32 = 20;
+
+impl World {
+    /// Ponto único de entrada para sistemas reportarem acontecimentos.
+    ///
+    /// Retorna o ID do evento histórico criado, ou `None` se o
+    /// acontecimento não foi relevante o suficiente.
+    pub fn report_happening(&mut self, happening: Happening) -> Option<u64> {
+        if self.significance(&happening) < self.history.min_significance {
+            return None;
+        }
+
+        let participants = happening.participants();
+        let mut factions = happening.factions();
+        for id in &participants {
+            let faction = self.historical_figures.get(id).and_then(|f| f.faction_id);
+            if let Some(faction) = faction {
+                if !factions.contains(&faction) {
+                    factions.push(faction);
+                }
+            }
+        }
+
+        let event = HistoricalEvent {
+            id: self.next_event_id,
+            tick: self.current_tick,
+            event_type: happening.event_type(),
+            participants,
+            factions,
+            location: happening.location(),
+            description: self.describe(&happening),
+        };
+        self.record(|| Mutation::EventRecorded { event: event.clone() });
+
+        let id = event.id;
+        let description = event.description.clone();
+        self.store_event(event);
+        self.publish(WorldEvent::HistoryRecorded { event_id: id, description });
+        Some(id)
+    }
+
+    /// Guarda um evento já numerado na história e nos índices
+    pub(crate) fn store_event(&mut self, event: HistoricalEvent) {
+        // O local onde aconteceu guarda a lembrança, mesmo depois de ruir
+        let site_id = self.site_at(event.location).map(|s| s.id);
+        if let Some(site) = site_id.and_then(|id| self.sites.get_mut(&id)) {
+            site.history.push(event.id);
+        }
+
+        self.next_event_id = event.id + 1;
+        self.history_index.insert(&event, self.historical_events.len());
+        self.historical_events.push(event);
+    }
+
+    /// Calcula a relevância de um acontecimento
+    pub fn significance(&self, happening: &Happening) -> u32 {
+        let participants: u32 = happening
+            .participants()
+            .iter()
+            .map(|id| self.participant_significance(*id))
+            .sum();
+
+        happening.base_significance() + participants
+    }
+
+    fn participant_significance(&self, id: u32) -> u32 {
+        let figure = self.historical_figures.get(&id);
+        let entity_type = self
+            .entity_layer
+            .get(&id)
+            .map(|e| e.entity_type)
+            .or(figure.map(|f| f.entity_type));
+
+        entity_type.map(participant_weight).unwrap_or(0)
+            + figure.map(|_| FIGURE_WEIGHT).unwrap_or(0)
+    }
+
+    /// Gera a descrição em prosa de um acontecimento
+    fn describe(&self, happening: &Happening) -> String {
+        let place = self.place_phrase(happening.location());
+
+        match happening {
+            Happening::Death { victim, killer: Some(killer), .. } => format!(
+                "{} was slain by {} {}",
+                self.entity_name(*victim),
+                self.entity_name(*killer),
+                place
+            ),
+            Happening::Death { victim, killer: None, .. } => {
+                format!("{} died {}", self.entity_name(*victim), place)
+            }
+            Happening::Fight { attacker, defender, .. } => format!(
+                "{} attacked {} {}",
+                self.entity_name(*attacker),
+                self.entity_name(*defender),
+                place
+            ),
+            Happening::Founding { faction_id, founder: Some(founder), .. } => format!(
+                "{} founded {} {}",
+                self.entity_name(*founder),
+                self.faction_name(*faction_id),
+                place
+            ),
+            Happening::Founding { faction_id, founder: None, .. } => {
+                format!("{} was founded {}", self.faction_name(*faction_id), place)
+            }
+            Happening::Conquest { conqueror_faction, defeated_faction, .. } => format!(
+                "{} conquered the lands of {} {}",
+                self.faction_name(*conqueror_faction),
+                self.faction_name(*defeated_faction),
+                place
+            ),
+            Happening::Battle { attacker, defender, site_id, attacker_won: true, .. } => format!(
+                "{} routed the defenders of {} at {}",
+                self.faction_name(*attacker),
+                self.faction_name(*defender),
+                self.site_name(*site_id)
+            ),
+            Happening::Battle { attacker, defender, site_id, attacker_won: false, .. } => format!(
+                "{} held {} against {}",
+                self.faction_name(*defender),
+                self.site_name(*site_id),
+                self.faction_name(*attacker)
+            ),
+            Happening::Theft { thief, victim, item, .. } => format!(
+                "{} stole {} from {} {}",
+                self.entity_name(*thief),
+                item,
+                self.entity_name(*victim),
+                place
+            ),
+            Happening::Migration { faction_id, leader: Some(leader), .. } => format!(
+                "{} led settlers of {} to new lands {}",
+                self.entity_name(*leader),
+                self.faction_name(*faction_id),
+                place
+            ),
+            Happening::Migration { faction_id, leader: None, .. } => format!(
+                "Settlers of {} migrated to new lands {}",
+                self.faction_name(*faction_id),
+                place
+            ),
+            Happening::WarDeclared { aggressor, defender, .. } => format!(
+                "{} declared war on {}",
+                self.faction_name(*aggressor),
+                self.faction_name(*defender)
+            ),
+            Happening::Peace { faction_a, faction_b, .. } => format!(
+                "{} and {} made peace",
+                self.faction_name(*faction_a),
+                self.faction_name(*faction_b)
+            ),
+            Happening::Alliance { faction_a, faction_b, .. } => format!(
+                "{} and {} swore an alliance",
+                self.faction_name(*faction_a),
+                self.faction_name(*faction_b)
+            ),
+            Happening::Succession { faction_id, heir, .. } => format!(
+                "{} rose to lead {}",
+                self.entity_name(*heir),
+                self.faction_name(*faction_id)
+            ),
+            Happening::Abandonment { faction_id, site_id, .. } => format!(
+                "{} abandoned {}",
+                self.faction_name(*faction_id),
+                self.site_name(*site_id)
+            ),
+            Happening::Burning { site_id, faction: Some(faction), .. } => format!(
+                "{} put {} to the torch",
+                self.faction_name(*faction),
+                self.site_name(*site_id)
+            ),
+            Happening::Burning { site_id, faction: None, .. } => {
+                format!("{} burned to the ground", self.site_name(*site_id))
+            }
+            Happening::Ruination { site_id, .. } => {
+                format!("The last walls of {} crumbled into ruin", self.site_name(*site_id))
+            }
+            Happening::Ambush { attacker, caravan_owner, escort, repelled, .. } => {
+                let caravan = match caravan_owner {
+                    Some(owner) => format!("a caravan of {}", self.faction_name(*owner)),
+                    None => "a caravan".to_string(),
+                };
+                match (escort, repelled) {
+                    (Some(escort), true) => format!(
+                        "{} drove off {} from {} {}",
+                        self.entity_name(*escort),
+                        self.faction_name(*attacker),
+                        caravan,
+                        place
+                    ),
+                    _ => format!("{} ambushed {} {}", self.faction_name(*attacker), caravan, place),
+                }
+            }
+            Happening::Raid { raider, site_id, resource, .. } => format!(
+                "{} raided {} for {}",
+                self.faction_name(*raider),
+                self.site_name(*site_id),
+                resource.name()
+            ),
+            Happening::RoadBuilt { from_site, to_site, .. } => format!(
+                "A road was laid between {} and {}",
+                self.site_name(*from_site),
+                self.site_name(*to_site)
+            ),
+        }
+    }
+
+    /// Onde aconteceu: o nome do local, se houver, senão o terreno
+    fn place_phrase(&self, pos: Position) -> String {
+        match self.site_at(pos) {
+            Some(site) if site.state == SiteState::Ruined => format!("in the ruins of {}", site.name),
+            Some(site) => format!("at {}", site.name),
+            None => self
+                .terrain_layer
+                .get(&pos)
+                .and_then(|t| self.raws.terrain(t.terrain.id()))
+                .map(|t| t.phrase.clone())
+                .unwrap_or_else(|| "in the wilds".to_string()),
+        }
+    }
+
+    fn entity_name(&self, id: u32) -> String {
+        self.entity_layer
+            .get(&id)
+            .map(|e| e.name.clone())
+            .or_else(|| self.historical_figures.get(&id).map(|f| f.name.clone()))
+            .unwrap_or_else(|| "someone forgotten".to_string())
+    }
+
+    pub(crate) fn site_name(&self, id: u32) -> String {
+        self.sites
+            .get(&id)
+            .map(|s| s.name.clone())
+            .unwrap_or_else(|| "a forgotten place".to_string())
+    }
+
+    fn faction_name(&self, id: u32) -> String {
+        self.factions
+            .get(&id)
+            .map(|f| f.name.clone())
+            .unwrap_or_else(|| "a lost people".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_death_is_recorded_with_prose() {
+        let mut world = World::new(20, 20);
+        let victim = world.spawn_entity("Urist".to_string(), Position::new(14, 4), EntityType::NPC).unwrap();
+        let killer = world.spawn_entity("Kogan".to_string(), Position::new(15, 4), EntityType::Player).unwrap();
+
+        let id = world.report_happening(Happening::Death {
+            victim,
+            killer: Some(killer),
+            location: Position::new(14, 4),
+        });
+
+        assert!(id.is_some());
+        let events = world.get_historical_events(1);
+        assert_eq!(events[0].event_type, EventType::Death);
+        assert_eq!(events[0].participants, vec![victim, killer]);
+        assert_eq!(events[0].description, "Urist was slain by Kogan in the grasslands");
+    }
+
+    #[test]
+    fn test_insignificant_happening_is_filtered() {
+        let mut world = World::new(20, 20);
+        let a = world.spawn_entity("Wolf".to_string(), Position::new(14, 4), EntityType::Animal).unwrap();
+        let b = world.spawn_entity("Deer".to_string(), Position::new(15, 4), EntityType::Animal).unwrap();
+
+        let id = world.report_happening(Happening::Fight {
+            attacker: a,
+            defender: b,
+            location: Position::new(14, 4),
+        });
+
+        assert!(id.is_none());
+        assert!(world.get_historical_events(10).is_empty());
+    }
+
+    #[test]
+    fn test_no_events_without_happenings() {
+        let mut world = World::new(20, 20);
+        for _ in 0..200 {
+            world.tick();
+        }
+        assert!(world.get_historical_events(10).is_empty());
+    }
+}
@@ -0,0 +1,57 @@
+use crate::World;
+use common::*;
+
+impl World {
+    /// Larga um item no chão; retorna o ID do item criado
+    pub fn place_item(&mut self, pos: Position, name: String, kind: ItemKind) -> u32 {
+        let id = self.next_item_id;
+        self.next_item_id += 1;
+        self.item_layer.entry(pos).or_default().push(Item::new(id, name, kind));
+        self.touch(pos);
+        id
+    }
+
+    /// Retorna os itens largados em uma posição
+    pub fn get_items(&self, pos: Position) -> &[Item] {
+        self.item_layer.get(&pos).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Remove de uma posição os itens que não passam no filtro
+    pub(crate) fn retain_items<F>(&mut self, pos: Position, mut keep: F)
+    where
+        F: FnMut(&Item) -> bool,
+    {
+        if let Some(items) = self.item_layer.get_mut(&pos) {
+            let before = items.len();
+            items.retain(|item| keep(item));
+            let removed = items.len() < before;
+            if items.is_empty() {
+                self.item_layer.remove(&pos);
+            }
+            if removed {
+                self.touch(pos);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_place_and_retain_items() {
+        let mut world = World::new(20, 20);
+        let pos = Position::new(3, 3);
+        world.place_item(pos, "a sack of grain".to_string(), ItemKind::Food);
+        world.place_item(pos, "a crude spear".to_string(), ItemKind::Weapon);
+        assert_eq!(world.get_items(pos).len(), 2);
+
+        world.retain_items(pos, |item| item.kind != ItemKind::Food);
+        assert_eq!(world.get_items(pos).len(), 1);
+        assert_eq!(world.get_items(pos)[0].name, "a crude spear");
+
+        world.retain_items(pos, |_| false);
+        assert!(world.get_items(pos).is_empty());
+    }
+}
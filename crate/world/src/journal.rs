@@ -0,0 +1,359 @@
+use crate::World;
+use common::*;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Mutação do mundo registrada no diário
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Mutation {
+    /// Início de um tick da simulação
+    Tick { tick: u64 },
+    Spawn { entity: Entity },
+    Move { entity_id: u32, to: Position },
+    Despawn { entity_id: u32 },
+    TileChanged { pos: Position, tile: Tile },
+    SurfaceChanged { pos: Position, surface: Option<String> },
+    EventRecorded { event: HistoricalEvent },
+    /// Ação de um jogador (veja `World::apply_input`), reaplicada inteira
+    PlayerInput { id: u64, player: u32, message: ClientMessage },
+}
+
+/// Uma linha do diário
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalEntry {
+    /// Produzida dentro de um tick ou de uma ação de jogador. Na
+    /// recuperação o tick ou a ação é reexecutado (a simulação é
+    /// determinística) e estas entradas são puladas; as demais vêm de fora
+    /// e são reaplicadas.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub simulated: bool,
+    pub mutation: Mutation,
+}
+
+/// Diário append-only (JSON Lines) das mutações feitas desde o último
+/// snapshot. As entradas ficam em buffer até `World::commit_journal`.
+pub struct Journal {
+    writer: BufWriter<File>,
+    simulating: bool,
+    /// Primeiro erro de escrita, devolvido no próximo commit
+    error: Option<String>,
+}
+
+impl Journal {
+    /// Abre o diário para continuar escrevendo no fim
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Erro ao abrir {}: {}", path.display(), e))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            simulating: false,
+            error: None,
+        })
+    }
+
+    /// Cria um diário vazio, descartando o anterior
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Erro ao criar {}: {}", path.display(), e))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            simulating: false,
+            error: None,
+        })
+    }
+
+    fn append(&mut self, mutation: Mutation) {
+        if self.error.is_some() {
+            return;
+        }
+        let entry = JournalEntry {
+            simulated: self.simulating,
+            mutation,
+        };
+        let written = serde_json::to_writer(&mut self.writer, &entry)
+            .map_err(|e| e.to_string())
+            .and_then(|_| self.writer.write_all(b"\n").map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            self.error = Some(format!("Erro ao escrever no diário: {}", e));
+        }
+    }
+
+    /// Garante no disco tudo o que foi escrito até aqui
+    fn commit(&mut self) -> Result<(), String> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.writer.flush().map_err(|e| format!("Erro ao gravar o diário: {}", e))?;
+        self.writer
+            .get_ref()
+            .sync_data()
+            .map_err(|e| format!("Erro ao sincronizar o diário: {}", e))
+    }
+}
+
+/// Persistência incremental: o último snapshot do mundo e o diário das
+/// mutações desde ele, lado a lado (`world.json` e `world.journal`)
+pub struct WorldStore {
+    snapshot: PathBuf,
+    journal: PathBuf,
+}
+
+impl WorldStore {
+    pub fn new(snapshot: impl Into<PathBuf>) -> Self {
+        let snapshot = snapshot.into();
+        let journal = snapshot.with_extension("journal");
+        Self { snapshot, journal }
+    }
+
+    pub fn snapshot_path(&self) -> &Path {
+        &self.snapshot
+    }
+
+    /// Carrega o último snapshot e reaplica o diário por cima; `None` se
+    /// ainda não há snapshot
+    pub fn recover(&self) -> Result<Option<World>, String> {
+        if !self.snapshot.exists() {
+            return Ok(None);
+        }
+        let mut world = World::load_from_file(&self.snapshot)?;
+        if !self.journal.exists() {
+            return Ok(Some(world));
+        }
+
+        let file = File::open(&self.journal).map_err(|e| format!("Erro ao abrir {}: {}", self.journal.display(), e))?;
+        let mut lines = BufReader::new(file).lines().peekable();
+        let mut line_number = 0;
+        while let Some(line) = lines.next() {
+            line_number += 1;
+            let line = line.map_err(|e| format!("Erro ao ler {}: {}", self.journal.display(), e))?;
+            match serde_json::from_str::<JournalEntry>(&line) {
+                Ok(entry) => world.replay_entry(entry)?,
+                // Uma queda no meio da escrita deixa a última linha incompleta
+                Err(_) if lines.peek().is_none() => break,
+                Err(e) => return Err(format!("Diário corrompido na linha {}: {}", line_number, e)),
+            }
+        }
+        Ok(Some(world))
+    }
+
+    /// Grava um snapshot do mundo e recomeça o diário a partir dele
+    pub fn compact(&self, world: &mut World) -> Result<(), String> {
+        world.commit_journal()?;
+        world.save_to_file(&self.snapshot)?;
+        world.attach_journal(Journal::create(&self.journal)?);
+        Ok(())
+    }
+}
+
+impl World {
+    /// Passa a registrar as mutações do mundo no diário
+    pub fn attach_journal(&mut self, journal: Journal) {
+        self.journal = Some(journal);
+    }
+
+    /// Garante no disco as mutações registradas até aqui
+    pub fn commit_journal(&mut self) -> Result<(), String> {
+        match self.journal.as_mut() {
+            Some(journal) => journal.commit(),
+            None => Ok(()),
+        }
+    }
+
+    /// Registra uma mutação, se há diário; a entrada só é montada quando
+    /// alguém vai lê-la
+    pub(crate) fn record<F>(&mut self, mutation: F)
+    where
+        F: FnOnce() -> Mutation,
+    {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.append(mutation());
+        }
+    }
+
+    /// Marca o início ou o fim de um tick no diário
+    pub(crate) fn journal_tick(&mut self, started: bool) {
+        let tick = self.current_tick;
+        if let Some(journal) = self.journal.as_mut() {
+            if started {
+                journal.append(Mutation::Tick { tick });
+            }
+            journal.simulating = started;
+        }
+    }
+
+    /// Registra a ação de um jogador. Até `end_journal_input`, o que ela
+    /// muda fica marcado como produzido por ela. Devolve a marcação
+    /// anterior, a restaurar no fim.
+    pub(crate) fn journal_input(&mut self, player: u32, message: &ClientMessage) -> bool {
+        let id = self.next_input_id;
+        self.next_input_id += 1;
+        match self.journal.as_mut() {
+            Some(journal) => {
+                journal.append(Mutation::PlayerInput {
+                    id,
+                    player,
+                    message: message.clone(),
+                });
+                std::mem::replace(&mut journal.simulating, true)
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn end_journal_input(&mut self, simulating: bool) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.simulating = simulating;
+        }
+    }
+
+    /// Reaplica uma entrada do diário. Mutações externas são escritas de
+    /// forma absoluta, então reaplicar as que o snapshot já contém não
+    /// muda nada; ticks e ações já contidos no snapshot são pulados.
+    fn replay_entry(&mut self, entry: JournalEntry) -> Result<(), String> {
+        if entry.simulated {
+            return Ok(());
+        }
+        match entry.mutation {
+            Mutation::Tick { tick } if tick <= self.current_tick => {}
+            Mutation::Tick { tick } if tick == self.current_tick + 1 => self.tick(),
+            Mutation::Tick { tick } => {
+                return Err(format!("Diário salta do tick {} para o tick {}", self.current_tick, tick));
+            }
+            Mutation::Spawn { entity } => {
+                self.next_entity_id = self.next_entity_id.max(entity.id + 1);
+                self.touch(entity.pos);
+                self.entity_layer.insert(entity.id, entity);
+            }
+            Mutation::Move { entity_id, to } => {
+                if let Some(entity) = self.entity_layer.get_mut(&entity_id) {
+                    let from = std::mem::replace(&mut entity.pos, to);
+                    self.touch(from);
+                    self.touch(to);
+                }
+            }
+            Mutation::Despawn { entity_id } => {
+                if let Some(entity) = self.entity_layer.remove(&entity_id) {
+                    self.touch(entity.pos);
+                }
+            }
+            Mutation::TileChanged { pos, tile } => {
+                self.terrain_layer.insert(pos, tile);
+                self.touch(pos);
+            }
+            Mutation::SurfaceChanged { pos, surface } => {
+                match surface {
+                    Some(id) => self.surface_layer.insert(pos, id),
+                    None => self.surface_layer.remove(&pos),
+                };
+                self.touch(pos);
+            }
+            Mutation::EventRecorded { event } => {
+                if event.id >= self.next_event_id {
+                    self.store_event(event);
+                }
+            }
+            Mutation::PlayerInput { id, .. } if id < self.next_input_id => {}
+            // Recusada na sessão original, é recusada de novo
+            Mutation::PlayerInput { player, message, .. } => {
+                let _ = self.apply_input(player, &message);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InputOutcome, SiteKind, Stockpile};
+
+    fn snapshot(world: &World) -> String {
+        world.to_save().to_json().unwrap()
+    }
+
+    fn temp_store(name: &str) -> WorldStore {
+        let dir = std::env::temp_dir().join(format!("mm-journal-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        WorldStore::new(dir.join("world.json"))
+    }
+
+    #[test]
+    fn test_recovery_replays_ticks_and_player_actions() {
+        let store = temp_store("replay");
+        let mut world = World::new(40, 40);
+        store.compact(&mut world).unwrap();
+
+        let player = world.spawn_entity("Hero".to_string(), Position::new(5, 5), EntityType::Player).unwrap();
+        world.commit_journal().unwrap();
+        for _ in 0..150 {
+            world.tick();
+            world.move_entity(player, 1, 0);
+            world.commit_journal().unwrap();
+        }
+        world.despawn_entity(1);
+        world.commit_journal().unwrap();
+
+        // Queda: o mundo em memória some sem novo snapshot
+        let recovered = store.recover().unwrap().unwrap();
+        assert_eq!(snapshot(&recovered), snapshot(&world));
+        std::fs::remove_dir_all(store.snapshot_path().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_recovery_replays_caravan_robbery() {
+        let store = temp_store("robbery");
+        let mut world = World::new(60, 60);
+        let faction = world.add_faction("The River Kingdom", FactionType::new("human"));
+        let origin = world.found_site("Ashford".to_string(), SiteKind::Town, Some(faction), Position::new(10, 10), 40);
+        let destination =
+            world.found_site("Deephold".to_string(), SiteKind::Town, Some(faction), Position::new(10, 40), 40);
+        let cargo = Stockpile {
+            food: 100,
+            ..Default::default()
+        };
+        let caravan = world.dispatch_caravan(origin, destination, cargo).unwrap();
+        world.update_caravans(1);
+        let leader = world.get_caravan(caravan).unwrap().leader;
+        let pos = world.get_entity(leader).unwrap().pos;
+        let player = world.spawn_entity("Thief".to_string(), pos, EntityType::Player).unwrap();
+        store.compact(&mut world).unwrap();
+
+        let robbed = world.apply_input(player, &ClientMessage::RobCaravan).unwrap();
+        assert!(matches!(robbed, InputOutcome::Robbed { .. }));
+        world.commit_journal().unwrap();
+        for _ in 0..20 {
+            world.tick();
+            world.commit_journal().unwrap();
+        }
+
+        let recovered = store.recover().unwrap().unwrap();
+        assert_eq!(recovered.get_pack(player).map(|p| p.food), Some(100));
+        assert_eq!(recovered.state_hash(), world.state_hash());
+
+        // Queda entre o snapshot novo e o diário novo: o roubo não se repete
+        world.save_to_file(store.snapshot_path()).unwrap();
+        assert_eq!(store.recover().unwrap().unwrap().state_hash(), world.state_hash());
+        std::fs::remove_dir_all(store.snapshot_path().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_recovery_ignores_torn_last_line() {
+        let store = temp_store("torn");
+        let mut world = World::new(30, 30);
+        store.compact(&mut world).unwrap();
+        world.tick();
+        world.commit_journal().unwrap();
+        let expected = snapshot(&world);
+
+        let mut journal = OpenOptions::new().append(true).open(&store.journal).unwrap();
+        journal.write_all(b"{\"mutation\":{\"Tick\":{\"ti").unwrap();
+
+        let recovered = store.recover().unwrap().unwrap();
+        assert_eq!(snapshot(&recovered), expected);
+        std::fs::remove_dir_all(store.snapshot_path().parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,414 @@
+use crate::{Site, World};
+use common::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Versão do formato do arquivo de lendas exportado
+pub const LEGENDS_FORMAT_VERSION: u32 = 2;
+
+/// Lado (em tiles) das regiões usadas no índice espacial de eventos
+const REGION_SIZE: i32 = 16;
+
+/// Limite de eventos por página quando a consulta não informa um
+pub const DEFAULT_PAGE_LIMIT: usize = 100;
+
+/// Índices sobre `historical_events` (guardam posições no vetor)
+#[derive(Debug, Clone, Default)]
+pub struct HistoryIndex {
+    by_participant: HashMap<u32, Vec<usize>>,
+    by_faction: HashMap<u32, Vec<usize>>,
+    by_type: HashMap<EventType, Vec<usize>>,
+    by_region: HashMap<(i32, i32), Vec<usize>>,
+}
+
+fn region_of(pos: Position) -> (i32, i32) {
+    (pos.x.div_euclid(REGION_SIZE), pos.y.div_euclid(REGION_SIZE))
+}
+
+impl HistoryIndex {
+    /// Indexa um evento que será armazenado na posição `index`
+    pub fn insert(&mut self, event: &HistoricalEvent, index: usize) {
+        for id in &event.participants {
+            self.by_participant.entry(*id).or_default().push(index);
+        }
+        for id in &event.factions {
+            self.by_faction.entry(*id).or_default().push(index);
+        }
+        self.by_type.entry(event.event_type).or_default().push(index);
+        self.by_region.entry(region_of(event.location)).or_default().push(index);
+    }
+
+    /// Reconstrói todos os índices a partir da lista de eventos
+    pub fn rebuild(events: &[HistoricalEvent]) -> Self {
+        let mut index = Self::default();
+        for (i, event) in events.iter().enumerate() {
+            index.insert(event, i);
+        }
+        index
+    }
+
+    fn near(&self, center: Position, radius: i32) -> Vec<usize> {
+        let (min_x, min_y) = region_of(center.moved(-radius, -radius));
+        let (max_x, max_y) = region_of(center.moved(radius, radius));
+
+        let mut found = Vec::new();
+        for rx in min_x..=max_x {
+            for ry in min_y..=max_y {
+                if let Some(indices) = self.by_region.get(&(rx, ry)) {
+                    found.extend_from_slice(indices);
+                }
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+}
+
+/// Consulta sobre a história. Filtros ausentes não restringem.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    pub participant: Option<u32>,
+    pub faction: Option<u32>,
+    /// Centro e raio (quadrado, como em `get_entities_in_region`)
+    pub near: Option<(Position, i32)>,
+    pub event_type: Option<EventType>,
+    /// Tick inicial (inclusivo)
+    pub from_tick: Option<u64>,
+    /// Tick final (inclusivo)
+    pub to_tick: Option<u64>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    fn matches(&self, event: &HistoricalEvent) -> bool {
+        self.participant.is_none_or(|id| event.participants.contains(&id))
+            && self.faction.is_none_or(|id| event.factions.contains(&id))
+            && self.event_type.is_none_or(|t| event.event_type == t)
+            && self.from_tick.is_none_or(|t| event.tick >= t)
+            && self.to_tick.is_none_or(|t| event.tick <= t)
+            && self.near.is_none_or(|(center, radius)| {
+                (event.location.x - center.x).abs() <= radius
+                    && (event.location.y - center.y).abs() <= radius
+            })
+    }
+}
+
+/// Página de resultados, em ordem cronológica
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryPage {
+    pub events: Vec<HistoricalEvent>,
+    /// Total de eventos que satisfazem a consulta (todas as páginas)
+    pub total: usize,
+    pub offset: usize,
+}
+
+impl World {
+    /// Consulta eventos históricos usando os índices
+    pub fn query_history(&self, query: &HistoryQuery) -> HistoryPage {
+        let events = &self.historical_events;
+        let index = &self.history_index;
+
+        // Eventos estão em ordem de tick: o intervalo vira uma fatia
+        let start = query.from_tick.map_or(0, |t| events.partition_point(|e| e.tick < t));
+        let end = query.to_tick.map_or(events.len(), |t| events.partition_point(|e| e.tick <= t));
+
+        let mut candidates: Vec<&[usize]> = Vec::new();
+        let empty: Vec<usize> = Vec::new();
+        if let Some(id) = query.participant {
+            candidates.push(index.by_participant.get(&id).unwrap_or(&empty));
+        }
+        if let Some(id) = query.faction {
+            candidates.push(index.by_faction.get(&id).unwrap_or(&empty));
+        }
+        if let Some(event_type) = query.event_type {
+            candidates.push(index.by_type.get(&event_type).unwrap_or(&empty));
+        }
+        let nearby;
+        if let Some((center, radius)) = query.near {
+            nearby = index.near(center, radius);
+            candidates.push(&nearby);
+        }
+
+        let matching: Vec<&HistoricalEvent> = match candidates.into_iter().min_by_key(|c| c.len()) {
+            Some(smallest) => smallest
+                .iter()
+                .filter(|i| (start..end).contains(*i))
+                .map(|i| &events[*i])
+                .filter(|e| query.matches(e))
+                .collect(),
+            None => events[start..end.max(start)]
+                .iter()
+                .filter(|e| query.matches(e))
+                .collect(),
+        };
+
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        HistoryPage {
+            total: matching.len(),
+            offset: query.offset,
+            events: matching
+                .into_iter()
+                .skip(query.offset)
+                .take(limit)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Arquivo de lendas: toda a história do mundo em formato autocontido,
+/// para ferramentas externas e o gerador da wiki
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LegendsExport {
+    pub format_version: u32,
+    pub world: WorldInfo,
+    pub figures: Vec<FigureLegend>,
+    pub factions: Vec<FactionLegend>,
+    /// Locais do mundo (a partir da versão 2)
+    #[serde(default)]
+    pub sites: Vec<Site>,
+    pub events: Vec<HistoricalEvent>,
+    pub relationships: Vec<Relationship>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorldInfo {
+    pub width: i32,
+    pub height: i32,
+    pub seed: u64,
+    pub current_tick: u64,
+}
+
+/// Figura histórica com a entidade correspondente, se ainda viva
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FigureLegend {
+    #[serde(flatten)]
+    pub figure: HistoricalFigure,
+    pub position: Option<Position>,
+    /// IDs dos eventos em que a figura participa
+    pub events: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FactionLegend {
+    pub id: u32,
+    pub name: String,
+    pub faction_type: FactionType,
+    pub member_count: usize,
+    pub territory: Vec<Position>,
+    pub events: Vec<u64>,
+}
+
+/// Relação entre dois sujeitos do arquivo de lendas
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind")]
+pub enum Relationship {
+    /// Relação diplomática entre duas fações (registrada uma vez por par)
+    Diplomacy { faction_a: u32, faction_b: u32, relation: Relation },
+    /// Figura pertencente a uma fação
+    Membership { figure: u32, faction: u32 },
+}
+
+impl LegendsExport {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let export: Self = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if export.format_version > LEGENDS_FORMAT_VERSION {
+            return Err(format!(
+                "Formato de lendas v{} é mais novo que o suportado (v{})",
+                export.format_version, LEGENDS_FORMAT_VERSION
+            ));
+        }
+        Ok(export)
+    }
+}
+
+impl World {
+    /// Exporta toda a história do mundo para o formato de lendas
+    pub fn export_legends(&self) -> LegendsExport {
+        let event_ids = |indices: Option<&Vec<usize>>| -> Vec<u64> {
+            indices
+                .map(|list| list.iter().map(|i| self.historical_events[*i].id).collect())
+                .unwrap_or_default()
+        };
+
+        let figures = self
+            .get_historical_figures()
+            .into_iter()
+            .map(|figure| FigureLegend {
+                figure: figure.clone(),
+                position: self.entity_layer.get(&figure.id).map(|e| e.pos),
+                events: event_ids(self.history_index.by_participant.get(&figure.id)),
+            })
+            .collect();
+
+        let factions = self
+            .get_factions()
+            .into_iter()
+            .map(|faction| FactionLegend {
+                id: faction.id,
+                name: faction.name.clone(),
+                faction_type: faction.faction_type.clone(),
+                member_count: faction.member_count,
+                territory: faction.territory.clone(),
+                events: event_ids(self.history_index.by_faction.get(&faction.id)),
+            })
+            .collect();
+
+        let mut relationships = Vec::new();
+        for faction in self.get_factions() {
+            let mut relations: Vec<(&u32, &Relation)> = faction.relations.iter().collect();
+            relations.sort_by_key(|(id, _)| **id);
+            for (other, relation) in relations {
+                if *other > faction.id {
+                    relationships.push(Relationship::Diplomacy {
+                        faction_a: faction.id,
+                        faction_b: *other,
+                        relation: *relation,
+                    });
+                }
+            }
+        }
+        for figure in self.get_historical_figures() {
+            if let Some(faction) = figure.faction_id {
+                relationships.push(Relationship::Membership {
+                    figure: figure.id,
+                    faction,
+                });
+            }
+        }
+
+        LegendsExport {
+            format_version: LEGENDS_FORMAT_VERSION,
+            world: WorldInfo {
+                width: self.width,
+                height: self.height,
+                seed: self.seed,
+                current_tick: self.current_tick,
+            },
+            figures,
+            factions,
+            sites: self.get_sites().into_iter().cloned().collect(),
+            events: self.historical_events.clone(),
+            relationships,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Happening, HistoryGenConfig};
+
+    fn legendary_world() -> World {
+        let mut world = World::with_seed(80, 80, 5);
+        world.generate_history(&HistoryGenConfig::default());
+        world
+    }
+
+    #[test]
+    fn test_query_matches_linear_scan() {
+        let world = legendary_world();
+        let all = world.get_historical_events(usize::MAX);
+        let faction = world.get_factions()[0].id;
+
+        let page = world.query_history(&HistoryQuery {
+            faction: Some(faction),
+            event_type: Some(EventType::Combat),
+            limit: Some(usize::MAX),
+            ..Default::default()
+        });
+
+        let expected: Vec<u64> = all
+            .iter()
+            .filter(|e| e.factions.contains(&faction) && e.event_type == EventType::Combat)
+            .map(|e| e.id)
+            .collect();
+        let found: Vec<u64> = page.events.iter().map(|e| e.id).collect();
+        assert_eq!(found, expected);
+        assert_eq!(page.total, expected.len());
+    }
+
+    #[test]
+    fn test_query_by_participant_and_location() {
+        let mut world = World::new(40, 40);
+        let a = world.spawn_entity("Urist".to_string(), Position::new(5, 5), EntityType::Player).unwrap();
+        let b = world.spawn_entity("Kogan".to_string(), Position::new(30, 30), EntityType::Player).unwrap();
+        world.report_happening(Happening::Fight { attacker: a, defender: b, location: Position::new(5, 5) });
+        world.report_happening(Happening::Death { victim: b, killer: None, location: Position::new(30, 30) });
+
+        let about_b = world.query_history(&HistoryQuery { participant: Some(b), ..Default::default() });
+        assert_eq!(about_b.total, 2);
+
+        let about_a = world.query_history(&HistoryQuery { participant: Some(a), ..Default::default() });
+        assert_eq!(about_a.total, 1);
+
+        let near = world.query_history(&HistoryQuery {
+            near: Some((Position::new(29, 31), 2)),
+            ..Default::default()
+        });
+        assert_eq!(near.total, 1);
+        assert_eq!(near.events[0].event_type, EventType::Death);
+    }
+
+    #[test]
+    fn test_query_tick_range_and_pagination() {
+        let world = legendary_world();
+        let all = world.get_historical_events(usize::MAX);
+        let from = all[all.len() / 4].tick;
+        let to = all[all.len() / 2].tick;
+
+        let expected: Vec<u64> = all
+            .iter()
+            .filter(|e| e.tick >= from && e.tick <= to)
+            .map(|e| e.id)
+            .collect();
+
+        let mut collected = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = world.query_history(&HistoryQuery {
+                from_tick: Some(from),
+                to_tick: Some(to),
+                offset,
+                limit: Some(3),
+                ..Default::default()
+            });
+            if page.events.is_empty() {
+                break;
+            }
+            offset += page.events.len();
+            collected.extend(page.events.iter().map(|e| e.id));
+        }
+        assert_eq!(collected, expected);
+    }
+
+    #[test]
+    fn test_legends_export_round_trip() {
+        let world = legendary_world();
+        let export = world.export_legends();
+
+        assert_eq!(export.format_version, LEGENDS_FORMAT_VERSION);
+        assert_eq!(export.events.len(), world.get_historical_events(usize::MAX).len());
+        assert_eq!(export.factions.len(), world.get_factions().len());
+        assert_eq!(export.figures.len(), world.get_historical_figures().len());
+        assert_eq!(export.sites.len(), world.get_sites().len());
+
+        let json = export.to_json().unwrap();
+        let parsed = LegendsExport::from_json(&json).unwrap();
+        assert_eq!(parsed.relationships, export.relationships);
+        assert_eq!(parsed.world.seed, world.seed());
+    }
+
+    #[test]
+    fn test_legends_rejects_newer_format() {
+        let mut export = World::new(20, 20).export_legends();
+        export.format_version = LEGENDS_FORMAT_VERSION + 1;
+        let json = export.to_json().unwrap();
+        assert!(LegendsExport::from_json(&json).is_err());
+    }
+}
@@ -1,48 +1,166 @@
+pub mod ai;
+pub mod caravans;
+pub mod combat;
+pub mod economy;
+pub mod events;
+pub mod history;
+pub mod items;
+pub mod journal;
+pub mod legends;
+pub mod lod;
+pub mod migrations;
+pub mod mods;
+pub mod pathfinding;
+pub mod persistence;
+pub mod raws;
+pub mod replay;
+pub mod rng;
+pub mod scheduler;
+pub mod roads;
+pub mod ruins;
+pub mod rules;
+pub mod sites;
+pub mod snapshots;
+pub mod worldgen;
+
 use common::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+pub use caravans::{Caravan, CARAVAN_REACH};
+pub use economy::{EconomicIntent, Stockpile, ECONOMY_INTERVAL};
+pub use events::{EventBus, WorldEvent, MAX_MEMORIES, WITNESS_RADIUS};
+pub use history::{Happening, HistoryRecorder};
+pub use journal::{Journal, JournalEntry, Mutation, WorldStore};
+pub use legends::{HistoryIndex, HistoryPage, HistoryQuery, LegendsExport, LEGENDS_FORMAT_VERSION};
+pub use mods::{Mod, ModConflict, ModManifest, ModRef, ModReport, MODS_DIR};
+pub use lod::{region_of, Region, COARSE_INTERVAL, DETAIL_RADIUS, REGION_SIZE};
+pub use persistence::{IdCounters, SaveHeader, WorldSave, WorldState, SAVE_FORMAT_VERSION};
+pub use raws::{
+    Behavior, CreatureDef, FactionDef, Glyph, ItemClass, ItemDef, MaterialDef, Raws, TerrainDef, RAWS_DIR,
+};
+pub use replay::{Checkpoint, InputOutcome, Replay, ReplayInput, ReplayStart, REPLAY_FORMAT_VERSION};
+pub use rng::WorldRng;
+pub use scheduler::{Phase, Scheduler, System, SystemTiming};
+pub use roads::ROAD_TRADE_THRESHOLD;
+pub use ruins::{Desolation, DECAY_INTERVAL};
+pub use rules::{RuleDef, RuleEffect, SurfaceDef, TileCondition};
+pub use sites::{Building, BuildingKind, Site, SiteKind, SiteState};
+pub use snapshots::{Change, EntityMove, SnapshotInfo, Snapshots, WorldDiff, MAX_SNAPSHOTS};
+pub use worldgen::{HistoryGenConfig, TICKS_PER_YEAR};
+
+/// Semente usada quando nenhuma é informada
+pub const DEFAULT_SEED: u64 = 0x4D4D_5F57_4F52_4C44;
+
+/// Onde novos jogadores chegam ao mundo
+pub const PLAYER_SPAWN: Position = Position { x: 10, y: 10 };
 
 /// Gerenciador do mundo com sistema de camadas
 pub struct World {
     /// Camada de terreno (base)
     terrain_layer: HashMap<Position, Tile>,
+    /// Camada de construções (paredes, portas, ruínas)
+    construction_layer: HashMap<Position, Construction>,
+    /// Camada de itens largados (bens, cadáveres)
+    item_layer: HashMap<Position, Vec<Item>>,
+    /// Camada de superfícies (fogo, sangue, gelo), por ID dos raws
+    surface_layer: HashMap<Position, String>,
+    /// Tiles alterados desde a última passada das regras de interação
+    changed_tiles: HashSet<Position>,
     /// Camada de entidades (criaturas, NPCs, jogadores)
     entity_layer: HashMap<u32, Entity>,
     /// Comportamentos de IA para entidades
     ai_behaviors: HashMap<u32, AIBehavior>,
     /// Fações no mundo
-    #[allow(dead_code)]
     factions: HashMap<u32, Faction>,
+    /// Locais: cidades, acampamentos, covis e ruínas
+    sites: HashMap<u32, Site>,
     /// Eventos históricos
     historical_events: Vec<HistoricalEvent>,
+    /// Caravanas de comércio em viagem
+    caravans: HashMap<u32, Caravan>,
+    /// Recursos carregados por entidades (jogadores)
+    packs: HashMap<u32, Stockpile>,
+    /// Filtro de relevância da história emergente
+    history: HistoryRecorder,
+    /// Índices de consulta sobre os eventos históricos
+    history_index: HistoryIndex,
+    /// Figuras históricas, vivas ou mortas
+    historical_figures: HashMap<u32, HistoricalFigure>,
+    /// Semente que originou o mundo
+    seed: u64,
+    /// Gerador aleatório determinístico
+    rng: WorldRng,
     /// Dimensões do mundo
     width: i32,
     height: i32,
     /// Próximo ID de entidade
     next_entity_id: u32,
     /// Próximo ID de facção
-    #[allow(dead_code)]
     next_faction_id: u32,
+    /// Próximo ID de local
+    next_site_id: u32,
+    /// Próximo ID de item
+    next_item_id: u32,
+    /// Próximo ID de caravana
+    next_caravan_id: u32,
     /// Próximo ID de evento
     next_event_id: u64,
+    /// Próximo número de ação de jogador, para o diário
+    next_input_id: u64,
     /// Tick atual do mundo
     current_tick: u64,
+    /// Diário de mutações desde o último snapshot, se a persistência
+    /// incremental está ligada
+    journal: Option<Journal>,
+    /// Eventos publicados pelos sistemas, à espera da fase de despacho
+    events: EventBus,
+    /// Sistemas rodados a cada tick
+    scheduler: Scheduler,
+    /// Definições de conteúdo (terrenos, criaturas, itens, povos)
+    raws: Arc<Raws>,
 }
 
 impl World {
     /// Cria um novo mundo com tamanho especificado
     pub fn new(width: i32, height: i32) -> Self {
+        Self::with_seed(width, height, DEFAULT_SEED)
+    }
+
+    /// Cria um novo mundo com semente aleatória explícita
+    pub fn with_seed(width: i32, height: i32, seed: u64) -> Self {
         let mut world = Self {
             terrain_layer: HashMap::new(),
+            construction_layer: HashMap::new(),
+            item_layer: HashMap::new(),
+            surface_layer: HashMap::new(),
+            changed_tiles: HashSet::new(),
             entity_layer: HashMap::new(),
             ai_behaviors: HashMap::new(),
             factions: HashMap::new(),
+            sites: HashMap::new(),
             historical_events: Vec::new(),
+            caravans: HashMap::new(),
+            packs: HashMap::new(),
+            history: HistoryRecorder::default(),
+            history_index: HistoryIndex::default(),
+            historical_figures: HashMap::new(),
+            seed,
+            rng: WorldRng::new(seed),
             width,
             height,
             next_entity_id: 1,
             next_faction_id: 1,
+            next_site_id: 1,
+            next_item_id: 1,
+            next_caravan_id: 1,
             next_event_id: 1,
+            next_input_id: 1,
             current_tick: 0,
+            journal: None,
+            events: EventBus::default(),
+            scheduler: Scheduler::with_builtin_systems(),
+            raws: Raws::active(),
         };
         
         world.generate_terrain();
@@ -58,11 +176,11 @@ impl World {
                 
                 // Geração simples: água no centro, grama ao redor
                 let tile = if self.is_water_region(x, y) {
-                    Tile::water()
+                    self.tile(TerrainType::WATER)
                 } else if self.is_stone_region(x, y) {
-                    Tile::new(TerrainType::Stone, true)
+                    self.tile(TerrainType::STONE)
                 } else {
-                    Tile::grass()
+                    self.tile(TerrainType::GRASS)
                 };
                 
                 self.terrain_layer.insert(pos, tile);
@@ -76,9 +194,12 @@ impl World {
         let center_y = self.height / 2;
         let dx = (x - center_x).abs();
         let dy = (y - center_y).abs();
+        // Mundos pequenos (os de teste) ficam com um lago menor, para sobrar
+        // chão em volta; a partir de 90x90 o lago é o de sempre
+        let radius_sq = (self.width.min(self.height) / 10).min(9);
         
         // Lago circular no centro
-        dx * dx + dy * dy < 9
+        dx * dx + dy * dy < radius_sq
     }
 
     /// Determina se posição deve ser pedra
@@ -87,25 +208,51 @@ impl World {
         x < 2 || x >= self.width - 2 || y < 2 || y >= self.height - 2
     }
 
+    /// Coloca um novo jogador no ponto de chegada (ou no tile livre mais
+    /// próximo)
+    pub fn spawn_player(&mut self, name: String) -> Option<u32> {
+        let pos = self.nearest_walkable(PLAYER_SPAWN, 10)?;
+        self.spawn_creature(raws::PLAYER_CREATURE, name, pos)
+    }
+
     /// Adiciona uma entidade ao mundo
     pub fn spawn_entity(&mut self, name: String, pos: Position, entity_type: EntityType) -> Option<u32> {
+        self.spawn(Entity::new(0, name, pos, entity_type))
+    }
+
+    /// Adiciona ao mundo uma criatura definida nos raws
+    pub fn spawn_creature(&mut self, creature: &str, name: String, pos: Position) -> Option<u32> {
+        let mut entity = Entity::new(0, name, pos, self.raws.creature(creature)?.kind);
+        entity.creature = Some(creature.to_string());
+        self.spawn(entity)
+    }
+
+    fn spawn(&mut self, mut entity: Entity) -> Option<u32> {
+        let pos = entity.pos;
         // Valida se posição está no mundo
         if !self.is_valid_position(pos) {
             return None;
         }
 
         // Valida se tile é transitável
-        if let Some(tile) = self.terrain_layer.get(&pos) {
-            if !tile.walkable {
-                return None;
-            }
+        if !self.is_walkable(pos) {
+            return None;
         }
 
         let id = self.next_entity_id;
         self.next_entity_id += 1;
         
-        let entity = Entity::new(id, name, pos, entity_type);
+        entity.id = id;
+        let (name, entity_type) = (entity.name.clone(), entity.entity_type);
+        self.record(|| Mutation::Spawn { entity: entity.clone() });
         self.entity_layer.insert(id, entity);
+        self.touch(pos);
+        self.publish(WorldEvent::EntitySpawned {
+            entity_id: id,
+            name,
+            pos,
+            entity_type,
+        });
         Some(id)
     }
 
@@ -120,17 +267,16 @@ impl World {
             }
 
             // Valida se tile é transitável
-            if let Some(tile) = self.terrain_layer.get(&new_pos) {
-                if !tile.walkable {
-                    return false;
-                }
-            } else {
+            if !self.is_walkable(new_pos) {
                 return false;
             }
 
             // Move a entidade
             if let Some(entity) = self.entity_layer.get_mut(&entity_id) {
-                entity.pos = new_pos;
+                let old_pos = std::mem::replace(&mut entity.pos, new_pos);
+                self.record(|| Mutation::Move { entity_id, to: new_pos });
+                self.touch(old_pos);
+                self.touch(new_pos);
                 return true;
             }
         }
@@ -139,7 +285,73 @@ impl World {
 
     /// Remove uma entidade do mundo
     pub fn despawn_entity(&mut self, entity_id: u32) -> bool {
-        self.entity_layer.remove(&entity_id).is_some()
+        let Some(entity) = self.entity_layer.remove(&entity_id) else {
+            return false;
+        };
+        self.record(|| Mutation::Despawn { entity_id });
+        self.touch(entity.pos);
+        true
+    }
+
+    /// Mata uma entidade: reporta a morte à história, deixa o cadáver
+    /// (e o sangue) no chão e a remove do mundo
+    pub fn kill_entity(&mut self, entity_id: u32, killer: Option<u32>) -> bool {
+        let (location, name, blood) = match self.entity_layer.get(&entity_id) {
+            Some(entity) => (entity.pos, entity.name.clone(), self.raws.creature_of(entity).blood.clone()),
+            None => return false,
+        };
+
+        self.report_happening(Happening::Death {
+            victim: entity_id,
+            killer,
+            location,
+        });
+        self.ai_behaviors.remove(&entity_id);
+        if let Some(figure) = self.historical_figures.get_mut(&entity_id) {
+            figure.death_tick = Some(self.current_tick);
+        }
+        self.place_item(location, format!("the corpse of {}", name), ItemKind::Corpse { of: entity_id });
+        if let Some(blood) = blood {
+            self.set_surface(location, Some(&blood));
+        }
+        let despawned = self.despawn_entity(entity_id);
+        self.publish(WorldEvent::EntityDied {
+            entity_id,
+            name,
+            killer,
+            location,
+        });
+        despawned
+    }
+
+    /// Verifica se a posição pode ser ocupada (terreno e construção)
+    pub fn is_walkable(&self, pos: Position) -> bool {
+        let terrain_ok = self.terrain_layer.get(&pos).is_some_and(|t| t.walkable);
+        let construction_ok = self.construction_layer.get(&pos).is_none_or(|c| c.walkable);
+        terrain_ok && construction_ok
+    }
+
+    /// Procura a posição transitável mais próxima (busca em anéis)
+    pub fn nearest_walkable(&self, near: Position, max_radius: i32) -> Option<Position> {
+        for radius in 0..=max_radius {
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    if dx.abs() != radius && dy.abs() != radius {
+                        continue;
+                    }
+                    let pos = near.moved(dx, dy);
+                    if self.is_walkable(pos) && !self.is_occupied(pos) {
+                        return Some(pos);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Verifica se alguma entidade ocupa a posição
+    fn is_occupied(&self, pos: Position) -> bool {
+        self.entity_layer.values().any(|e| e.pos == pos)
     }
 
     /// Verifica se posição está dentro dos limites
@@ -152,6 +364,11 @@ impl World {
         self.terrain_layer.get(&pos)
     }
 
+    /// Retorna construção em uma posição
+    pub fn get_construction(&self, pos: Position) -> Option<&Construction> {
+        self.construction_layer.get(&pos)
+    }
+
     /// Retorna entidade por ID
     pub fn get_entity(&self, id: u32) -> Option<&Entity> {
         self.entity_layer.get(&id)
@@ -159,20 +376,20 @@ impl World {
 
     /// Retorna todas as entidades em uma região
     pub fn get_entities_in_region(&self, center: Position, radius: i32) -> Vec<&Entity> {
-        self.entity_layer.values()
+        let mut entities: Vec<&Entity> = self.entity_layer.values()
             .filter(|e| {
                 let dx = (e.pos.x - center.x).abs();
                 let dy = (e.pos.y - center.y).abs();
                 dx <= radius && dy <= radius
             })
-            .collect()
+            .collect();
+        entities.sort_by_key(|e| e.id);
+        entities
     }
 
     /// Retorna snapshot do mundo visível para um jogador
     pub fn get_visible_snapshot(&self, center: Position, view_radius: i32) -> WorldSnapshot {
         let mut tiles = Vec::new();
-        let mut entities = Vec::new();
-
         // Coleta tiles visíveis
         for x in (center.x - view_radius)..=(center.x + view_radius) {
             for y in (center.y - view_radius)..=(center.y + view_radius) {
@@ -184,7 +401,7 @@ impl World {
         }
 
         // Coleta entidades visíveis
-        entities = self.get_entities_in_region(center, view_radius)
+        let entities = self.get_entities_in_region(center, view_radius)
             .into_iter()
             .cloned()
             .collect();
@@ -197,6 +414,11 @@ impl World {
         (self.width, self.height)
     }
 
+    /// Retorna a semente do mundo
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     /// Retorna número de entidades ativas
     pub fn entity_count(&self) -> usize {
         self.entity_layer.len()
@@ -204,16 +426,16 @@ impl World {
 
     /// Spawna NPCs iniciais com IA
     fn spawn_initial_npcs(&mut self) {
+        let goal = self
+            .raws
+            .creature(raws::VILLAGER_CREATURE)
+            .and_then(|c| c.behaviors.first().copied());
         // Spawna alguns NPCs com IA
         for i in 0..5 {
             let pos = Position::new(5 + i, 5 + i);
-            if let Some(id) = self.spawn_entity(
-                format!("NPC_{}", i),
-                pos,
-                EntityType::NPC,
-            ) {
+            if let Some(id) = self.spawn_creature(raws::VILLAGER_CREATURE, format!("NPC_{}", i), pos) {
                 self.ai_behaviors.insert(id, AIBehavior {
-                    current_goal: AIGoal::Wander,
+                    current_goal: goal.map_or(AIGoal::Wander, |b| b.goal_at(pos)),
                     memory: Vec::new(),
                     personality: Personality {
                         aggression: 0.3,
@@ -228,66 +450,13 @@ impl World {
     /// Processa um tick do mundo
     pub fn tick(&mut self) {
         self.current_tick += 1;
+        self.journal_tick(true);
+        self.defer_events();
         
-        // Atualiza IA de todas as entidades
-        self.update_ai();
-        
-        // Atualiza fações
-        self.update_factions();
-        
-        // Gera eventos aleatórios
-        if self.current_tick % 100 == 0 {
-            self.generate_random_event();
-        }
-    }
+        // IA, movimento, economia, história e eventos, em ordem de fase
+        self.run_systems();
 
-    fn update_ai(&mut self) {
-        let entity_ids: Vec<u32> = self.ai_behaviors.keys().copied().collect();
-        
-        for entity_id in entity_ids {
-            if let Some(behavior) = self.ai_behaviors.get(&entity_id) {
-                match behavior.current_goal {
-                    AIGoal::Wander => {
-                        // Movimento aleatório
-                        let dx = (self.current_tick % 3) as i32 - 1;
-                        let dy = ((self.current_tick / 3) % 3) as i32 - 1;
-                        self.move_entity(entity_id, dx, dy);
-                    }
-                    AIGoal::Patrol { start, end } => {
-                        // Patrulha entre dois pontos
-                        if let Some(entity) = self.entity_layer.get(&entity_id) {
-                            let target = if (self.current_tick / 50) % 2 == 0 { start } else { end };
-                            let dx = (target.x - entity.pos.x).signum();
-                            let dy = (target.y - entity.pos.y).signum();
-                            self.move_entity(entity_id, dx, dy);
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-    }
-
-    fn update_factions(&mut self) {
-        // Atualiza relações entre fações
-        // TODO: Implementar lógica de diplomacia
-    }
-
-    fn generate_random_event(&mut self) {
-        // Gera eventos históricos aleatórios
-        if self.entity_layer.len() > 1 {
-            let event = HistoricalEvent {
-                id: self.next_event_id,
-                tick: self.current_tick,
-                event_type: EventType::Combat,
-                participants: vec![1, 2],
-                location: Position::new(10, 10),
-                description: "A skirmish occurred in the grasslands".to_string(),
-            };
-            
-            self.next_event_id += 1;
-            self.historical_events.push(event);
-        }
+        self.journal_tick(false);
     }
 
     /// Retorna tick atual
@@ -295,6 +464,11 @@ impl World {
         self.current_tick
     }
 
+    /// Configura o filtro de relevância da história
+    pub fn set_history_recorder(&mut self, recorder: HistoryRecorder) {
+        self.history = recorder;
+    }
+
     /// Retorna eventos históricos
     pub fn get_historical_events(&self, limit: usize) -> Vec<HistoricalEvent> {
         let start = if self.historical_events.len() > limit {
@@ -305,6 +479,51 @@ impl World {
         self.historical_events[start..].to_vec()
     }
 
+    /// Retorna fação por ID
+    pub fn get_faction(&self, id: u32) -> Option<&Faction> {
+        self.factions.get(&id)
+    }
+
+    /// Cria uma fação sem território, neutra com todas as outras
+    pub fn add_faction(&mut self, name: &str, faction_type: FactionType) -> u32 {
+        let id = self.next_faction_id;
+        self.next_faction_id += 1;
+
+        let mut relations = HashMap::new();
+        for other in self.factions.values_mut() {
+            other.relations.insert(id, Relation::Neutral);
+            relations.insert(other.id, Relation::Neutral);
+        }
+        self.factions.insert(id, Faction {
+            id,
+            name: name.to_string(),
+            faction_type,
+            territory: Vec::new(),
+            member_count: 0,
+            relations,
+        });
+        id
+    }
+
+    /// Retorna todas as fações ordenadas por ID
+    pub fn get_factions(&self) -> Vec<&Faction> {
+        let mut factions: Vec<&Faction> = self.factions.values().collect();
+        factions.sort_by_key(|f| f.id);
+        factions
+    }
+
+    /// Retorna figura histórica por ID
+    pub fn get_historical_figure(&self, id: u32) -> Option<&HistoricalFigure> {
+        self.historical_figures.get(&id)
+    }
+
+    /// Retorna todas as figuras históricas ordenadas por ID
+    pub fn get_historical_figures(&self) -> Vec<&HistoricalFigure> {
+        let mut figures: Vec<&HistoricalFigure> = self.historical_figures.values().collect();
+        figures.sort_by_key(|f| f.id);
+        figures
+    }
+
     /// Retorna todas as entidades (para debug/admin)
     pub fn get_all_entities(&self) -> Vec<&Entity> {
         self.entity_layer.values().collect()
@@ -330,7 +549,12 @@ mod tests {
     fn test_world_creation() {
         let world = World::new(20, 20);
         assert_eq!(world.dimensions(), (20, 20));
-        assert_eq!(world.entity_count(), 0);
+        // O mundo já nasce com os NPCs de `test_npc_spawning`; jogadores, nenhum
+        let players = world.get_all_entities()
+            .iter()
+            .filter(|e| e.entity_type == EntityType::Player)
+            .count();
+        assert_eq!(players, 0);
     }
 
     #[test]
@@ -339,17 +563,19 @@ mod tests {
         
         // Centro deve ter água
         let center_tile = world.get_tile(Position::new(5, 5)).unwrap();
-        assert_eq!(center_tile.terrain, TerrainType::Water);
+        assert_eq!(center_tile.terrain, TerrainType::WATER);
         assert!(!center_tile.walkable);
         
         // Cantos devem ter pedra (montanhas)
         let corner_tile = world.get_tile(Position::new(0, 0)).unwrap();
-        assert_eq!(corner_tile.terrain, TerrainType::Stone);
+        assert_eq!(corner_tile.terrain, TerrainType::STONE);
     }
 
     #[test]
     fn test_spawn_entity() {
         let mut world = World::new(10, 10);
+        // Descontados os NPCs iniciais
+        let initial = world.entity_count();
         
         let id = world.spawn_entity(
             "TestPlayer".to_string(),
@@ -358,7 +584,7 @@ mod tests {
         );
         
         assert!(id.is_some());
-        assert_eq!(world.entity_count(), 1);
+        assert_eq!(world.entity_count(), initial + 1);
     }
 
     #[test]
@@ -428,6 +654,8 @@ mod tests {
     #[test]
     fn test_despawn_entity() {
         let mut world = World::new(10, 10);
+        // Descontados os NPCs iniciais
+        let initial = world.entity_count();
         
         let id = world.spawn_entity(
             "Player".to_string(),
@@ -436,31 +664,33 @@ mod tests {
         ).unwrap();
         
         assert!(world.despawn_entity(id));
-        assert_eq!(world.entity_count(), 0);
+        assert_eq!(world.entity_count(), initial);
         assert!(world.get_entity(id).is_none());
     }
 
     #[test]
     fn test_get_entities_in_region() {
         let mut world = World::new(20, 20);
+        // Longe do lago em (10, 10) e dos NPCs iniciais na diagonal
         
-        world.spawn_entity("P1".to_string(), Position::new(10, 10), EntityType::Player);
-        world.spawn_entity("P2".to_string(), Position::new(11, 11), EntityType::Player);
+        world.spawn_entity("P1".to_string(), Position::new(14, 4), EntityType::Player);
+        world.spawn_entity("P2".to_string(), Position::new(15, 5), EntityType::Player);
         world.spawn_entity("P3".to_string(), Position::new(15, 15), EntityType::Player);
         
-        let nearby = world.get_entities_in_region(Position::new(10, 10), 2);
+        let nearby = world.get_entities_in_region(Position::new(14, 4), 2);
         assert_eq!(nearby.len(), 2);
     }
 
     #[test]
     fn test_visible_snapshot() {
         let mut world = World::new(20, 20);
+        // Longe do lago em (10, 10) e dos NPCs iniciais na diagonal
         
-        world.spawn_entity("P1".to_string(), Position::new(10, 10), EntityType::Player);
+        world.spawn_entity("P1".to_string(), Position::new(14, 4), EntityType::Player);
         
-        let snapshot = world.get_visible_snapshot(Position::new(10, 10), 3);
+        let snapshot = world.get_visible_snapshot(Position::new(14, 4), 3);
         
-        assert!(snapshot.tiles.len() > 0);
+        assert!(!snapshot.tiles.is_empty());
         assert_eq!(snapshot.entities.len(), 1);
     }
 
@@ -474,6 +704,23 @@ mod tests {
         assert_eq!(world.get_current_tick(), initial_tick + 1);
     }
 
+    #[test]
+    fn test_kill_entity_records_death() {
+        let mut world = World::new(20, 20);
+        let id = world.spawn_entity("Urist".to_string(), Position::new(14, 4), EntityType::NPC).unwrap();
+
+        assert!(world.kill_entity(id, None));
+        assert!(world.get_entity(id).is_none());
+
+        let events = world.get_historical_events(1);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, EventType::Death);
+
+        let corpse = &world.get_items(Position::new(14, 4))[0];
+        assert_eq!(corpse.kind, ItemKind::Corpse { of: id });
+        assert_eq!(corpse.name, "the corpse of Urist");
+    }
+
     #[test]
     fn test_npc_spawning() {
         let world = World::new(20, 20);
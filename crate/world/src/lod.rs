@@ -0,0 +1,437 @@
+use crate::caravans::distance;
+use crate::history::Happening;
+use crate::sites::SITE_CAPACITY;
+use crate::World;
+use common::*;
+use std::collections::{HashMap, HashSet};
+
+/// Lado, em tiles, das regiões do mundo
+pub const REGION_SIZE: i32 = 32;
+/// Quantas regiões ao redor de cada jogador são simuladas em detalhe
+pub const DETAIL_RADIUS: i32 = 1;
+/// Intervalo, em ticks, da simulação grossa das regiões distantes
+pub const COARSE_INTERVAL: u64 = 10;
+/// Chance, a cada salto, de uma entidade com IA topar com um inimigo da
+/// mesma região
+const ENCOUNTER_CHANCE: f32 = 0.3;
+/// Chance, a cada salto, de cada morador de um local com comida de sobra
+/// ter um filho
+const BIRTH_CHANCE: f32 = 0.01;
+/// Chance, a cada salto, de um local em guerra atacar o inimigo mais próximo
+const BATTLE_CHANCE: f32 = 0.05;
+/// Distância máxima, em tiles, entre locais que se atacam
+const BATTLE_RANGE: i32 = REGION_SIZE * 2;
+/// Moradores abaixo dos quais um local atacado cai
+const CONQUEST_FLOOR: usize = 5;
+
+/// Coordenadas de uma região (em regiões, não em tiles)
+pub type Region = (i32, i32);
+
+pub fn region_of(pos: Position) -> Region {
+    (pos.x.div_euclid(REGION_SIZE), pos.y.div_euclid(REGION_SIZE))
+}
+
+impl World {
+    /// Regiões simuladas tick a tick: as que estão perto de algum jogador.
+    /// As demais avançam aos saltos, a cada `COARSE_INTERVAL` ticks. Quem
+    /// cruza a fronteira muda de nível sozinho, pela posição.
+    pub fn detailed_regions(&self) -> HashSet<Region> {
+        let mut regions = HashSet::new();
+        for entity in self.entity_layer.values() {
+            if entity.entity_type != EntityType::Player {
+                continue;
+            }
+            let (rx, ry) = region_of(entity.pos);
+            for dy in -DETAIL_RADIUS..=DETAIL_RADIUS {
+                for dx in -DETAIL_RADIUS..=DETAIL_RADIUS {
+                    regions.insert((rx + dx, ry + dy));
+                }
+            }
+        }
+        regions
+    }
+
+    /// Posição atual de uma caravana na rota
+    fn caravan_position(&self, id: u32) -> Option<Position> {
+        let caravan = self.caravans.get(&id)?;
+        caravan.route.get(caravan.progress.saturating_sub(1)).copied()
+    }
+
+    /// Caravanas perto de jogadores (ou longe deles), ordenadas por ID
+    fn caravans_where(&self, detailed: &HashSet<Region>, near_players: bool) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .caravans
+            .keys()
+            .copied()
+            .filter(|id| {
+                let pos = self.caravan_position(*id);
+                pos.is_some_and(|pos| detailed.contains(&region_of(pos))) == near_players
+            })
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Caravanas perto de jogadores andam tick a tick
+    pub(crate) fn update_detailed_caravans(&mut self) {
+        let detailed = self.detailed_regions();
+        if detailed.is_empty() {
+            return;
+        }
+        for id in self.caravans_where(&detailed, true) {
+            self.advance_caravan(id, 1);
+        }
+    }
+
+    /// Entidades com IA fora das regiões detalhadas, ordenadas por ID
+    fn coarse_behaviors(&self, detailed: &HashSet<Region>) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .ai_behaviors
+            .keys()
+            .copied()
+            .filter(|id| self.entity_layer.get(id).is_some_and(|e| !detailed.contains(&region_of(e.pos))))
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Locais habitados, com dono, fora das regiões detalhadas, ordenados
+    /// por ID
+    fn coarse_sites(&self, detailed: &HashSet<Region>) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .sites
+            .values()
+            .filter(|s| s.is_inhabited() && s.owner.is_some() && !detailed.contains(&region_of(s.center)))
+            .map(|s| s.id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Simulação grossa das regiões sem jogadores: patrulhas dão de uma vez
+    /// os passos do intervalo, parando onde a IA de cada tick pararia; quem
+    /// vaga dá os mesmos passos em direções sorteadas; inimigos da mesma
+    /// região se encontram por sorteio; caravanas avançam `COARSE_INTERVAL`
+    /// ticks de uma vez, com emboscadas sorteadas para o trecho inteiro. Os
+    /// locais crescem e guerreiam por estatística (a produção já é, veja
+    /// `run_economy`).
+    pub(crate) fn simulate_coarse(&mut self) {
+        let detailed = self.detailed_regions();
+
+        let mut patrols: Vec<(u32, Position)> = self
+            .ai_behaviors
+            .iter()
+            .filter_map(|(id, behavior)| match behavior.current_goal {
+                AIGoal::Patrol { start, end } => {
+                    let target = if (self.current_tick / 50).is_multiple_of(2) {
+                        start
+                    } else {
+                        end
+                    };
+                    Some((*id, target))
+                }
+                _ => None,
+            })
+            .collect();
+        patrols.sort_unstable_by_key(|(id, _)| *id);
+        for (id, target) in patrols {
+            let Some(pos) = self.entity_layer.get(&id).map(|e| e.pos) else {
+                continue;
+            };
+            if detailed.contains(&region_of(pos)) {
+                continue;
+            }
+            let mut pos = pos;
+            for _ in 0..COARSE_INTERVAL {
+                let (dx, dy) = ((target.x - pos.x).signum(), (target.y - pos.y).signum());
+                if (dx, dy) == (0, 0) || !self.move_entity(id, dx, dy) {
+                    break;
+                }
+                pos = pos.moved(dx, dy);
+            }
+        }
+
+        self.coarse_wanderers(&detailed);
+        self.coarse_encounters(&detailed);
+        for id in self.caravans_where(&detailed, false) {
+            self.advance_caravan(id, COARSE_INTERVAL);
+        }
+        self.coarse_population(&detailed);
+        self.coarse_battles(&detailed);
+    }
+
+    /// Quem vaga dá de uma vez os passos do intervalo, cada um numa direção
+    /// sorteada; um passo bloqueado é perdido
+    fn coarse_wanderers(&mut self, detailed: &HashSet<Region>) {
+        for id in self.coarse_behaviors(detailed) {
+            if !matches!(self.ai_behaviors[&id].current_goal, AIGoal::Wander) {
+                continue;
+            }
+            for _ in 0..COARSE_INTERVAL {
+                let (dx, dy) = (self.rng.range(-1, 2), self.rng.range(-1, 2));
+                if (dx, dy) != (0, 0) {
+                    self.move_entity(id, dx, dy);
+                }
+            }
+        }
+    }
+
+    /// Cada entidade com IA pode topar com um inimigo da mesma região, sem
+    /// precisar chegar ao lado dele; a luta é a mesma das regiões detalhadas
+    fn coarse_encounters(&mut self, detailed: &HashSet<Region>) {
+        let mut by_region: HashMap<Region, Vec<u32>> = HashMap::new();
+        for entity in self.entity_layer.values() {
+            let region = region_of(entity.pos);
+            if !detailed.contains(&region) {
+                by_region.entry(region).or_default().push(entity.id);
+            }
+        }
+        for ids in by_region.values_mut() {
+            ids.sort_unstable();
+        }
+
+        for attacker in self.coarse_behaviors(detailed) {
+            // Pode ter morrido num encontro anterior do mesmo salto
+            let Some(pos) = self.entity_layer.get(&attacker).map(|e| e.pos) else {
+                continue;
+            };
+            if !self.rng.chance(ENCOUNTER_CHANCE) {
+                continue;
+            }
+            let defender = by_region.get(&region_of(pos)).and_then(|ids| {
+                ids.iter().copied().find(|id| {
+                    *id != attacker && self.entity_layer.get(id).is_some_and(|e| self.is_enemy(attacker, e))
+                })
+            });
+            if let Some(defender) = defender {
+                self.fight(attacker, defender);
+            }
+        }
+    }
+
+    /// Locais com comida de sobra crescem: cada morador tem uma chance de
+    /// ter um filho a cada salto, até a capacidade do local
+    fn coarse_population(&mut self, detailed: &HashSet<Region>) {
+        for site_id in self.coarse_sites(detailed) {
+            let site = &self.sites[&site_id];
+            if site.surplus(Resource::Food) == 0 {
+                continue;
+            }
+            let population = site.population;
+            let births = (0..population).filter(|_| self.rng.chance(BIRTH_CHANCE)).count();
+            let Some(site) = self.sites.get_mut(&site_id) else {
+                continue;
+            };
+            site.population = (site.population + births).min(SITE_CAPACITY);
+            let owner = site.owner;
+            self.refresh_member_count(owner);
+        }
+    }
+
+    /// Locais de fações em guerra atacam o local inimigo mais próximo, a até
+    /// `BATTLE_RANGE` tiles. O lado mais populoso vence com mais chance; o
+    /// perdedor perde um décimo dos moradores do vencedor, e o local atacado
+    /// que fica com até `CONQUEST_FLOOR` moradores é conquistado.
+    fn coarse_battles(&mut self, detailed: &HashSet<Region>) {
+        for site_id in self.coarse_sites(detailed) {
+            // Pode ter caído numa batalha anterior do mesmo salto
+            let Some(site) = self.sites.get(&site_id).filter(|s| s.is_inhabited()) else {
+                continue;
+            };
+            let (Some(attacker), center) = (site.owner, site.center) else {
+                continue;
+            };
+            let at_war = |owner: u32| {
+                self.factions
+                    .get(&attacker)
+                    .and_then(|f| f.relations.get(&owner))
+                    .is_some_and(|r| matches!(r, Relation::War))
+            };
+            let target = self
+                .sites
+                .values()
+                .filter(|s| s.is_inhabited() && !detailed.contains(&region_of(s.center)))
+                .filter_map(|s| Some((distance(center, s.center), s.id, s.owner.filter(|o| at_war(*o))?)))
+                .filter(|(d, _, _)| *d <= BATTLE_RANGE)
+                .min();
+            let Some((_, target, defender)) = target else {
+                continue;
+            };
+            if !self.rng.chance(BATTLE_CHANCE) {
+                continue;
+            }
+
+            let (attacking, defending) = (self.sites[&site_id].population, self.sites[&target].population);
+            let attacker_won = self.rng.chance(attacking as f32 / (attacking + defending).max(1) as f32);
+            let (loser, losses, loser_faction) = if attacker_won {
+                (target, attacking / 10, defender)
+            } else {
+                (site_id, defending / 10, attacker)
+            };
+            let location = self.sites[&target].center;
+            self.report_happening(Happening::Battle {
+                attacker,
+                defender,
+                site_id: target,
+                attacker_won,
+                location,
+            });
+            if let Some(site) = self.sites.get_mut(&loser) {
+                site.population = site.population.saturating_sub(losses.max(1)).max(1);
+            }
+            self.refresh_member_count(Some(loser_faction));
+
+            if attacker_won && self.sites[&target].population <= CONQUEST_FLOOR {
+                self.report_happening(Happening::Conquest {
+                    conqueror_faction: attacker,
+                    defeated_faction: defender,
+                    location,
+                });
+                self.conquer_site(target, attacker);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::STOCKPILE_CAP;
+    use crate::{SiteKind, Stockpile};
+
+    #[test]
+    fn test_distant_caravan_travels_coarsely_until_a_player_is_near() {
+        let mut world = World::new(100, 100);
+        let faction = world.add_faction("The Tin Guild", FactionType::new("dwarf"));
+        let origin = world.found_site(
+            "Ashford".to_string(),
+            SiteKind::Town,
+            Some(faction),
+            Position::new(10, 10),
+            40,
+        );
+        let destination = world.found_site(
+            "Deephold".to_string(),
+            SiteKind::Town,
+            Some(faction),
+            Position::new(85, 85),
+            40,
+        );
+        let cargo = Stockpile {
+            food: 50,
+            ..Default::default()
+        };
+        let id = world.dispatch_caravan(origin, destination, cargo).unwrap();
+
+        // Sem jogadores, a caravana só anda nos saltos
+        for _ in 0..COARSE_INTERVAL - 1 {
+            world.tick();
+        }
+        assert_eq!(world.get_caravan(id).unwrap().progress, 0);
+        world.tick();
+        let jumped = world.get_caravan(id).unwrap().progress;
+        assert!(jumped >= COARSE_INTERVAL as usize);
+
+        // Um jogador chega perto: volta a andar tick a tick
+        let pos = world.caravan_position(id).unwrap();
+        world.spawn_entity("Hero".to_string(), pos, EntityType::Player).unwrap();
+        let route = world.get_caravan(id).unwrap().route.clone();
+        let on_road = |i: usize| route.get(i).is_some_and(|p| world.is_road(*p));
+        let step = if on_road(jumped) && on_road(jumped + 1) { 2 } else { 1 };
+        world.tick();
+        assert_eq!(world.get_caravan(id).unwrap().progress, jumped + step);
+    }
+
+    fn give_goal(world: &mut World, id: u32, current_goal: AIGoal) {
+        world.ai_behaviors.insert(
+            id,
+            AIBehavior {
+                current_goal,
+                memory: Vec::new(),
+                personality: Personality {
+                    aggression: 0.5,
+                    curiosity: 0.5,
+                    sociability: 0.5,
+                },
+            },
+        );
+    }
+
+    #[test]
+    fn test_distant_wanderers_roam_and_meet_their_enemies() {
+        let mut world = World::new(100, 100);
+        let villager = world.spawn_creature("villager", "Urist".to_string(), Position::new(80, 80)).unwrap();
+        give_goal(&mut world, villager, AIGoal::Wander);
+        let start = world.get_entity(villager).unwrap().pos;
+        for _ in 0..COARSE_INTERVAL {
+            world.tick();
+        }
+        assert_ne!(world.get_entity(villager).unwrap().pos, start);
+
+        // Um caçador na mesma região, longe demais para lutar tick a tick
+        let wolf = world.spawn_creature("wolf", "Wolf".to_string(), Position::new(70, 70)).unwrap();
+        give_goal(&mut world, wolf, AIGoal::Hunt);
+        for _ in 0..50 {
+            world.simulate_coarse();
+        }
+        let survivors = [wolf, villager].iter().filter(|id| world.get_entity(**id).is_some()).count();
+        assert_eq!(survivors, 1);
+    }
+
+    #[test]
+    fn test_distant_sites_grow_and_go_to_war() {
+        let mut world = World::new(100, 100);
+        let a = world.add_faction("The River Kingdom", FactionType::new("human"));
+        let b = world.add_faction("The Copper Brotherhood", FactionType::new("dwarf"));
+        let ashford = world.found_site("Ashford".to_string(), SiteKind::Town, Some(a), Position::new(20, 20), 40);
+        let deephold = world.found_site("Deephold".to_string(), SiteKind::Town, Some(b), Position::new(50, 20), 40);
+        world.sites.get_mut(&ashford).unwrap().stockpile.food = STOCKPILE_CAP;
+
+        for _ in 0..20 {
+            world.simulate_coarse();
+        }
+        assert!(world.get_site(ashford).unwrap().population > 40);
+        assert_eq!(world.get_site(deephold).unwrap().population, 40);
+
+        world.set_relation(a, b, Relation::War);
+        for _ in 0..200 {
+            world.simulate_coarse();
+        }
+        let battles = world
+            .get_historical_events(1000)
+            .into_iter()
+            .filter(|e| e.event_type == EventType::Combat)
+            .count();
+        assert!(battles > 0);
+        let deephold = world.get_site(deephold).unwrap();
+        assert!(deephold.population < 40 || !deephold.is_inhabited());
+    }
+
+    #[test]
+    fn test_coarse_patrol_stops_at_water() {
+        let mut world = World::new(100, 100);
+        let id = world.spawn_entity("Sentry".to_string(), Position::new(44, 50), EntityType::NPC).unwrap();
+        // O lago do centro fica entre a patrulha e o alvo
+        let target = Position::new(56, 50);
+        world.ai_behaviors.insert(
+            id,
+            AIBehavior {
+                current_goal: AIGoal::Patrol {
+                    start: target,
+                    end: target,
+                },
+                memory: Vec::new(),
+                personality: Personality {
+                    aggression: 0.0,
+                    curiosity: 0.0,
+                    sociability: 0.0,
+                },
+            },
+        );
+
+        for _ in 0..COARSE_INTERVAL {
+            world.tick();
+        }
+        assert_eq!(world.get_entity(id).unwrap().pos, Position::new(47, 50));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::persistence::SAVE_FORMAT_VERSION;
+use serde_json::{Map, Value};
+
+/// Atualiza um save cru de uma versão para a seguinte
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// Cadeia de migrações: `MIGRATIONS[i]` leva da versão `i + 1` para a
+/// `i + 2`. Toda mudança no formato (inclusive nos tipos de `common`
+/// gravados no save) sobe `SAVE_FORMAT_VERSION`, acrescenta um passo aqui
+/// e um save de exemplo em `tests/fixtures/saves`.
+const MIGRATIONS: [Migration; 5] = [v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6];
+const _: () = assert!(MIGRATIONS.len() as u32 + 1 == SAVE_FORMAT_VERSION);
+
+/// Versão de um save cru: no cabeçalho a partir da v2, na raiz na v1
+pub fn save_version(save: &Value) -> Option<u32> {
+    save.pointer("/header/format_version")
+        .or_else(|| save.get("format_version"))
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+}
+
+/// Atualiza um save cru, passo a passo, até a versão atual; retorna a
+/// versão original
+pub fn migrate(save: &mut Value) -> Result<u32, String> {
+    let original = save_version(save).ok_or("Save sem versão de formato")?;
+    if original == 0 || original > SAVE_FORMAT_VERSION {
+        return Err(format!(
+            "Formato de mundo salvo v{} não é suportado (atual: v{})",
+            original, SAVE_FORMAT_VERSION
+        ));
+    }
+
+    let fields = save.as_object_mut().ok_or("Save não é um objeto JSON")?;
+    for (step, migration) in MIGRATIONS.iter().enumerate().skip(original as usize - 1) {
+        migration(fields).map_err(|e| format!("Migração v{} → v{}: {}", step + 1, step + 2, e))?;
+    }
+    Ok(original)
+}
+
+fn take_field(fields: &mut Map<String, Value>, name: &str) -> Result<Value, String> {
+    fields.remove(name).ok_or_else(|| format!("campo `{}` ausente", name))
+}
+
+/// Cabeçalho de um save v2 ou mais recente
+fn header_mut(fields: &mut Map<String, Value>) -> Result<&mut Map<String, Value>, String> {
+    fields
+        .get_mut("header")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| "campo `header` ausente".to_string())
+}
+
+/// v2: versão, semente, dimensões e tick vão para um cabeçalho no início
+/// do arquivo, legível sem interpretar o mundo inteiro; o resto do estado
+/// fica sob `world`
+fn v1_to_v2(fields: &mut Map<String, Value>) -> Result<(), String> {
+    take_field(fields, "format_version")?;
+    let mut header = Map::new();
+    header.insert("format_version".to_string(), Value::from(2));
+    for name in ["seed", "width", "height", "current_tick"] {
+        header.insert(name.to_string(), take_field(fields, name)?);
+    }
+    let world = std::mem::take(fields);
+    fields.insert("header".to_string(), Value::Object(header));
+    fields.insert("world".to_string(), Value::Object(world));
+    Ok(())
+}
+
+/// v3: entidades dizem de que criatura dos raws são (`creature`). As
+/// antigas ficam sem, e valem pela criatura padrão do seu tipo.
+fn v2_to_v3(fields: &mut Map<String, Value>) -> Result<(), String> {
+    let header = header_mut(fields)?;
+    header.insert("format_version".to_string(), Value::from(3));
+    Ok(())
+}
+
+/// v4: o cabeçalho lista os mods ativos (`mods`); saves antigos são de
+/// mundos sem mods
+fn v3_to_v4(fields: &mut Map<String, Value>) -> Result<(), String> {
+    let header = header_mut(fields)?;
+    header.insert("format_version".to_string(), Value::from(4));
+    header.insert("mods".to_string(), Value::Array(Vec::new()));
+    Ok(())
+}
+
+/// v5: camada de superfícies (`surfaces`) e tiles à espera das regras de
+/// interação (`changed_tiles`), ambos vazios nos saves antigos
+fn v4_to_v5(fields: &mut Map<String, Value>) -> Result<(), String> {
+    header_mut(fields)?.insert("format_version".to_string(), Value::from(5));
+    let world = fields
+        .get_mut("world")
+        .and_then(Value::as_object_mut)
+        .ok_or("campo `world` ausente")?;
+    world.insert("surfaces".to_string(), Value::Array(Vec::new()));
+    world.insert("changed_tiles".to_string(), Value::Array(Vec::new()));
+    Ok(())
+}
+
+/// v6: terrenos e povos são IDs dos raws (`"grass"`, `"human"`) em vez dos
+/// nomes das antigas variantes (`"Grass"`, `"Human"`)
+fn v5_to_v6(fields: &mut Map<String, Value>) -> Result<(), String> {
+    header_mut(fields)?.insert("format_version".to_string(), Value::from(6));
+    let world = fields
+        .get_mut("world")
+        .and_then(Value::as_object_mut)
+        .ok_or("campo `world` ausente")?;
+    if let Some(tiles) = world.get_mut("terrain").and_then(Value::as_array_mut) {
+        for tile in tiles.iter_mut().filter_map(|entry| entry.get_mut(1)) {
+            lowercase_field(tile, "terrain");
+        }
+    }
+    if let Some(factions) = world.get_mut("factions").and_then(Value::as_array_mut) {
+        for faction in factions {
+            lowercase_field(faction, "faction_type");
+        }
+    }
+    Ok(())
+}
+
+fn lowercase_field(value: &mut Value, name: &str) {
+    if let Some(field) = value.get_mut(name) {
+        if let Some(id) = field.as_str() {
+            *field = Value::from(id.to_lowercase());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{World, WorldSave};
+    use std::path::Path;
+
+    #[test]
+    fn test_every_fixture_loads() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/saves");
+        let mut versions = Vec::new();
+        for file in std::fs::read_dir(&dir).unwrap() {
+            let path = file.unwrap().path();
+            let json = std::fs::read_to_string(&path).unwrap();
+            let raw: Value = serde_json::from_str(&json).unwrap();
+            versions.push(save_version(&raw).unwrap());
+
+            let mut world = World::from_save(WorldSave::from_json(&json).unwrap())
+                .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            assert!(!world.get_sites().is_empty(), "{}", path.display());
+            for _ in 0..100 {
+                world.tick();
+            }
+        }
+        // Um exemplo por versão, inclusive a atual
+        versions.sort_unstable();
+        assert_eq!(versions, (1..=SAVE_FORMAT_VERSION).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_v1_header_moves_into_place() {
+        let mut save = serde_json::json!({
+            "format_version": 1,
+            "seed": 37,
+            "width": 32,
+            "height": 32,
+            "current_tick": 20030,
+            "terrain": [],
+        });
+        assert_eq!(migrate(&mut save), Ok(1));
+        assert_eq!(save_version(&save), Some(SAVE_FORMAT_VERSION));
+        assert_eq!(save.pointer("/header/seed"), Some(&Value::from(37)));
+        assert_eq!(save.pointer("/world/terrain"), Some(&serde_json::json!([])));
+        assert!(save.get("width").is_none());
+
+        let mut broken = serde_json::json!({ "format_version": 1, "seed": 37 });
+        assert!(migrate(&mut broken).unwrap_err().contains("v1 → v2"));
+    }
+}
@@ -0,0 +1,338 @@
+use crate::raws::{read_json_dir, RawsFile};
+use crate::Raws;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// Pasta dos mods, relativa à raiz do projeto; cada mod é uma subpasta
+pub const MODS_DIR: &str = "mods";
+/// Manifesto de cada mod; os demais `.json` da pasta são raws
+pub const MOD_MANIFEST: &str = "mod.json";
+
+/// Manifesto de um mod
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ModManifest {
+    pub id: String,
+    /// Versão no formato `1.2.3`
+    pub version: String,
+    #[serde(default)]
+    pub name: String,
+    /// Mods exigidos, com a versão mínima de cada um
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+}
+
+/// Mod ativo, como gravado no cabeçalho dos saves
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ModRef {
+    pub id: String,
+    pub version: String,
+}
+
+impl fmt::Display for ModRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.id, self.version)
+    }
+}
+
+/// Um mod lido do disco: manifesto e arquivos de raws `(nome, json)`
+#[derive(Debug, Clone)]
+pub struct Mod {
+    pub manifest: ModManifest,
+    pub files: Vec<(String, String)>,
+}
+
+/// Definição redefinida por mais de um mod sem que um dependa do outro;
+/// vale a do último na ordem de carga
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ModConflict {
+    pub category: String,
+    pub id: String,
+    /// Mods que redefinem, em ordem de carga
+    pub mods: Vec<String>,
+}
+
+impl fmt::Display for ModConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let winner = self.mods.last().map(String::as_str).unwrap_or_default();
+        write!(
+            f,
+            "{} `{}` redefinido por {} (vale {})",
+            self.category,
+            self.id,
+            self.mods.join(", "),
+            winner
+        )
+    }
+}
+
+/// Resultado da carga dos mods
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ModReport {
+    /// Mods aplicados, em ordem de carga
+    pub order: Vec<ModRef>,
+    pub conflicts: Vec<ModConflict>,
+}
+
+impl Mod {
+    /// Lê um mod da sua pasta
+    pub fn load_dir(dir: &Path) -> Result<Mod, String> {
+        let manifest_path = dir.join(MOD_MANIFEST);
+        let json = std::fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Não foi possível ler {}: {}", manifest_path.display(), e))?;
+        let manifest: ModManifest =
+            serde_json::from_str(&json).map_err(|e| format!("{}: {}", manifest_path.display(), e))?;
+        let files = read_json_dir(dir, &[MOD_MANIFEST])?;
+        Ok(Mod { manifest, files })
+    }
+
+    fn to_ref(&self) -> ModRef {
+        ModRef {
+            id: self.manifest.id.clone(),
+            version: self.manifest.version.clone(),
+        }
+    }
+}
+
+/// Mods de uma pasta (um por subpasta); sem a pasta, nenhum
+pub fn load_mods(dir: impl AsRef<Path>) -> Result<Vec<Mod>, String> {
+    let dir = dir.as_ref();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Não foi possível ler {}: {}", dir.display(), e))?;
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    paths.sort();
+    paths.iter().map(|path| Mod::load_dir(path)).collect()
+}
+
+fn parse_version(version: &str) -> Result<Vec<u32>, String> {
+    version
+        .split('.')
+        .map(|part| part.parse().map_err(|_| format!("versão inválida `{}`", version)))
+        .collect()
+}
+
+/// `version` é pelo menos `minimum`? (`1.2` == `1.2.0`)
+fn satisfies(version: &str, minimum: &str) -> Result<bool, String> {
+    let (mut version, mut minimum) = (parse_version(version)?, parse_version(minimum)?);
+    let len = version.len().max(minimum.len());
+    version.resize(len, 0);
+    minimum.resize(len, 0);
+    Ok(version >= minimum)
+}
+
+/// Ordem de carga: cada mod depois das suas dependências; entre os que
+/// podem carregar, o de menor ID primeiro. Retorna índices de `mods`.
+pub fn load_order(mods: &[Mod]) -> Result<Vec<usize>, String> {
+    let mut by_id: BTreeMap<&str, usize> = BTreeMap::new();
+    for (i, m) in mods.iter().enumerate() {
+        parse_version(&m.manifest.version).map_err(|e| format!("mod `{}`: {}", m.manifest.id, e))?;
+        if by_id.insert(&m.manifest.id, i).is_some() {
+            return Err(format!("Mod `{}` instalado mais de uma vez", m.manifest.id));
+        }
+    }
+
+    let mut problems = Vec::new();
+    for m in mods {
+        for (dependency, minimum) in &m.manifest.dependencies {
+            match by_id.get(dependency.as_str()).map(|i| &mods[*i]) {
+                None => problems.push(format!(
+                    "mod `{}` precisa de `{}`, que não está instalado",
+                    m.manifest.id, dependency
+                )),
+                Some(found) => {
+                    let ok = satisfies(&found.manifest.version, minimum)
+                        .map_err(|e| format!("mod `{}`: {}", m.manifest.id, e))?;
+                    if !ok {
+                        problems.push(format!(
+                            "mod `{}` precisa de `{}` {} ou mais recente (instalado: {})",
+                            m.manifest.id, dependency, minimum, found.manifest.version
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    if !problems.is_empty() {
+        return Err(format!("Mods incompatíveis:\n  - {}", problems.join("\n  - ")));
+    }
+
+    let mut order = Vec::new();
+    let mut loaded: BTreeSet<&str> = BTreeSet::new();
+    while order.len() < mods.len() {
+        let next = by_id.iter().find(|(id, i)| {
+            !loaded.contains(*id)
+                && mods[**i]
+                    .manifest
+                    .dependencies
+                    .keys()
+                    .all(|d| loaded.contains(d.as_str()))
+        });
+        let Some((id, i)) = next else {
+            let stuck: Vec<&str> = by_id.keys().filter(|id| !loaded.contains(*id)).copied().collect();
+            return Err(format!("Dependência circular entre os mods {}", stuck.join(", ")));
+        };
+        loaded.insert(id);
+        order.push(*i);
+    }
+    Ok(order)
+}
+
+/// Todas as dependências de um mod, diretas ou não
+fn requirements<'a>(mods: &'a [Mod], by_id: &HashMap<&str, usize>, i: usize) -> BTreeSet<&'a str> {
+    let mut found = BTreeSet::new();
+    let mut pending: Vec<&str> = mods[i].manifest.dependencies.keys().map(String::as_str).collect();
+    while let Some(id) = pending.pop() {
+        if found.insert(id) {
+            if let Some(&j) = by_id.get(id) {
+                pending.extend(mods[j].manifest.dependencies.keys().map(String::as_str));
+            }
+        }
+    }
+    found
+}
+
+impl Raws {
+    /// Raws de uma pasta com os mods de outra aplicados por cima
+    pub fn load_with_mods(raws_dir: impl AsRef<Path>, mods_dir: impl AsRef<Path>) -> Result<(Raws, ModReport), String> {
+        let files = read_json_dir(raws_dir.as_ref(), &[])?;
+        Raws::with_mods(&files, &load_mods(mods_dir)?)
+    }
+
+    /// Aplica os mods, em ordem de carga, sobre os arquivos de raws do
+    /// jogo e valida o resultado
+    pub fn with_mods(files: &[(String, String)], mods: &[Mod]) -> Result<(Raws, ModReport), String> {
+        let (mut raws, mut problems) = Raws::merge_files(files)?;
+        let order = load_order(mods)?;
+        let by_id: HashMap<&str, usize> = mods
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (m.manifest.id.as_str(), i))
+            .collect();
+
+        // Quem redefiniu cada definição, em ordem de carga
+        let mut owners: BTreeMap<(&str, String), Vec<usize>> = BTreeMap::new();
+        for &i in &order {
+            let (merged, mod_problems) = Raws::merge_files(&mods[i].files)?;
+            problems.extend(
+                mod_problems
+                    .into_iter()
+                    .map(|p| format!("mod `{}`: {}", mods[i].manifest.id, p)),
+            );
+            let file = RawsFile {
+                materials: merged.materials,
+                terrains: merged.terrains,
+                creatures: merged.creatures,
+                items: merged.items,
+                factions: merged.factions,
+                surfaces: merged.surfaces,
+                rules: merged.rules,
+            };
+            for (category, id) in file.ids() {
+                owners.entry((category, id.to_string())).or_default().push(i);
+            }
+            raws.merge(file);
+        }
+
+        let mut report = ModReport {
+            order: order.iter().map(|&i| mods[i].to_ref()).collect(),
+            conflicts: Vec::new(),
+        };
+        for ((category, id), defined_by) in owners {
+            // Redefinir o que uma dependência definiu é intencional
+            let clash = defined_by.iter().enumerate().skip(1).any(|(n, &later)| {
+                let required = requirements(mods, &by_id, later);
+                defined_by[..n]
+                    .iter()
+                    .any(|&earlier| !required.contains(mods[earlier].manifest.id.as_str()))
+            });
+            if clash {
+                report.conflicts.push(ModConflict {
+                    category: category.to_string(),
+                    id,
+                    mods: defined_by.iter().map(|&i| mods[i].manifest.id.clone()).collect(),
+                });
+            }
+        }
+
+        raws.mods = report.order.clone();
+        Ok((raws.validated(problems)?, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{World, WorldSave};
+
+    fn base_files() -> Vec<(String, String)> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../raws");
+        read_json_dir(&dir, &[]).unwrap()
+    }
+
+    fn make_mod(id: &str, dependencies: &[&str], raws: &str) -> Mod {
+        Mod {
+            manifest: ModManifest {
+                id: id.to_string(),
+                version: "1.0.0".to_string(),
+                name: String::new(),
+                dependencies: dependencies
+                    .iter()
+                    .map(|d| (d.to_string(), "1.0".to_string()))
+                    .collect(),
+            },
+            files: vec![(format!("{}/raws.json", id), raws.to_string())],
+        }
+    }
+
+    #[test]
+    fn test_mods_load_after_dependencies_and_report_conflicts() {
+        let troll = r##"{ "creatures": [{
+            "id": "troll", "name": "cave troll", "kind": "NPC", "body_plan": ["head", "torso"], "size": 250,
+            "material": "flesh", "glyph": { "ch": "T", "fg": "#7a7" }
+        }] }"##;
+        let blue_troll = troll.replace("#7a7", "#33f");
+        let red_troll = troll.replace("#7a7", "#f33");
+        let mods = [
+            make_mod("zz-trolls", &[], troll),
+            make_mod("blue", &["zz-trolls"], &blue_troll),
+            make_mod("red", &["zz-trolls"], &red_troll),
+        ];
+
+        let (raws, report) = Raws::with_mods(&base_files(), &mods).unwrap();
+        let order: Vec<&str> = report.order.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(order, ["zz-trolls", "blue", "red"]);
+        assert_eq!(raws.creature("troll").unwrap().glyph.fg, "#f33");
+        assert_eq!(raws.mods, report.order);
+        // blue e red redefinem o troll de zz-trolls, do qual dependem; o
+        // conflito é só entre os dois
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].mods, ["zz-trolls", "blue", "red"]);
+
+        let missing = [make_mod("blue", &["zz-trolls"], &blue_troll)];
+        assert!(Raws::with_mods(&base_files(), &missing)
+            .unwrap_err()
+            .contains("`zz-trolls`, que não está instalado"));
+        let cycle = [make_mod("a", &["b"], "{}"), make_mod("b", &["a"], "{}")];
+        assert!(load_order(&cycle).unwrap_err().contains("circular"));
+    }
+
+    #[test]
+    fn test_save_remembers_its_mods() {
+        let mut save = World::new(20, 20).to_save();
+        assert!(save.header.mods.is_empty());
+        save.header.mods = vec![ModRef {
+            id: "trolls".to_string(),
+            version: "1.0.0".to_string(),
+        }];
+        let save = WorldSave::from_json(&save.to_json().unwrap()).unwrap();
+        let error = World::from_save(save).err().unwrap();
+        assert!(error.contains("trolls 1.0.0"), "{}", error);
+    }
+}
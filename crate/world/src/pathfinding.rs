@@ -0,0 +1,133 @@
+use crate::World;
+use common::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Custo de um passo reto; diagonais custam ~√2 vezes mais
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+/// Em estradas, cada passo custa 30% menos
+const ROAD_STRAIGHT_COST: u32 = 7;
+const ROAD_DIAGONAL_COST: u32 = 10;
+
+const NEIGHBORS: [(i32, i32); 8] = [(0, -1), (1, 0), (0, 1), (-1, 0), (1, -1), (1, 1), (-1, 1), (-1, -1)];
+
+/// Distância octil a preço de estrada: estimativa admissível para
+/// movimento em 8 direções, mesmo que o caminho todo seja estrada
+fn heuristic(a: Position, b: Position) -> u32 {
+    let dx = (a.x - b.x).unsigned_abs();
+    let dy = (a.y - b.y).unsigned_abs();
+    ROAD_STRAIGHT_COST * dx.max(dy) + (ROAD_DIAGONAL_COST - ROAD_STRAIGHT_COST) * dx.min(dy)
+}
+
+fn step_cost(diagonal: bool, road: bool) -> u32 {
+    match (diagonal, road) {
+        (false, false) => STRAIGHT_COST,
+        (true, false) => DIAGONAL_COST,
+        (false, true) => ROAD_STRAIGHT_COST,
+        (true, true) => ROAD_DIAGONAL_COST,
+    }
+}
+
+impl World {
+    /// Caminho mais barato (A*) entre duas posições transitáveis.
+    ///
+    /// Retorna os passos depois de `from`, terminando em `to`; vazio se
+    /// já está no destino. Entidades não bloqueiam o caminho.
+    pub fn find_path(&self, from: Position, to: Position) -> Option<Vec<Position>> {
+        if !self.is_walkable(from) || !self.is_walkable(to) {
+            return None;
+        }
+
+        // Custos e predecessores indexados por tile, em vez de mapas
+        let index = |pos: Position| (pos.y * self.width + pos.x) as usize;
+        let tiles = (self.width * self.height) as usize;
+        let mut came_from: Vec<Option<Position>> = vec![None; tiles];
+        let mut best = vec![u32::MAX; tiles];
+        best[index(from)] = 0;
+
+        let mut open = BinaryHeap::new();
+        // Empates são desfeitos pela posição, para o caminho ser determinístico
+        open.push(Reverse((heuristic(from, to), 0, from.y, from.x)));
+
+        while let Some(Reverse((_, cost, y, x))) = open.pop() {
+            let current = Position::new(x, y);
+            if current == to {
+                let mut path = Vec::new();
+                let mut node = to;
+                while node != from {
+                    path.push(node);
+                    node = came_from[index(node)]?;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if cost > best[index(current)] {
+                continue;
+            }
+
+            for (dx, dy) in NEIGHBORS {
+                let next = current.moved(dx, dy);
+                if !self.is_walkable(next) {
+                    continue;
+                }
+                let next_cost = cost + step_cost(dx != 0 && dy != 0, self.is_road(next));
+                if next_cost >= best[index(next)] {
+                    continue;
+                }
+                best[index(next)] = next_cost;
+                came_from[index(next)] = Some(current);
+                open.push(Reverse((next_cost + heuristic(next, to), next_cost, next.y, next.x)));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SiteKind;
+
+    #[test]
+    fn test_path_goes_around_water() {
+        let world = World::new(40, 40);
+        let from = Position::new(10, 20);
+        let to = Position::new(30, 20);
+
+        let path = world.find_path(from, to).unwrap();
+        assert_eq!(path.last(), Some(&to));
+        assert!(path.iter().all(|p| world.is_walkable(*p)));
+        assert!(path.windows(2).all(|w| (w[0].x - w[1].x).abs() <= 1 && (w[0].y - w[1].y).abs() <= 1));
+        // O lago no centro obriga um desvio
+        assert!(!path.contains(&Position::new(20, 20)));
+    }
+
+    #[test]
+    fn test_path_leaves_walled_site_through_door() {
+        let mut world = World::new(40, 40);
+        let center = Position::new(10, 10);
+        world.found_site("Stonegate".to_string(), SiteKind::Town, None, center, 30);
+
+        let path = world.find_path(center, Position::new(10, 5)).unwrap();
+        assert!(path.contains(&center.moved(0, 2)));
+        assert_eq!(world.find_path(center, center), Some(Vec::new()));
+        assert_eq!(world.find_path(center, center.moved(2, 0)), None);
+    }
+
+    #[test]
+    fn test_path_prefers_road() {
+        let mut world = World::new(40, 40);
+        let from = Position::new(5, 5);
+        let to = Position::new(15, 5);
+        // Estrada paralela, dois tiles ao sul da linha reta
+        for x in 5..=15 {
+            world.terrain_layer.insert(Position::new(x, 7), Tile::road());
+        }
+
+        let path = world.find_path(from, to).unwrap();
+        assert_eq!(path.last(), Some(&to));
+        assert!(!path.contains(&Position::new(10, 5)));
+        assert!(path.iter().filter(|p| world.is_road(**p)).count() >= 5);
+    }
+}
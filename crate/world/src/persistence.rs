@@ -0,0 +1,281 @@
+use crate::economy::Stockpile;
+use crate::history::HistoryRecorder;
+use crate::legends::HistoryIndex;
+use crate::migrations;
+use crate::{Caravan, ModRef, Raws, Scheduler, Site, World, WorldRng};
+use common::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Versão do formato do arquivo de mundo salvo; saves antigos passam
+/// pela cadeia de `migrations` ao serem lidos
+pub const SAVE_FORMAT_VERSION: u32 = 6;
+
+/// Arquivo de mundo salvo: o cabeçalho vem primeiro, seguido do estado
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorldSave {
+    pub header: SaveHeader,
+    pub world: WorldState,
+}
+
+/// Estado completo do mundo, no formato gravado em disco.
+///
+/// Coleções vêm ordenadas por posição ou ID, para que salvar o mesmo
+/// mundo duas vezes gere o mesmo arquivo.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorldState {
+    pub rng: WorldRng,
+    pub counters: IdCounters,
+    pub min_significance: u32,
+    pub terrain: Vec<(Position, Tile)>,
+    pub constructions: Vec<(Position, Construction)>,
+    pub items: Vec<(Position, Vec<Item>)>,
+    pub surfaces: Vec<(Position, String)>,
+    /// Tiles à espera da próxima passada das regras de interação
+    pub changed_tiles: Vec<Position>,
+    pub entities: Vec<Entity>,
+    pub ai_behaviors: Vec<(u32, AIBehavior)>,
+    pub factions: Vec<Faction>,
+    pub sites: Vec<Site>,
+    pub caravans: Vec<Caravan>,
+    pub packs: Vec<(u32, Stockpile)>,
+    pub figures: Vec<HistoricalFigure>,
+    pub events: Vec<HistoricalEvent>,
+}
+
+/// Cabeçalho do save: identifica o formato e o mundo sem exigir a leitura
+/// das camadas
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveHeader {
+    pub format_version: u32,
+    pub seed: u64,
+    pub width: i32,
+    pub height: i32,
+    pub current_tick: u64,
+    /// Mods ativos quando o mundo foi salvo; só carrega com os mesmos
+    pub mods: Vec<ModRef>,
+}
+
+/// Próximos IDs a serem distribuídos
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IdCounters {
+    pub entity: u32,
+    pub faction: u32,
+    pub site: u32,
+    pub item: u32,
+    pub caravan: u32,
+    pub event: u64,
+    #[serde(default)]
+    pub input: u64,
+}
+
+fn by_position<T: Clone>(layer: &HashMap<Position, T>) -> Vec<(Position, T)> {
+    let mut tiles: Vec<(Position, T)> = layer.iter().map(|(pos, v)| (*pos, v.clone())).collect();
+    tiles.sort_by_key(|(pos, _)| (pos.y, pos.x));
+    tiles
+}
+
+fn by_id<T: Clone>(map: &HashMap<u32, T>) -> Vec<(u32, T)> {
+    let mut entries: Vec<(u32, T)> = map.iter().map(|(id, v)| (*id, v.clone())).collect();
+    entries.sort_by_key(|(id, _)| *id);
+    entries
+}
+
+impl WorldSave {
+    /// Serializa em JSON compacto. Passa por `serde_json::Value`, cujos
+    /// mapas são ordenados, para que mapas internos (relações entre
+    /// fações, volume de comércio) também saiam em ordem estável.
+    pub fn to_json(&self) -> Result<String, String> {
+        let value = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        serde_json::to_string(&value).map_err(|e| e.to_string())
+    }
+
+    /// Lê um save de qualquer versão suportada, migrando-o para a atual
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut raw: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        migrations::migrate(&mut raw)?;
+        serde_json::from_value(raw).map_err(|e| e.to_string())
+    }
+}
+
+impl World {
+    /// Captura todo o estado do mundo: camadas, IA, fações, locais,
+    /// história, estado do gerador aleatório e contadores de ID
+    pub fn to_save(&self) -> WorldSave {
+        let mut entities: Vec<Entity> = self.entity_layer.values().cloned().collect();
+        entities.sort_by_key(|e| e.id);
+        let mut figures: Vec<HistoricalFigure> = self.historical_figures.values().cloned().collect();
+        figures.sort_by_key(|f| f.id);
+        let mut changed_tiles: Vec<Position> = self.changed_tiles.iter().copied().collect();
+        changed_tiles.sort_by_key(|pos| (pos.y, pos.x));
+
+        WorldSave {
+            header: SaveHeader {
+                format_version: SAVE_FORMAT_VERSION,
+                seed: self.seed,
+                width: self.width,
+                height: self.height,
+                current_tick: self.current_tick,
+                mods: self.raws.mods.clone(),
+            },
+            world: WorldState {
+                rng: self.rng.clone(),
+                counters: IdCounters {
+                    entity: self.next_entity_id,
+                    faction: self.next_faction_id,
+                    site: self.next_site_id,
+                    item: self.next_item_id,
+                    caravan: self.next_caravan_id,
+                    event: self.next_event_id,
+                    input: self.next_input_id,
+                },
+                min_significance: self.history.min_significance,
+                terrain: by_position(&self.terrain_layer),
+                constructions: by_position(&self.construction_layer),
+                items: by_position(&self.item_layer),
+                surfaces: by_position(&self.surface_layer),
+                changed_tiles,
+                entities,
+                ai_behaviors: by_id(&self.ai_behaviors),
+                factions: self.get_factions().into_iter().cloned().collect(),
+                sites: self.get_sites().into_iter().cloned().collect(),
+                caravans: self.get_caravans().into_iter().cloned().collect(),
+                packs: by_id(&self.packs),
+                figures,
+                events: self.historical_events.clone(),
+            },
+        }
+    }
+
+    /// Reconstrói o mundo a partir do estado salvo; a simulação continua
+    /// exatamente de onde parou
+    pub fn from_save(save: WorldSave) -> Result<World, String> {
+        let (header, save) = (save.header, save.world);
+        if header.width <= 0 || header.height <= 0 {
+            return Err(format!("Dimensões inválidas: {}x{}", header.width, header.height));
+        }
+        if save.events.windows(2).any(|w| w[0].id >= w[1].id) {
+            return Err("Eventos históricos fora de ordem".to_string());
+        }
+        let raws = Raws::active();
+        if header.mods != raws.mods {
+            let describe = |mods: &[ModRef]| match mods {
+                [] => "nenhum".to_string(),
+                _ => mods.iter().map(ModRef::to_string).collect::<Vec<_>>().join(", "),
+            };
+            return Err(format!(
+                "O mundo foi salvo com os mods [{}], mas os ativos são [{}]",
+                describe(&header.mods),
+                describe(&raws.mods)
+            ));
+        }
+
+        Ok(World {
+            terrain_layer: save.terrain.into_iter().collect(),
+            construction_layer: save.constructions.into_iter().collect(),
+            item_layer: save.items.into_iter().collect(),
+            surface_layer: save.surfaces.into_iter().collect(),
+            changed_tiles: save.changed_tiles.into_iter().collect(),
+            entity_layer: save.entities.into_iter().map(|e| (e.id, e)).collect(),
+            ai_behaviors: save.ai_behaviors.into_iter().collect(),
+            factions: save.factions.into_iter().map(|f| (f.id, f)).collect(),
+            sites: save.sites.into_iter().map(|s| (s.id, s)).collect(),
+            history_index: HistoryIndex::rebuild(&save.events),
+            historical_events: save.events,
+            caravans: save.caravans.into_iter().map(|c| (c.id, c)).collect(),
+            packs: save.packs.into_iter().collect(),
+            history: HistoryRecorder::new(save.min_significance),
+            historical_figures: save.figures.into_iter().map(|f| (f.id, f)).collect(),
+            seed: header.seed,
+            rng: save.rng,
+            width: header.width,
+            height: header.height,
+            next_entity_id: save.counters.entity,
+            next_faction_id: save.counters.faction,
+            next_site_id: save.counters.site,
+            next_item_id: save.counters.item,
+            next_caravan_id: save.counters.caravan,
+            next_event_id: save.counters.event,
+            next_input_id: save.counters.input.max(1),
+            current_tick: header.current_tick,
+            journal: None,
+            events: Default::default(),
+            scheduler: Scheduler::with_builtin_systems(),
+            raws,
+        })
+    }
+
+    /// Grava o mundo em disco. Escreve num arquivo temporário e o renomeia,
+    /// para que uma falha no meio não corrompa o save anterior.
+    pub fn save_to_file(&self, path: &Path) -> Result<(), String> {
+        let json = self.to_save().to_json()?;
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Erro ao criar {}: {}", dir.display(), e))?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json).map_err(|e| format!("Erro ao escrever {}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, path).map_err(|e| format!("Erro ao gravar {}: {}", path.display(), e))
+    }
+
+    /// Carrega um mundo salvo com `save_to_file`
+    pub fn load_from_file(path: &Path) -> Result<World, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("Erro ao ler {}: {}", path.display(), e))?;
+        World::from_save(WorldSave::from_json(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HistoryGenConfig;
+
+    fn snapshot(world: &World) -> String {
+        world.to_save().to_json().unwrap()
+    }
+
+    #[test]
+    fn test_loaded_world_continues_identically() {
+        let mut world = World::with_seed(60, 60, 11);
+        world.generate_history(&HistoryGenConfig {
+            years: 30,
+            ..Default::default()
+        });
+        let mut loaded = World::from_save(WorldSave::from_json(&snapshot(&world)).unwrap()).unwrap();
+        assert_eq!(snapshot(&loaded), snapshot(&world));
+
+        for _ in 0..1000 {
+            world.tick();
+            loaded.tick();
+        }
+        assert_eq!(snapshot(&loaded), snapshot(&world));
+        assert_eq!(
+            loaded.get_historical_events(usize::MAX).len(),
+            world.get_historical_events(usize::MAX).len()
+        );
+    }
+
+    #[test]
+    fn test_save_file_roundtrip() {
+        let path = std::env::temp_dir().join(format!("mm-world-{}.json", std::process::id()));
+        let mut world = World::new(30, 30);
+        world.place_item(Position::new(3, 3), "a crude spear".to_string(), ItemKind::Weapon);
+
+        world.save_to_file(&path).unwrap();
+        let loaded = World::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            loaded.get_items(Position::new(3, 3)),
+            world.get_items(Position::new(3, 3))
+        );
+        assert_eq!(snapshot(&loaded), snapshot(&world));
+    }
+
+    #[test]
+    fn test_rejects_newer_format() {
+        let mut save = World::new(10, 10).to_save();
+        save.header.format_version = SAVE_FORMAT_VERSION + 1;
+        assert!(WorldSave::from_json(&save.to_json().unwrap()).is_err());
+    }
+}
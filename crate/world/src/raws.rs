@@ -0,0 +1,660 @@
+use crate::{BuildingKind, ModRef, RuleDef, RuleEffect, SiteKind, SurfaceDef, World};
+use common::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Pasta dos raws, relativa à raiz do projeto
+pub const RAWS_DIR: &str = "raws";
+/// Criatura dos personagens dos jogadores
+pub const PLAYER_CREATURE: &str = "adventurer";
+/// Criatura dos NPCs iniciais
+pub const VILLAGER_CREATURE: &str = "villager";
+/// Criatura que carrega a carga das caravanas
+pub const PACK_CREATURE: &str = "mule";
+
+/// Raws embutidos no binário, os mesmos de `RAWS_DIR`
+const BUILTIN: [(&str, &str); 7] = [
+    ("materials.json", include_str!("../../../raws/materials.json")),
+    ("terrains.json", include_str!("../../../raws/terrains.json")),
+    ("creatures.json", include_str!("../../../raws/creatures.json")),
+    ("items.json", include_str!("../../../raws/items.json")),
+    ("factions.json", include_str!("../../../raws/factions.json")),
+    ("surfaces.json", include_str!("../../../raws/surfaces.json")),
+    ("rules.json", include_str!("../../../raws/rules.json")),
+];
+
+/// Terrenos que o motor usa diretamente, na geração do mundo e nas
+/// estradas
+const REQUIRED_TERRAINS: [&str; 4] = [
+    TerrainType::GRASS,
+    TerrainType::WATER,
+    TerrainType::STONE,
+    TerrainType::ROAD,
+];
+const ENTITY_TYPES: [EntityType; 3] = [EntityType::Player, EntityType::NPC, EntityType::Animal];
+const ITEM_CLASSES: [ItemClass; 5] = [
+    ItemClass::Food,
+    ItemClass::Tool,
+    ItemClass::Weapon,
+    ItemClass::Treasure,
+    ItemClass::Corpse,
+];
+
+/// Como algo aparece no cliente
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Glyph {
+    pub ch: char,
+    pub fg: String,
+    /// Fundo; só terrenos pintam o fundo
+    #[serde(default)]
+    pub bg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MaterialDef {
+    pub id: String,
+    pub name: String,
+    pub hardness: u32,
+    #[serde(default)]
+    pub flammable: bool,
+}
+
+/// Definição de um terreno; os tiles guardam o `id`. O motor usa
+/// diretamente só os de `REQUIRED_TERRAINS`, e os demais chegam ao mundo
+/// pelas regras de interação.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TerrainDef {
+    pub id: String,
+    pub name: String,
+    pub walkable: bool,
+    pub material: Option<String>,
+    /// Onde algo aconteceu, na história ("in the grasslands")
+    pub phrase: String,
+    /// Rendimento de um tile por ciclo da economia, em décimos de unidade
+    #[serde(default)]
+    pub yields: BTreeMap<Resource, u32>,
+    pub glyph: Glyph,
+}
+
+/// Comportamento de uma criatura; o primeiro da lista é o objetivo
+/// inicial da sua IA
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Behavior {
+    Wander,
+    Hunt,
+    Flee,
+    Guard,
+    Sleep,
+}
+
+impl Behavior {
+    /// Objetivo de IA de uma criatura que começa em `pos`
+    pub fn goal_at(self, pos: Position) -> AIGoal {
+        match self {
+            Behavior::Wander => AIGoal::Wander,
+            Behavior::Hunt => AIGoal::Hunt,
+            Behavior::Flee => AIGoal::Flee,
+            Behavior::Guard => AIGoal::Guard { pos },
+            Behavior::Sleep => AIGoal::Sleep,
+        }
+    }
+}
+
+/// Definição de uma criatura. Cada uma é de um `EntityType`, e a primeira
+/// de cada tipo vale para entidades sem criatura.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CreatureDef {
+    pub id: String,
+    pub name: String,
+    pub kind: EntityType,
+    /// Partes do corpo
+    pub body_plan: Vec<String>,
+    /// Tamanho relativo (um humano tem 70)
+    pub size: u32,
+    #[serde(default)]
+    pub behaviors: Vec<Behavior>,
+    pub material: Option<String>,
+    /// Superfície deixada onde a criatura morre
+    #[serde(default)]
+    pub blood: Option<String>,
+    pub glyph: Glyph,
+}
+
+/// Categoria de item, como `ItemKind` sem os dados de cada item
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemClass {
+    Food,
+    Tool,
+    Weapon,
+    Treasure,
+    Corpse,
+}
+
+impl ItemClass {
+    pub fn of(kind: ItemKind) -> Self {
+        match kind {
+            ItemKind::Food => ItemClass::Food,
+            ItemKind::Tool => ItemClass::Tool,
+            ItemKind::Weapon => ItemClass::Weapon,
+            ItemKind::Treasure => ItemClass::Treasure,
+            ItemKind::Corpse { .. } => ItemClass::Corpse,
+        }
+    }
+
+    /// Tipo dos itens criados a partir da definição; cadáveres só surgem
+    /// de mortes
+    pub fn item_kind(self) -> Option<ItemKind> {
+        match self {
+            ItemClass::Food => Some(ItemKind::Food),
+            ItemClass::Tool => Some(ItemKind::Tool),
+            ItemClass::Weapon => Some(ItemKind::Weapon),
+            ItemClass::Treasure => Some(ItemKind::Treasure),
+            ItemClass::Corpse => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ItemDef {
+    pub id: String,
+    /// Nome dos itens no mundo ("a crude spear")
+    pub name: String,
+    pub class: ItemClass,
+    pub material: Option<String>,
+    /// Construções onde o item é guardado ao fundar um local
+    #[serde(default)]
+    pub found_in: Vec<BuildingKind>,
+    pub glyph: Glyph,
+}
+
+/// Definição de um povo, cujo `id` as fações guardam: como nomeia
+/// pessoas, locais e a si mesmo, e como trata os vizinhos
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FactionDef {
+    pub id: String,
+    pub name: String,
+    /// Funda civilizações na geração de história
+    #[serde(default)]
+    pub civilization: bool,
+    /// Nasce hostil a todos os outros povos, azeda mais depressa com os
+    /// neutros e nunca faz as pazes
+    #[serde(default)]
+    pub hostile: bool,
+    /// Queima os locais que conquista, em vez de saqueá-los
+    #[serde(default)]
+    pub burns: bool,
+    pub site: SiteKind,
+    /// Criatura dos membros (líderes de caravana, por exemplo)
+    pub creature: String,
+    /// Sílabas de nomes de pessoas: primeira e segunda
+    pub person_names: [Vec<String>; 2],
+    /// Prefixos e sufixos de nomes de locais
+    pub site_names: [Vec<String>; 2],
+    /// Adjetivos e substantivos de nomes de fações
+    pub faction_names: [Vec<String>; 2],
+}
+
+/// Conteúdo de um arquivo de raws; cada arquivo traz as categorias que
+/// quiser
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RawsFile {
+    #[serde(default)]
+    pub materials: Vec<MaterialDef>,
+    #[serde(default)]
+    pub terrains: Vec<TerrainDef>,
+    #[serde(default)]
+    pub creatures: Vec<CreatureDef>,
+    #[serde(default)]
+    pub items: Vec<ItemDef>,
+    #[serde(default)]
+    pub factions: Vec<FactionDef>,
+    #[serde(default)]
+    pub surfaces: Vec<SurfaceDef>,
+    #[serde(default)]
+    pub rules: Vec<RuleDef>,
+}
+
+impl RawsFile {
+    pub fn parse(name: &str, json: &str) -> Result<RawsFile, String> {
+        serde_json::from_str(json).map_err(|e| format!("{}: {}", name, e))
+    }
+
+    /// Categoria e ID de cada definição do arquivo
+    pub fn ids(&self) -> Vec<(&'static str, &str)> {
+        let materials = self.materials.iter().map(|d| ("material", d.id.as_str()));
+        let terrains = self.terrains.iter().map(|d| ("terreno", d.id.as_str()));
+        let creatures = self.creatures.iter().map(|d| ("criatura", d.id.as_str()));
+        let items = self.items.iter().map(|d| ("item", d.id.as_str()));
+        let factions = self.factions.iter().map(|d| ("fação", d.id.as_str()));
+        let surfaces = self.surfaces.iter().map(|d| ("superfície", d.id.as_str()));
+        let rules = self.rules.iter().map(|d| ("regra", d.id.as_str()));
+        materials
+            .chain(terrains)
+            .chain(creatures)
+            .chain(items)
+            .chain(factions)
+            .chain(surfaces)
+            .chain(rules)
+            .collect()
+    }
+}
+
+/// Definição identificada por um ID único na sua categoria
+trait Definition {
+    fn id(&self) -> &str;
+}
+
+macro_rules! impl_definition {
+    ($($def:ty),*) => {
+        $(impl Definition for $def {
+            fn id(&self) -> &str {
+                &self.id
+            }
+        })*
+    };
+}
+impl_definition!(MaterialDef, TerrainDef, CreatureDef, ItemDef, FactionDef, SurfaceDef, RuleDef);
+
+/// Acrescenta definições a uma lista; uma de mesmo ID substitui a antiga
+/// no lugar dela
+fn upsert<T: Definition>(list: &mut Vec<T>, defs: Vec<T>) {
+    for def in defs {
+        match list.iter_mut().find(|d| d.id() == def.id()) {
+            Some(old) => *old = def,
+            None => list.push(def),
+        }
+    }
+}
+
+/// Definições de conteúdo do jogo ("raws"), na ordem em que foram
+/// carregadas
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Raws {
+    pub materials: Vec<MaterialDef>,
+    pub terrains: Vec<TerrainDef>,
+    pub creatures: Vec<CreatureDef>,
+    pub items: Vec<ItemDef>,
+    pub factions: Vec<FactionDef>,
+    pub surfaces: Vec<SurfaceDef>,
+    /// Interações entre camadas, avaliadas nesta ordem
+    pub rules: Vec<RuleDef>,
+    /// Mods aplicados sobre os raws do jogo, em ordem de carga
+    #[serde(default)]
+    pub mods: Vec<ModRef>,
+}
+
+/// Raws em uso pelos mundos criados ou carregados daqui em diante
+static ACTIVE: RwLock<Option<Arc<Raws>>> = RwLock::new(None);
+
+impl Raws {
+    /// Os raws que acompanham o jogo
+    pub fn builtin() -> Arc<Raws> {
+        static BUILTIN_RAWS: OnceLock<Arc<Raws>> = OnceLock::new();
+        BUILTIN_RAWS
+            .get_or_init(|| {
+                let files = BUILTIN.map(|(name, json)| (name.to_string(), json.to_string()));
+                Arc::new(Raws::from_files(&files).unwrap_or_else(|e| panic!("Raws embutidos inválidos: {}", e)))
+            })
+            .clone()
+    }
+
+    /// Carrega e valida os arquivos `.json` de uma pasta, em ordem
+    /// alfabética
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Raws, String> {
+        Raws::from_files(&read_json_dir(dir.as_ref(), &[])?)
+    }
+
+    /// Junta e valida arquivos de raws `(nome, json)`
+    pub fn from_files(files: &[(String, String)]) -> Result<Raws, String> {
+        let (raws, problems) = Raws::merge_files(files)?;
+        raws.validated(problems)
+    }
+
+    /// Junta arquivos de raws sem validar o resultado; um ID repetido
+    /// entre os arquivos é um problema
+    pub(crate) fn merge_files(files: &[(String, String)]) -> Result<(Raws, Vec<String>), String> {
+        let mut raws = Raws::default();
+        let mut defined: HashMap<(&str, String), &str> = HashMap::new();
+        let mut problems = Vec::new();
+        for (name, json) in files {
+            let file = RawsFile::parse(name, json)?;
+            for (category, id) in file.ids() {
+                if let Some(first) = defined.insert((category, id.to_string()), name) {
+                    problems.push(format!("{} `{}` definido em {} e de novo em {}", category, id, first, name));
+                }
+            }
+            raws.merge(file);
+        }
+        Ok((raws, problems))
+    }
+
+    /// Aplica um arquivo de raws: IDs novos entram no fim, IDs existentes
+    /// substituem a definição anterior
+    pub(crate) fn merge(&mut self, file: RawsFile) {
+        upsert(&mut self.materials, file.materials);
+        upsert(&mut self.terrains, file.terrains);
+        upsert(&mut self.creatures, file.creatures);
+        upsert(&mut self.items, file.items);
+        upsert(&mut self.factions, file.factions);
+        upsert(&mut self.surfaces, file.surfaces);
+        upsert(&mut self.rules, file.rules);
+    }
+
+    /// Os raws, se eles e os problemas já encontrados permitirem usá-los
+    pub(crate) fn validated(self, mut problems: Vec<String>) -> Result<Raws, String> {
+        problems.extend(self.problems());
+        if problems.is_empty() {
+            Ok(self)
+        } else {
+            Err(format!("Raws inválidos:\n  - {}", problems.join("\n  - ")))
+        }
+    }
+
+    /// Tudo o que impede o jogo de usar estes raws
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let material = |owner: String, id: &Option<String>, problems: &mut Vec<String>| {
+            if let Some(id) = id {
+                if self.material(id).is_none() {
+                    problems.push(format!("{}: material `{}` não existe", owner, id));
+                }
+            }
+        };
+
+        for id in REQUIRED_TERRAINS {
+            if self.terrain(id).is_none() {
+                problems.push(format!("o terreno `{}` é obrigatório", id));
+            }
+        }
+        for def in &self.terrains {
+            material(format!("terreno `{}`", def.id), &def.material, &mut problems);
+        }
+
+        for kind in ENTITY_TYPES {
+            if !self.creatures.iter().any(|c| c.kind == kind) {
+                problems.push(format!("nenhuma criatura do tipo {:?}", kind));
+            }
+        }
+        for (id, kind) in [
+            (PLAYER_CREATURE, EntityType::Player),
+            (VILLAGER_CREATURE, EntityType::NPC),
+            (PACK_CREATURE, EntityType::Animal),
+        ] {
+            if self.creature(id).is_none_or(|c| c.kind != kind) {
+                problems.push(format!("a criatura `{}` ({:?}) é obrigatória", id, kind));
+            }
+        }
+        for def in &self.creatures {
+            if def.body_plan.is_empty() {
+                problems.push(format!("criatura `{}`: plano de corpo vazio", def.id));
+            }
+            if def.size == 0 {
+                problems.push(format!("criatura `{}`: tamanho precisa ser maior que zero", def.id));
+            }
+            material(format!("criatura `{}`", def.id), &def.material, &mut problems);
+            if let Some(blood) = def.blood.as_ref().filter(|id| self.surface(id).is_none()) {
+                problems.push(format!("criatura `{}`: superfície `{}` não existe", def.id, blood));
+            }
+        }
+
+        for class in ITEM_CLASSES {
+            if !self.items.iter().any(|i| i.class == class) {
+                problems.push(format!("nenhum item da classe {:?}", class));
+            }
+        }
+        for def in &self.items {
+            if def.class == ItemClass::Corpse && !def.found_in.is_empty() {
+                problems.push(format!("item `{}`: cadáveres não são guardados em construções", def.id));
+            }
+            material(format!("item `{}`", def.id), &def.material, &mut problems);
+        }
+
+        if !self.factions.iter().any(|f| f.civilization) {
+            problems.push("nenhuma fação funda civilizações".to_string());
+        }
+        for def in &self.factions {
+            if self.creature(&def.creature).is_none() {
+                problems.push(format!("fação `{}`: criatura `{}` não existe", def.id, def.creature));
+            }
+            let lists = def.person_names.iter().chain(&def.site_names).chain(&def.faction_names);
+            if lists.clone().any(Vec::is_empty) {
+                problems.push(format!("fação `{}`: listas de nomes não podem ser vazias", def.id));
+            }
+        }
+
+        for def in &self.rules {
+            let near = def.near.iter().flat_map(|n| &n.surface);
+            let set = def.effects.iter().filter_map(|e| match e {
+                RuleEffect::SetSurface(id) => Some(id),
+                _ => None,
+            });
+            for surface in def.at.surface.iter().chain(near).chain(set) {
+                if self.surface(surface).is_none() {
+                    problems.push(format!("regra `{}`: superfície `{}` não existe", def.id, surface));
+                }
+            }
+            let near = def.near.iter().flat_map(|n| &n.terrain);
+            let set = def.effects.iter().filter_map(|e| match e {
+                RuleEffect::SetTerrain(terrain) => Some(terrain),
+                _ => None,
+            });
+            for terrain in def.at.terrain.iter().chain(near).chain(set) {
+                if self.terrain(terrain.id()).is_none() {
+                    problems.push(format!("regra `{}`: terreno `{}` não existe", def.id, terrain.id()));
+                }
+            }
+            if !(def.chance > 0.0 && def.chance <= 1.0) {
+                problems.push(format!("regra `{}`: chance precisa estar entre 0 e 1", def.id));
+            }
+            if !(1..=8).contains(&def.near_min) {
+                problems.push(format!("regra `{}`: near_min precisa estar entre 1 e 8", def.id));
+            }
+            if def.effects.is_empty() {
+                problems.push(format!("regra `{}`: nenhum efeito", def.id));
+            }
+        }
+        problems
+    }
+
+    /// Passa a usar estes raws nos mundos criados ou carregados a seguir
+    pub fn install(raws: Raws) {
+        *ACTIVE.write().unwrap() = Some(Arc::new(raws));
+    }
+
+    /// Raws em uso: os instalados, ou os embutidos
+    pub fn active() -> Arc<Raws> {
+        ACTIVE.read().unwrap().clone().unwrap_or_else(Raws::builtin)
+    }
+
+    pub fn material(&self, id: &str) -> Option<&MaterialDef> {
+        self.materials.iter().find(|m| m.id == id)
+    }
+
+    pub fn terrain(&self, id: &str) -> Option<&TerrainDef> {
+        self.terrains.iter().find(|t| t.id == id)
+    }
+
+    pub fn creature(&self, id: &str) -> Option<&CreatureDef> {
+        self.creatures.iter().find(|c| c.id == id)
+    }
+
+    /// Criatura de uma entidade; sem uma, a primeira do seu tipo
+    pub fn creature_of(&self, entity: &Entity) -> &CreatureDef {
+        entity
+            .creature
+            .as_deref()
+            .and_then(|id| self.creature(id))
+            .or_else(|| self.creatures.iter().find(|c| c.kind == entity.entity_type))
+            .expect("os raws validados têm criaturas de todos os tipos")
+    }
+
+    /// Definição de um item do mundo: a de mesmo nome, ou a primeira da
+    /// sua classe
+    pub fn item_of(&self, item: &Item) -> &ItemDef {
+        let class = ItemClass::of(item.kind);
+        self.items
+            .iter()
+            .find(|i| i.class == class && i.name == item.name)
+            .or_else(|| self.items.iter().find(|i| i.class == class))
+            .expect("os raws validados têm itens de todas as classes")
+    }
+
+    /// Itens guardados numa construção ao fundar um local
+    pub fn goods_in(&self, building: BuildingKind) -> impl Iterator<Item = &ItemDef> {
+        self.items.iter().filter(move |i| i.found_in.contains(&building))
+    }
+
+    pub fn faction(&self, id: &str) -> Option<&FactionDef> {
+        self.factions.iter().find(|f| f.id == id)
+    }
+
+    pub fn surface(&self, id: &str) -> Option<&SurfaceDef> {
+        self.surfaces.iter().find(|s| s.id == id)
+    }
+
+    /// Povos que fundam civilizações, na ordem dos raws
+    pub fn civilizations(&self) -> Vec<FactionType> {
+        self.factions
+            .iter()
+            .filter(|f| f.civilization)
+            .map(|f| FactionType::new(&f.id))
+            .collect()
+    }
+}
+
+impl World {
+    /// Definições de conteúdo em uso por este mundo
+    pub fn raws(&self) -> &Raws {
+        &self.raws
+    }
+
+    /// Tile de um terreno, como definido nos raws; um terreno que os raws
+    /// não definem não é transitável
+    pub fn tile(&self, terrain: &str) -> Tile {
+        Tile::new(terrain, self.raws.terrain(terrain).is_some_and(|t| t.walkable))
+    }
+}
+
+/// Arquivos `.json` de uma pasta, em ordem alfabética, menos os de
+/// `skip`
+pub(crate) fn read_json_dir(dir: &Path, skip: &[&str]) -> Result<Vec<(String, String)>, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Não foi possível ler os raws em {}: {}", dir.display(), e))?;
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter(|path| !path.file_name().is_some_and(|name| skip.iter().any(|s| name == *s)))
+        .collect();
+    paths.sort();
+
+    let mut files = Vec::new();
+    for path in paths {
+        let json =
+            std::fs::read_to_string(&path).map_err(|e| format!("Não foi possível ler {}: {}", path.display(), e))?;
+        files.push((path.display().to_string(), json));
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_raws_match_the_raws_dir() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..").join(RAWS_DIR);
+        let loaded = Raws::load_dir(dir).unwrap();
+        assert_eq!(&loaded, Raws::builtin().as_ref());
+        assert_eq!(loaded.terrain(TerrainType::WATER).unwrap().glyph.ch, '≈');
+        assert_eq!(loaded.civilizations().len(), 4);
+    }
+
+    #[test]
+    fn test_invalid_raws_list_every_problem() {
+        let mut files: Vec<(String, String)> = BUILTIN
+            .iter()
+            .map(|(name, json)| (name.to_string(), json.to_string()))
+            .collect();
+        files.push((
+            "extra.json".to_string(),
+            r##"{
+                "materials": [{ "id": "soil", "name": "mud", "hardness": 1 }],
+                "creatures": [{
+                    "id": "troll", "name": "cave troll", "kind": "NPC", "body_plan": [], "size": 250,
+                    "material": "stone", "glyph": { "ch": "T", "fg": "#7a7" }
+                }]
+            }"##
+            .to_string(),
+        ));
+        let error = Raws::from_files(&files).unwrap_err();
+        assert!(error.contains("material `soil` definido em materials.json e de novo em extra.json"));
+        assert!(error.contains("criatura `troll`: plano de corpo vazio"));
+        assert!(error.contains("criatura `troll`: material `stone` não existe"));
+
+        files.pop();
+        files.push((
+            "typo.json".to_string(),
+            r#"{ "creatures": [{ "id": "x", "kind": "Dragon" }] }"#.to_string(),
+        ));
+        let error = Raws::from_files(&files).unwrap_err();
+        assert!(error.starts_with("typo.json: unknown variant `Dragon`"), "{}", error);
+    }
+
+    #[test]
+    fn test_raws_add_terrains_and_peoples() {
+        let mut files: Vec<(String, String)> = BUILTIN
+            .iter()
+            .map(|(name, json)| (name.to_string(), json.to_string()))
+            .collect();
+        files.push((
+            "volcano.json".to_string(),
+            r##"{
+                "terrains": [{
+                    "id": "lava", "name": "lava", "walkable": false, "material": "granite",
+                    "phrase": "by the lava", "glyph": { "ch": "~", "fg": "#f60", "bg": "#300" }
+                }],
+                "factions": [{
+                    "id": "kobold", "name": "kobolds", "civilization": true, "hostile": true,
+                    "site": "Camp", "creature": "goblin",
+                    "person_names": [["Kip"], ["zik"]],
+                    "site_names": [["Ember"], ["hole"]],
+                    "faction_names": [["Scorched"], ["Tribe"]]
+                }],
+                "rules": [{
+                    "id": "lava_lights_grass",
+                    "at": { "terrain": ["grass"], "no_surface": true },
+                    "near": { "terrain": ["lava"] },
+                    "effects": [{ "set_surface": "fire" }]
+                }]
+            }"##
+            .to_string(),
+        ));
+        let raws = Raws::from_files(&files).unwrap();
+        assert_eq!(raws.terrain("lava").unwrap().glyph.ch, '~');
+        assert!(raws.civilizations().contains(&FactionType::new("kobold")));
+
+        let mut world = World::new(20, 20);
+        world.raws = Arc::new(raws);
+        assert!(!world.tile("lava").walkable);
+        assert!(!world.tile("tar").walkable);
+
+        // Só os terrenos que o motor usa são obrigatórios, e as regras só
+        // falam de terrenos que existem
+        let last = files.len() - 1;
+        files[last].1 = files[last].1.replace(r#"["lava"]"#, r#"["magma"]"#);
+        let terrains = files.iter_mut().find(|(name, _)| name == "terrains.json").unwrap();
+        terrains.1 = terrains.1.replace(r#""id": "road""#, r#""id": "paved""#);
+        let error = Raws::from_files(&files).unwrap_err();
+        assert!(error.contains("o terreno `road` é obrigatório"), "{}", error);
+        assert!(error.contains("regra `lava_lights_grass`: terreno `magma` não existe"), "{}", error);
+    }
+}
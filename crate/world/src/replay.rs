@@ -0,0 +1,325 @@
+use crate::economy::Stockpile;
+use crate::migrations;
+use crate::{HistoryGenConfig, World, WorldSave};
+use common::*;
+use serde::{Deserialize, Serialize};
+
+/// Versão do formato do arquivo de replay
+pub const REPLAY_FORMAT_VERSION: u32 = 1;
+
+/// De onde a reprodução parte
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ReplayStart {
+    /// Mundo gerado a partir da semente, com a fase de história opcional
+    Seed {
+        seed: u64,
+        width: i32,
+        height: i32,
+        history: Option<HistoryGenConfig>,
+    },
+    /// Mundo salvo (migrado para o formato atual ao ser lido)
+    Snapshot(Box<WorldSave>),
+}
+
+/// Ação de um jogador, aplicada no tick em que chegou
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayInput {
+    pub tick: u64,
+    /// Entidade do jogador; num `Login`, a que deve ser criada
+    pub player: u32,
+    pub message: ClientMessage,
+}
+
+/// Hash do estado esperado ao fim de um tick
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Checkpoint {
+    pub tick: u64,
+    pub state_hash: u64,
+}
+
+/// Sessão gravada: o mundo inicial e as ações dos jogadores, tick a tick.
+/// Como a simulação é determinística, reaplicá-las leva ao mesmo estado;
+/// os checkpoints apontam o primeiro tick em que isso deixa de valer.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Replay {
+    pub format_version: u32,
+    pub start: ReplayStart,
+    pub inputs: Vec<ReplayInput>,
+    pub checkpoints: Vec<Checkpoint>,
+    /// Tick em que a reprodução termina
+    pub end_tick: u64,
+}
+
+/// O que a ação de um jogador causou no mundo
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputOutcome {
+    /// Ação que só lê o mundo
+    Ignored,
+    Moved { moved: bool },
+    Escorting { caravan: u32 },
+    Robbed { caravan: u32, loot: Stockpile },
+    /// Troca feita; `paid` é o que o jogador deu em troca
+    Traded { caravan: u32, paid: u32 },
+}
+
+impl Replay {
+    /// Replay de um mundo novo, gerado a partir da semente
+    pub fn from_seed(seed: u64, width: i32, height: i32, history: Option<HistoryGenConfig>) -> Self {
+        Self::starting_at(
+            ReplayStart::Seed {
+                seed,
+                width,
+                height,
+                history,
+            },
+            0,
+        )
+    }
+
+    /// Replay que parte do estado atual do mundo
+    pub fn from_world(world: &World) -> Self {
+        Self::starting_at(ReplayStart::Snapshot(Box::new(world.to_save())), world.current_tick)
+    }
+
+    fn starting_at(start: ReplayStart, tick: u64) -> Self {
+        Self {
+            format_version: REPLAY_FORMAT_VERSION,
+            start,
+            inputs: Vec::new(),
+            checkpoints: Vec::new(),
+            end_tick: tick,
+        }
+    }
+
+    /// Grava a ação de um jogador no tick atual
+    pub fn record(&mut self, tick: u64, player: u32, message: &ClientMessage) {
+        self.inputs.push(ReplayInput {
+            tick,
+            player,
+            message: message.clone(),
+        });
+        self.end_tick = self.end_tick.max(tick);
+    }
+
+    /// Grava o hash do estado do mundo, que a reprodução deve reencontrar
+    pub fn checkpoint(&mut self, world: &World) {
+        self.checkpoints.push(Checkpoint {
+            tick: world.current_tick,
+            state_hash: world.state_hash(),
+        });
+        self.end_tick = self.end_tick.max(world.current_tick);
+    }
+
+    /// Mundo no ponto de partida da gravação
+    pub fn start_world(&self) -> Result<World, String> {
+        match &self.start {
+            ReplayStart::Seed {
+                seed,
+                width,
+                height,
+                history,
+            } => {
+                if *width <= 0 || *height <= 0 {
+                    return Err(format!("Dimensões inválidas: {}x{}", width, height));
+                }
+                let mut world = World::with_seed(*width, *height, *seed);
+                if let Some(config) = history {
+                    world.generate_history(config);
+                }
+                Ok(world)
+            }
+            ReplayStart::Snapshot(save) => World::from_save(save.as_ref().clone()),
+        }
+    }
+
+    /// Reexecuta a gravação e devolve o mundo final
+    pub fn run(&self) -> Result<World, String> {
+        self.run_observed(|_| {})
+    }
+
+    /// Reexecuta a gravação chamando `observe` ao fim de cada tick, depois
+    /// das ações dos jogadores. Falha no primeiro checkpoint divergente.
+    pub fn run_observed<F>(&self, mut observe: F) -> Result<World, String>
+    where
+        F: FnMut(&World),
+    {
+        let mut world = self.start_world()?;
+        let mut inputs = self.inputs.iter().peekable();
+        let mut checkpoints = self.checkpoints.iter().peekable();
+
+        loop {
+            let tick = world.current_tick;
+            while let Some(input) = inputs.next_if(|i| i.tick <= tick) {
+                if input.tick < tick {
+                    return Err(format!("Ação do tick {} fora de ordem (mundo no tick {})", input.tick, tick));
+                }
+                world.replay_input(input)?;
+            }
+            observe(&world);
+            while let Some(checkpoint) = checkpoints.next_if(|c| c.tick <= tick) {
+                let found = world.state_hash();
+                if checkpoint.tick < tick || checkpoint.state_hash != found {
+                    return Err(format!(
+                        "Dessincronia no tick {}: esperado {:016x}, obtido {:016x}",
+                        checkpoint.tick, checkpoint.state_hash, found
+                    ));
+                }
+            }
+            if tick >= self.end_tick && inputs.peek().is_none() && checkpoints.peek().is_none() {
+                return Ok(world);
+            }
+            world.tick();
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+
+    /// Lê um replay; um snapshot inicial em formato antigo é migrado
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut raw: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let version = raw.get("format_version").and_then(serde_json::Value::as_u64);
+        if version != Some(REPLAY_FORMAT_VERSION as u64) {
+            return Err(format!(
+                "Formato de replay {:?} não é suportado (atual: v{})",
+                version, REPLAY_FORMAT_VERSION
+            ));
+        }
+        if let Some(save) = raw.pointer_mut("/start/Snapshot") {
+            migrations::migrate(save)?;
+        }
+        serde_json::from_value(raw).map_err(|e| e.to_string())
+    }
+}
+
+impl World {
+    /// Aplica a ação de um jogador ao mundo. `Login` não passa por aqui:
+    /// o jogador ainda não tem entidade (veja `spawn_player`).
+    pub fn apply_input(&mut self, player: u32, message: &ClientMessage) -> Result<InputOutcome, String> {
+        let pos = self.entity_layer.get(&player).ok_or("Unknown entity")?.pos;
+        if matches!(message, ClientMessage::Login { .. } | ClientMessage::ReadHistory) {
+            return Ok(InputOutcome::Ignored);
+        }
+        // O diário guarda a ação, não cada mudança que ela faz
+        let simulating = self.journal_input(player, message);
+        let outcome = self.perform_input(player, pos, message);
+        self.end_journal_input(simulating);
+        outcome
+    }
+
+    fn perform_input(&mut self, player: u32, pos: Position, message: &ClientMessage) -> Result<InputOutcome, String> {
+        match *message {
+            ClientMessage::Move { dx, dy } => Ok(InputOutcome::Moved {
+                moved: self.move_entity(player, dx, dy),
+            }),
+            ClientMessage::EscortCaravan => {
+                let caravan = self.caravan_near(pos).ok_or("There is no caravan nearby")?;
+                self.escort_caravan(player, caravan)?;
+                Ok(InputOutcome::Escorting { caravan })
+            }
+            ClientMessage::RobCaravan => {
+                let caravan = self.caravan_near(pos).ok_or("There is no caravan nearby")?;
+                let loot = self.rob_caravan(player, caravan)?;
+                Ok(InputOutcome::Robbed { caravan, loot })
+            }
+            ClientMessage::TradeWithCaravan { give, take, amount } => {
+                let caravan = self.caravan_near(pos).ok_or("There is no caravan nearby")?;
+                let paid = self.trade_with_caravan(player, caravan, give, take, amount)?;
+                Ok(InputOutcome::Traded { caravan, paid })
+            }
+            ClientMessage::Login { .. } | ClientMessage::ReadHistory => Ok(InputOutcome::Ignored),
+        }
+    }
+
+    /// Reaplica uma ação gravada. Ações recusadas na sessão original são
+    /// recusadas de novo e não mudam nada; só um login que não recria a
+    /// mesma entidade indica que o mundo divergiu.
+    fn replay_input(&mut self, input: &ReplayInput) -> Result<(), String> {
+        if let ClientMessage::Login { player_name } = &input.message {
+            let spawned = self.spawn_player(player_name.clone());
+            if spawned != Some(input.player) {
+                return Err(format!(
+                    "Dessincronia no tick {}: login de {} criou {:?}, esperado {}",
+                    input.tick, player_name, spawned, input.player
+                ));
+            }
+            return Ok(());
+        }
+        let _ = self.apply_input(input.player, &input.message);
+        Ok(())
+    }
+
+    /// Hash (FNV-1a de 64 bits) do estado salvo do mundo; dois mundos com o
+    /// mesmo hash gravariam o mesmo arquivo
+    pub fn state_hash(&self) -> u64 {
+        let json = self.to_save().to_json().unwrap_or_default();
+        json.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sessão curta: um jogador entra, anda e tenta roubar caravanas
+    fn play(world: &mut World, replay: &mut Replay) {
+        let login = ClientMessage::Login {
+            player_name: "Hero".to_string(),
+        };
+        let player = world.spawn_player("Hero".to_string()).unwrap();
+        replay.record(world.current_tick, player, &login);
+        for step in 0..300 {
+            world.tick();
+            let message = if step % 50 == 49 {
+                ClientMessage::RobCaravan
+            } else {
+                ClientMessage::Move {
+                    dx: [1, 0, -1][step % 3],
+                    dy: [0, 1, 1][step % 3],
+                }
+            };
+            let _ = world.apply_input(player, &message);
+            replay.record(world.current_tick, player, &message);
+            if step % 100 == 99 {
+                replay.checkpoint(world);
+            }
+        }
+    }
+
+    #[test]
+    fn test_replay_reproduces_session() {
+        let config = HistoryGenConfig {
+            years: 10,
+            ..Default::default()
+        };
+        let mut world = World::with_seed(60, 60, 38);
+        world.generate_history(&config);
+        let mut replay = Replay::from_seed(38, 60, 60, Some(config));
+        play(&mut world, &mut replay);
+
+        let replay = Replay::from_json(&replay.to_json().unwrap()).unwrap();
+        let replayed = replay.run().unwrap();
+        assert_eq!(replayed.current_tick, world.current_tick);
+        assert_eq!(replayed.state_hash(), world.state_hash());
+
+        // Partindo de um snapshot no meio da sessão
+        let mut resumed = Replay::from_world(&replayed);
+        let mut world = replayed;
+        play(&mut world, &mut resumed);
+        assert_eq!(resumed.run().unwrap().state_hash(), world.state_hash());
+    }
+
+    #[test]
+    fn test_replay_reports_first_desync() {
+        let mut world = World::with_seed(40, 40, 7);
+        let mut replay = Replay::from_seed(7, 40, 40, None);
+        play(&mut world, &mut replay);
+
+        replay.checkpoints[1].state_hash ^= 1;
+        let error = replay.run().err().unwrap();
+        assert!(error.contains(&format!("tick {}", replay.checkpoints[1].tick)), "{}", error);
+    }
+}
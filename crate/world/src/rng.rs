@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+/// Gerador pseudoaleatório determinístico do mundo (SplitMix64).
+///
+/// O estado é serializável para que a simulação possa ser salva e
+/// reproduzida exatamente a partir da mesma semente.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WorldRng {
+    state: u64,
+}
+
+impl WorldRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Inteiro no intervalo [min, max)
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+        let span = (max as i64 - min as i64) as u64;
+        (min as i64 + (self.next_u64() % span) as i64) as i32
+    }
+
+    /// Retorna true com a probabilidade informada (0.0 - 1.0)
+    pub fn chance(&mut self, probability: f32) -> bool {
+        let roll = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        roll < probability
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        let index = (self.next_u64() % items.len() as u64) as usize;
+        items.get(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = WorldRng::new(42);
+        let mut b = WorldRng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        let mut rng = WorldRng::new(7);
+        for _ in 0..1000 {
+            let v = rng.range(-3, 5);
+            assert!((-3..5).contains(&v));
+        }
+        assert_eq!(rng.range(4, 4), 4);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::history::Happening;
+use crate::journal::Mutation;
+use crate::World;
+use common::*;
+
+/// Carga trocada entre dois locais a partir da qual eles abrem uma estrada
+pub const ROAD_TRADE_THRESHOLD: u32 = 300;
+
+impl World {
+    /// Verifica se a posição é estrada
+    pub fn is_road(&self, pos: Position) -> bool {
+        self.terrain_layer.get(&pos).is_some_and(|t| t.terrain == TerrainType::ROAD)
+    }
+
+    /// Abre uma estrada entre dois locais pelo caminho mais barato. O
+    /// terreno vira estrada fora das construções; caminhos que já passam
+    /// por estradas as aproveitam. Retorna `false` se os locais já estão
+    /// ligados ou não há caminho entre eles.
+    pub fn build_road(&mut self, from_site: u32, to_site: u32) -> bool {
+        let (Some(from), Some(to)) = (self.sites.get(&from_site), self.sites.get(&to_site)) else {
+            return false;
+        };
+        if from_site == to_site || from.roads.contains(&to_site) {
+            return false;
+        }
+        let (faction, location) = (from.owner, from.center);
+        let Some(path) = self.find_path(from.center, to.center) else {
+            return false;
+        };
+
+        let road = self.tile(TerrainType::ROAD);
+        for pos in path {
+            if self.construction_layer.contains_key(&pos) {
+                continue;
+            }
+            if let Some(tile) = self.terrain_layer.get_mut(&pos) {
+                *tile = road.clone();
+                self.record(|| Mutation::TileChanged { pos, tile: road.clone() });
+                self.touch(pos);
+            }
+        }
+        if let Some(site) = self.sites.get_mut(&from_site) {
+            site.roads.push(to_site);
+        }
+        if let Some(site) = self.sites.get_mut(&to_site) {
+            site.roads.push(from_site);
+        }
+
+        self.report_happening(Happening::RoadBuilt {
+            faction,
+            from_site,
+            to_site,
+            location,
+        });
+        true
+    }
+
+    /// Registra carga entregue entre dois locais; comércio intenso entre
+    /// eles acaba abrindo uma estrada
+    pub(crate) fn record_trade(&mut self, origin: u32, destination: u32, amount: u32) {
+        let mut volume = 0;
+        for (site_id, partner) in [(origin, destination), (destination, origin)] {
+            if let Some(site) = self.sites.get_mut(&site_id) {
+                let traded = site.trade_volume.entry(partner).or_default();
+                *traded += amount;
+                volume = *traded;
+            }
+        }
+        if volume >= ROAD_TRADE_THRESHOLD {
+            self.build_road(origin, destination);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SiteKind;
+
+    #[test]
+    fn test_road_connects_sites() {
+        let mut world = World::new(40, 40);
+        let a = world.found_site("Ashford".to_string(), SiteKind::Town, None, Position::new(6, 6), 30);
+        let b = world.found_site("Brookend".to_string(), SiteKind::Town, None, Position::new(6, 30), 30);
+
+        assert!(world.build_road(a, b));
+        assert!(!world.build_road(b, a));
+        assert!(world.is_road(Position::new(6, 18)));
+        assert_eq!(world.get_construction(Position::new(6, 8)).map(|c| c.kind), Some(ConstructionType::Door));
+        assert_eq!(world.get_site(a).unwrap().roads, vec![b]);
+        assert_eq!(world.get_site(b).unwrap().roads, vec![a]);
+
+        let history = world.site_history(a);
+        assert_eq!(history.last().unwrap().description, "A road was laid between Ashford and Brookend");
+    }
+
+    #[test]
+    fn test_heavy_trade_builds_road() {
+        let mut world = World::new(40, 40);
+        let a = world.found_site("Ashford".to_string(), SiteKind::Town, None, Position::new(6, 6), 30);
+        let b = world.found_site("Brookend".to_string(), SiteKind::Town, None, Position::new(6, 30), 30);
+
+        world.record_trade(a, b, ROAD_TRADE_THRESHOLD - 1);
+        assert!(world.get_site(a).unwrap().roads.is_empty());
+        world.record_trade(b, a, 1);
+        assert_eq!(world.get_site(a).unwrap().roads, vec![b]);
+        assert_eq!(world.get_site(b).unwrap().trade_volume[&a], ROAD_TRADE_THRESHOLD);
+    }
+}
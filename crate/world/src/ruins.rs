@@ -0,0 +1,241 @@
+use crate::economy::Stockpile;
+use crate::history::Happening;
+use crate::sites::SiteState;
+use crate::World;
+use common::*;
+use serde::{Deserialize, Serialize};
+
+/// Intervalo, em ticks, entre passos de decadência dos locais desolados
+pub const DECAY_INTERVAL: u64 = 100;
+
+/// O que despovoou um local
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Desolation {
+    /// Tomado por inimigos e saqueado
+    Conquered,
+    /// Incendiado
+    Burned,
+    /// Deixado para trás pelos moradores
+    Abandoned,
+}
+
+impl Desolation {
+    /// Chance de cada construção de pé desabar a cada passo de decadência
+    fn decay_chance(self) -> f32 {
+        match self {
+            Desolation::Burned => 0.5,
+            Desolation::Conquered => 0.2,
+            Desolation::Abandoned => 0.05,
+        }
+    }
+
+    /// Se um item fica no local depois da desolação
+    fn leaves_behind(self, kind: ItemKind) -> bool {
+        match (self, kind) {
+            (_, ItemKind::Corpse { .. }) => true,
+            // Saqueadores levam o que tem valor ou enche a barriga
+            (Desolation::Conquered, ItemKind::Tool) => true,
+            (Desolation::Conquered, _) => false,
+            // O fogo consome comida e ferramentas; metal sobra nas cinzas
+            (Desolation::Burned, ItemKind::Food | ItemKind::Tool) => false,
+            (Desolation::Burned, _) => true,
+            // Quem parte leva os tesouros e deixa o peso para trás
+            (Desolation::Abandoned, ItemKind::Treasure) => false,
+            (Desolation::Abandoned, _) => true,
+        }
+    }
+
+    /// Se um recurso do estoque é levado (saqueado, consumido pelo fogo
+    /// ou carregado pelos moradores)
+    fn removes(self, resource: Resource) -> bool {
+        match self {
+            Desolation::Conquered => resource != Resource::Stone,
+            Desolation::Burned => matches!(resource, Resource::Food | Resource::Wood),
+            Desolation::Abandoned => matches!(resource, Resource::Food | Resource::Metal),
+        }
+    }
+}
+
+impl World {
+    /// Inimigos tomam o local: os moradores somem e os bens são saqueados.
+    /// Retorna o butim tirado do estoque.
+    pub fn sack_site(&mut self, site_id: u32) -> Stockpile {
+        self.desolate_site(site_id, Desolation::Conquered)
+            .map(|(_, loot)| loot)
+            .unwrap_or_default()
+    }
+
+    /// O local é incendiado, opcionalmente por uma fação
+    pub fn burn_site(&mut self, site_id: u32, by: Option<u32>) {
+        let Some((center, _)) = self.desolate_site(site_id, Desolation::Burned) else {
+            return;
+        };
+        self.report_happening(Happening::Burning {
+            site_id,
+            faction: by,
+            location: center,
+        });
+    }
+
+    /// Moradores deixam o local; as construções permanecem de pé por um
+    /// tempo. Retorna o que os moradores levam consigo.
+    pub fn abandon_site(&mut self, site_id: u32) -> Stockpile {
+        self.desolate_site(site_id, Desolation::Abandoned)
+            .map(|(_, carried)| carried)
+            .unwrap_or_default()
+    }
+
+    /// Despovoa o local e aplica o destino aos itens e ao estoque;
+    /// retorna o centro e o que saiu do estoque
+    fn desolate_site(&mut self, site_id: u32, desolation: Desolation) -> Option<(Position, Stockpile)> {
+        let site = self.sites.get_mut(&site_id)?;
+        let owner = site.owner.take();
+        site.state = SiteState::Abandoned;
+        site.population = 0;
+        site.desolation = Some(desolation);
+        let center = site.center;
+        let footprint = site.footprint.clone();
+
+        let mut removed = Stockpile::default();
+        for resource in Resource::ALL.into_iter().filter(|r| desolation.removes(*r)) {
+            let amount = site.stockpile.get(resource);
+            removed.add(resource, site.stockpile.take(resource, amount));
+        }
+
+        for pos in footprint {
+            self.retain_items(pos, |item| desolation.leaves_behind(item.kind));
+        }
+        self.refresh_member_count(owner);
+        Some((center, removed))
+    }
+
+    /// Um passo de decadência: muralhas e portas de locais desolados
+    /// desabam aos poucos; quando nada resta de pé, o local vira ruína
+    pub(crate) fn decay_ruins(&mut self) {
+        let mut decaying: Vec<u32> = self
+            .sites
+            .values()
+            .filter(|s| s.state == SiteState::Abandoned)
+            .map(|s| s.id)
+            .collect();
+        decaying.sort_unstable();
+
+        for site_id in decaying {
+            let site = &self.sites[&site_id];
+            let chance = site.desolation.unwrap_or(Desolation::Abandoned).decay_chance();
+            let footprint = site.footprint.clone();
+            let center = site.center;
+
+            let mut standing = false;
+            for pos in footprint {
+                let Some(construction) = self.construction_layer.get_mut(&pos) else {
+                    continue;
+                };
+                if !matches!(construction.kind, ConstructionType::Wall | ConstructionType::Door) {
+                    continue;
+                }
+                if self.rng.chance(chance) {
+                    *construction = Construction::ruin();
+                    self.touch(pos);
+                } else {
+                    standing = true;
+                }
+            }
+
+            if !standing {
+                if let Some(site) = self.sites.get_mut(&site_id) {
+                    site.state = SiteState::Ruined;
+                }
+                self.report_happening(Happening::Ruination {
+                    site_id,
+                    location: center,
+                });
+            }
+        }
+    }
+
+    /// Eventos históricos ocorridos em um local, em ordem cronológica
+    pub fn site_history(&self, site_id: u32) -> Vec<&HistoricalEvent> {
+        let Some(site) = self.sites.get(&site_id) else {
+            return Vec::new();
+        };
+        site.history
+            .iter()
+            .filter_map(|id| {
+                self.historical_events
+                    .binary_search_by_key(id, |e| e.id)
+                    .ok()
+                    .map(|index| &self.historical_events[index])
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SiteKind;
+
+    fn decay_for(world: &mut World, steps: u64) {
+        for _ in 0..steps * DECAY_INTERVAL {
+            world.tick();
+        }
+    }
+
+    #[test]
+    fn test_sacked_site_decays_into_ruin() {
+        let mut world = World::new(40, 40);
+        let center = Position::new(10, 10);
+        let id = world.found_site("Ashford".to_string(), SiteKind::Town, None, center, 30);
+
+        let loot = world.sack_site(id);
+        assert_eq!(loot.food, 90);
+        assert_eq!(world.get_site(id).unwrap().stockpile.stone, 30);
+        assert_eq!(world.get_site(id).unwrap().state, SiteState::Abandoned);
+        assert_eq!(world.get_construction(center.moved(2, 0)).map(|c| c.kind), Some(ConstructionType::Wall));
+        assert!(world.get_items(center).is_empty());
+        assert!(world.get_items(center.moved(-1, -1)).iter().all(|i| i.kind == ItemKind::Tool));
+
+        decay_for(&mut world, 60);
+        assert_eq!(world.get_site(id).unwrap().state, SiteState::Ruined);
+        assert_eq!(world.get_construction(center.moved(2, 0)).map(|c| c.kind), Some(ConstructionType::Ruin));
+
+        let history = world.site_history(id);
+        assert_eq!(history.last().map(|e| e.event_type), Some(EventType::SiteRuined));
+        assert_eq!(history.last().unwrap().description, "The last walls of Ashford crumbled into ruin");
+    }
+
+    #[test]
+    fn test_burned_site_remembers_the_dead() {
+        let mut world = World::new(40, 40);
+        let center = Position::new(25, 10);
+        let id = world.found_site("Quietmere".to_string(), SiteKind::Town, None, center, 30);
+        let victim = world.spawn_entity("Urist".to_string(), center, EntityType::NPC).unwrap();
+
+        world.kill_entity(victim, None);
+        world.burn_site(id, None);
+
+        let items = world.get_items(center);
+        assert!(items.iter().any(|i| i.kind == ItemKind::Corpse { of: victim }));
+        assert!(items.iter().any(|i| i.kind == ItemKind::Treasure));
+        assert!(world.get_items(center.moved(-1, -1)).is_empty());
+
+        let history: Vec<&str> = world.site_history(id).iter().map(|e| e.description.as_str()).collect();
+        assert_eq!(history, vec!["Urist died at Quietmere", "Quietmere burned to the ground"]);
+    }
+
+    #[test]
+    fn test_abandoned_site_keeps_standing_for_a_while() {
+        let mut world = World::new(40, 40);
+        let center = Position::new(10, 25);
+        let id = world.found_site("Lastlight".to_string(), SiteKind::Town, None, center, 30);
+
+        world.abandon_site(id);
+        decay_for(&mut world, 1);
+
+        let site = world.get_site(id).unwrap();
+        assert_eq!(site.state, SiteState::Abandoned);
+        assert_eq!(site.desolation, Some(Desolation::Abandoned));
+        assert!(world.get_items(center.moved(1, -1)).iter().any(|i| i.kind == ItemKind::Food));
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{Glyph, Mutation, World};
+use common::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+
+/// Algo espalhado sobre um tile, entre o terreno e os itens: fogo, sangue,
+/// cinzas, gelo
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SurfaceDef {
+    pub id: String,
+    pub name: String,
+    /// Reavaliada pelas regras a cada tick, mesmo sem nada mudar no tile
+    /// (o fogo se alastra e se apaga sozinho; cinzas ficam)
+    #[serde(default)]
+    pub active: bool,
+    pub glyph: Glyph,
+}
+
+/// Condição sobre as camadas de um tile. Campos vazios não restringem;
+/// listas pedem um dos valores listados.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TileCondition {
+    #[serde(default)]
+    pub terrain: Vec<TerrainType>,
+    #[serde(default)]
+    pub construction: Vec<ConstructionType>,
+    /// O tile não tem construção
+    #[serde(default)]
+    pub no_construction: bool,
+    #[serde(default)]
+    pub surface: Vec<String>,
+    /// O tile não tem superfície
+    #[serde(default)]
+    pub no_surface: bool,
+    /// Algum item largado no tile é de material inflamável
+    #[serde(default)]
+    pub flammable_items: bool,
+    /// Alguma entidade está no tile
+    #[serde(default)]
+    pub entity: bool,
+}
+
+/// O que uma regra faz com o tile quando dispara
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleEffect {
+    SetSurface(String),
+    ClearSurface,
+    SetTerrain(TerrainType),
+    SetConstruction(ConstructionType),
+    ClearConstruction,
+    /// Destrói os itens de material inflamável
+    BurnItems,
+    /// Entidades com IA no tile guardam uma lembrança
+    Remember(String),
+}
+
+fn one() -> u32 {
+    1
+}
+
+fn always() -> f32 {
+    1.0
+}
+
+/// Interação entre camadas: quando o tile passa em `at` e ao menos
+/// `near_min` dos seus oito vizinhos passam em `near`, os efeitos são
+/// aplicados com a chance dada
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RuleDef {
+    pub id: String,
+    #[serde(default)]
+    pub at: TileCondition,
+    #[serde(default)]
+    pub near: Option<TileCondition>,
+    #[serde(default = "one")]
+    pub near_min: u32,
+    #[serde(default = "always")]
+    pub chance: f32,
+    pub effects: Vec<RuleEffect>,
+}
+
+/// Os oito vizinhos de uma posição (podem estar fora do mundo)
+fn neighbours(pos: Position) -> impl Iterator<Item = Position> {
+    (-1..=1)
+        .flat_map(move |dy| (-1..=1).map(move |dx| (dx, dy)))
+        .filter(|&(dx, dy)| dx != 0 || dy != 0)
+        .map(move |(dx, dy)| pos.moved(dx, dy))
+}
+
+impl World {
+    /// Superfície sobre um tile, se houver
+    pub fn get_surface(&self, pos: Position) -> Option<&str> {
+        self.surface_layer.get(&pos).map(String::as_str)
+    }
+
+    /// Põe ou tira a superfície de um tile; retorna se algo mudou. A
+    /// superfície precisa estar definida nos raws.
+    pub fn set_surface(&mut self, pos: Position, surface: Option<&str>) -> bool {
+        if !self.is_valid_position(pos) || surface.is_some_and(|id| self.raws.surface(id).is_none()) {
+            return false;
+        }
+        if self.get_surface(pos) == surface {
+            return false;
+        }
+        match surface {
+            Some(id) => self.surface_layer.insert(pos, id.to_string()),
+            None => self.surface_layer.remove(&pos),
+        };
+        let surface = surface.map(str::to_string);
+        self.record(|| Mutation::SurfaceChanged { pos, surface });
+        self.touch(pos);
+        true
+    }
+
+    /// Marca um tile como alterado: as regras o reavaliam, junto com os
+    /// vizinhos, no próximo tick
+    pub(crate) fn touch(&mut self, pos: Position) {
+        self.changed_tiles.insert(pos);
+    }
+
+    /// Sistema das regras de interação entre camadas. Avalia só os tiles
+    /// alterados desde a última passada, os de superfície ativa e os
+    /// vizinhos de ambos; todas as regras olham o mundo como estava no
+    /// início da passada, e os efeitos são aplicados depois, em ordem de
+    /// posição e de regra.
+    pub(crate) fn apply_rules(&mut self) {
+        let changed = std::mem::take(&mut self.changed_tiles);
+        let raws = self.raws.clone();
+        if raws.rules.is_empty() {
+            return;
+        }
+
+        let active = self
+            .surface_layer
+            .iter()
+            .filter(|(_, id)| raws.surface(id).is_some_and(|s| s.active))
+            .map(|(pos, _)| *pos);
+        let mut candidates = BTreeSet::new();
+        for pos in changed.into_iter().chain(active) {
+            for pos in std::iter::once(pos).chain(neighbours(pos)) {
+                if self.is_valid_position(pos) {
+                    candidates.insert((pos.y, pos.x));
+                }
+            }
+        }
+
+        let occupied: HashSet<Position> = self.entity_layer.values().map(|e| e.pos).collect();
+        let mut fired = Vec::new();
+        for (y, x) in candidates {
+            let pos = Position::new(x, y);
+            for rule in &raws.rules {
+                if self.rule_matches(pos, rule, &occupied) && (rule.chance >= 1.0 || self.rng.chance(rule.chance)) {
+                    fired.push((pos, rule));
+                }
+            }
+        }
+        for (pos, rule) in fired {
+            for effect in &rule.effects {
+                self.apply_effect(pos, effect);
+            }
+        }
+    }
+
+    fn rule_matches(&self, pos: Position, rule: &RuleDef, occupied: &HashSet<Position>) -> bool {
+        if !self.tile_matches(pos, &rule.at, occupied) {
+            return false;
+        }
+        match &rule.near {
+            Some(near) => {
+                let count = neighbours(pos)
+                    .filter(|p| self.tile_matches(*p, near, occupied))
+                    .count();
+                count >= rule.near_min as usize
+            }
+            None => true,
+        }
+    }
+
+    /// Se as camadas do tile passam na condição; `occupied` são as
+    /// posições com alguma entidade
+    fn tile_matches(&self, pos: Position, condition: &TileCondition, occupied: &HashSet<Position>) -> bool {
+        let Some(tile) = self.terrain_layer.get(&pos) else {
+            return false;
+        };
+        let construction = self.construction_layer.get(&pos).map(|c| c.kind);
+        let surface = self.get_surface(pos);
+
+        (condition.terrain.is_empty() || condition.terrain.contains(&tile.terrain))
+            && (condition.construction.is_empty() || construction.is_some_and(|c| condition.construction.contains(&c)))
+            && !(condition.no_construction && construction.is_some())
+            && (condition.surface.is_empty() || surface.is_some_and(|s| condition.surface.iter().any(|c| c == s)))
+            && !(condition.no_surface && surface.is_some())
+            && (!condition.flammable_items || self.get_items(pos).iter().any(|i| self.is_flammable(i)))
+            && (!condition.entity || occupied.contains(&pos))
+    }
+
+    fn is_flammable(&self, item: &Item) -> bool {
+        let material = self.raws.item_of(item).material.as_deref();
+        material
+            .and_then(|m| self.raws.material(m))
+            .is_some_and(|m| m.flammable)
+    }
+
+    fn apply_effect(&mut self, pos: Position, effect: &RuleEffect) {
+        match effect {
+            RuleEffect::SetSurface(id) => {
+                self.set_surface(pos, Some(id));
+            }
+            RuleEffect::ClearSurface => {
+                self.set_surface(pos, None);
+            }
+            RuleEffect::SetTerrain(terrain) => {
+                let tile = self.tile(terrain.id());
+                if self.terrain_layer.get(&pos) != Some(&tile) {
+                    self.terrain_layer.insert(pos, tile.clone());
+                    self.record(|| Mutation::TileChanged { pos, tile });
+                    self.touch(pos);
+                }
+            }
+            RuleEffect::SetConstruction(kind) => {
+                let construction = match kind {
+                    ConstructionType::Wall => Construction::wall(),
+                    ConstructionType::Door => Construction::door(),
+                    ConstructionType::Floor => Construction::floor(),
+                    ConstructionType::Ruin => Construction::ruin(),
+                };
+                if self.construction_layer.get(&pos) != Some(&construction) {
+                    self.construction_layer.insert(pos, construction);
+                    self.touch(pos);
+                }
+            }
+            RuleEffect::ClearConstruction => {
+                if self.construction_layer.remove(&pos).is_some() {
+                    self.touch(pos);
+                }
+            }
+            RuleEffect::BurnItems => {
+                let flammable: Vec<u32> = self
+                    .get_items(pos)
+                    .iter()
+                    .filter(|i| self.is_flammable(i))
+                    .map(|i| i.id)
+                    .collect();
+                self.retain_items(pos, |item| !flammable.contains(&item.id));
+            }
+            RuleEffect::Remember(event) => {
+                let mut present: Vec<u32> = self
+                    .entity_layer
+                    .values()
+                    .filter(|e| e.pos == pos)
+                    .map(|e| e.id)
+                    .collect();
+                present.sort_unstable();
+                for id in present {
+                    self.remember(id, event.clone());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grassland() -> World {
+        let mut world = World::new(40, 40);
+        for id in world.get_entity_ids() {
+            world.despawn_entity(id);
+        }
+        world
+    }
+
+    #[test]
+    fn test_fire_spreads_over_grass_and_burns_out() {
+        let mut world = grassland();
+        let start = Position::new(30, 30);
+        world.place_item(start, "a sack of grain".to_string(), ItemKind::Food);
+        world.place_item(start, "a cooking pot".to_string(), ItemKind::Tool);
+        assert!(world.set_surface(start, Some("fire")));
+
+        let mut burned = 0;
+        for _ in 0..400 {
+            world.tick();
+            burned = burned.max(
+                (25..36)
+                    .flat_map(|y| (25..36).map(move |x| Position::new(x, y)))
+                    .filter(|p| world.get_surface(*p).is_some())
+                    .count(),
+            );
+        }
+        assert!(burned > 1, "o fogo não se espalhou");
+        // O grão queima; a panela, de ferro, fica
+        let items = world.get_items(start);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].name, "a cooking pot");
+        assert!(
+            world.surface_layer.values().all(|s| s != "fire"),
+            "o fogo não se apagou"
+        );
+    }
+
+    #[test]
+    fn test_rules_only_wake_up_on_changes() {
+        let mut world = grassland();
+        let pos = Position::new(20, 25);
+        assert!(world.set_surface(pos, Some("ice")));
+        world.tick();
+        assert!(world.changed_tiles.is_empty());
+
+        let id = world
+            .spawn_entity("Urist".to_string(), pos.moved(-1, 0), EntityType::NPC)
+            .unwrap();
+        world.ai_behaviors.insert(
+            id,
+            AIBehavior {
+                current_goal: AIGoal::Sleep,
+                memory: Vec::new(),
+                personality: Personality {
+                    aggression: 0.0,
+                    curiosity: 0.0,
+                    sociability: 0.0,
+                },
+            },
+        );
+        world.tick();
+        assert!(world.ai_behaviors[&id].memory.is_empty());
+
+        world.move_entity(id, 1, 0);
+        world.tick();
+        let memory = &world.ai_behaviors[&id].memory;
+        assert_eq!(memory.len(), 1);
+        assert_eq!(memory[0].event, "Slipped on the ice");
+        // Parada sobre o gelo, a entidade não escorrega de novo
+        world.tick();
+        assert_eq!(world.ai_behaviors[&id].memory.len(), 1);
+    }
+}
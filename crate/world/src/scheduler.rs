@@ -0,0 +1,188 @@
+use crate::{World, COARSE_INTERVAL, DECAY_INTERVAL, ECONOMY_INTERVAL, TICKS_PER_YEAR};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// Fases de um tick, na ordem em que rodam
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    /// Decisões: IA das entidades e das fações
+    Ai,
+    Movement,
+    Combat,
+    Climate,
+    Economy,
+    /// Consequências de longo prazo: história, decadência das ruínas
+    History,
+    /// Os sistemas reagem aos eventos publicados no tick
+    Events,
+}
+
+/// Sistema da simulação registrado no agendador
+#[derive(Clone)]
+pub struct System {
+    pub name: &'static str,
+    pub phase: Phase,
+    /// Posição dentro da fase (menor roda antes)
+    pub order: i32,
+    /// Roda nos ticks múltiplos deste intervalo (1 = todo tick)
+    pub every: u64,
+    pub run: fn(&mut World),
+}
+
+impl System {
+    /// Sistema na ordem 0 da sua fase
+    pub fn new(name: &'static str, phase: Phase, every: u64, run: fn(&mut World)) -> Self {
+        Self {
+            name,
+            phase,
+            order: 0,
+            every,
+            run,
+        }
+    }
+}
+
+/// Tempo gasto por um sistema
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SystemTiming {
+    pub name: String,
+    pub phase: Phase,
+    pub every: u64,
+    pub runs: u64,
+    pub last_us: u64,
+    pub max_us: u64,
+    pub total_us: u64,
+}
+
+impl SystemTiming {
+    /// Tempo médio por execução, em microssegundos
+    pub fn average_us(&self) -> u64 {
+        self.total_us.checked_div(self.runs).unwrap_or(0)
+    }
+}
+
+/// Roda os sistemas de cada tick em ordem de fase e mede quanto cada um
+/// leva
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    /// Ordenados por fase e ordem; `timings[i]` é o de `systems[i]`
+    systems: Vec<System>,
+    timings: Vec<SystemTiming>,
+}
+
+impl Scheduler {
+    /// Os sistemas da simulação do mundo
+    pub fn with_builtin_systems() -> Self {
+        let mut scheduler = Self::default();
+        // Mesma fase e ordem: rodam na ordem em que aparecem aqui
+        let builtin = [
+            System::new("ai", Phase::Ai, 1, World::update_ai),
+            System::new("caravans", Phase::Movement, 1, World::update_detailed_caravans),
+            System::new("coarse", Phase::Movement, COARSE_INTERVAL, World::simulate_coarse),
+            System::new("combat", Phase::Combat, 1, World::resolve_combat),
+            System::new("rules", Phase::Climate, 1, World::apply_rules),
+            System::new("economy", Phase::Economy, ECONOMY_INTERVAL, |world| world.run_economy(1)),
+            System::new("shortages", Phase::Economy, TICKS_PER_YEAR, World::resolve_shortages),
+            System::new("ruins", Phase::History, DECAY_INTERVAL, World::decay_ruins),
+            System::new("events", Phase::Events, 1, World::dispatch_events),
+        ];
+        for system in builtin {
+            scheduler.register(system);
+        }
+        scheduler
+    }
+
+    /// Registra um sistema; com fase e ordem iguais, roda depois dos já
+    /// registrados
+    pub fn register(&mut self, system: System) {
+        let at = self
+            .systems
+            .partition_point(|s| (s.phase, s.order) <= (system.phase, system.order));
+        self.timings.insert(at, SystemTiming {
+            name: system.name.to_string(),
+            phase: system.phase,
+            every: system.every.max(1),
+            runs: 0,
+            last_us: 0,
+            max_us: 0,
+            total_us: 0,
+        });
+        self.systems.insert(at, System {
+            every: system.every.max(1),
+            ..system
+        });
+    }
+
+    /// Roda os sistemas devidos neste tick
+    fn run(&mut self, world: &mut World) {
+        let tick = world.current_tick;
+        for (system, timing) in self.systems.iter().zip(&mut self.timings) {
+            if !tick.is_multiple_of(system.every) {
+                continue;
+            }
+            let started = Instant::now();
+            (system.run)(world);
+            let elapsed = started.elapsed().as_micros().min(u64::MAX as u128) as u64;
+            timing.runs += 1;
+            timing.last_us = elapsed;
+            timing.max_us = timing.max_us.max(elapsed);
+            timing.total_us += elapsed;
+        }
+    }
+
+    pub fn timings(&self) -> &[SystemTiming] {
+        &self.timings
+    }
+}
+
+impl World {
+    /// Registra um sistema a mais na simulação
+    pub fn register_system(&mut self, system: System) {
+        self.scheduler.register(system);
+    }
+
+    /// Tempo gasto por cada sistema, na ordem em que rodam
+    pub fn system_timings(&self) -> &[SystemTiming] {
+        self.scheduler.timings()
+    }
+
+    /// Roda os sistemas do tick atual
+    pub(crate) fn run_systems(&mut self) {
+        let mut scheduler = std::mem::take(&mut self.scheduler);
+        scheduler.run(self);
+        // Um sistema pode ter registrado outros enquanto rodava
+        for system in std::mem::take(&mut self.scheduler).systems {
+            scheduler.register(system);
+        }
+        self.scheduler = scheduler;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_systems_run_in_phase_order_at_their_frequency() {
+        let mut world = World::new(30, 30);
+        world.register_system(System {
+            order: 100,
+            ..System::new("late", Phase::History, 10, |_| {})
+        });
+        world.register_system(System {
+            order: -1,
+            ..System::new("first", Phase::Ai, 1, |_| {})
+        });
+        for _ in 0..100 {
+            world.tick();
+        }
+
+        let names: Vec<&str> = world.system_timings().iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["first", "ai", "caravans", "coarse", "combat", "rules", "economy", "shortages", "ruins", "late", "events"]);
+        let runs = |name: &str| world.system_timings().iter().find(|t| t.name == name).unwrap().runs;
+        assert_eq!(runs("first"), 100);
+        assert_eq!(runs("late"), 10);
+        assert_eq!(runs("economy"), 1);
+        assert_eq!(runs("shortages"), 0);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::economy::Stockpile;
+use crate::ruins::Desolation;
+use crate::World;
+use common::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Raio (Chebyshev) da muralha de um assentamento
+pub const SITE_WALL_RADIUS: i32 = 2;
+/// Raio de território reivindicado ao redor de um assentamento
+pub const SITE_CLAIM_RADIUS: i32 = 5;
+/// População máxima sustentada por um assentamento
+pub const SITE_CAPACITY: usize = 150;
+
+/// Local do mundo: cidade, acampamento, covil ou o que restou deles
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Site {
+    pub id: u32,
+    pub name: String,
+    pub kind: SiteKind,
+    pub center: Position,
+    /// Tiles ocupados pelas construções do local
+    pub footprint: Vec<Position>,
+    /// Fação dona do local (covis e ruínas não têm dono)
+    pub owner: Option<u32>,
+    pub population: usize,
+    pub buildings: Vec<Building>,
+    pub state: SiteState,
+    pub founded_tick: u64,
+    /// O que despovoou o local, se ele não está mais habitado
+    #[serde(default)]
+    pub desolation: Option<Desolation>,
+    /// IDs dos eventos históricos ocorridos dentro do local
+    #[serde(default)]
+    pub history: Vec<u64>,
+    /// Recursos guardados pelos moradores
+    #[serde(default)]
+    pub stockpile: Stockpile,
+    /// Locais ligados a este por estrada
+    #[serde(default)]
+    pub roads: Vec<u32>,
+    /// Unidades de carga trocadas com cada parceiro comercial
+    #[serde(default)]
+    pub trade_volume: HashMap<u32, u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum SiteKind {
+    Town,
+    Fortress,
+    Camp,
+    Lair,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum SiteState {
+    Thriving,
+    Abandoned,
+    Ruined,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Building {
+    pub kind: BuildingKind,
+    pub pos: Position,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BuildingKind {
+    Hall,
+    House,
+    Workshop,
+    Tent,
+    Den,
+}
+
+impl Site {
+    pub fn is_inhabited(&self) -> bool {
+        self.state == SiteState::Thriving
+    }
+}
+
+/// Construções internas de cada tipo de local
+fn layout_for(kind: SiteKind) -> &'static [(BuildingKind, i32, i32)] {
+    match kind {
+        SiteKind::Town => &[(BuildingKind::Hall, 0, 0), (BuildingKind::House, -1, -1), (BuildingKind::House, 1, -1)],
+        SiteKind::Fortress => &[(BuildingKind::Hall, 0, 0), (BuildingKind::Workshop, -1, 1), (BuildingKind::Workshop, 1, 1)],
+        SiteKind::Camp => &[(BuildingKind::Tent, -1, 0), (BuildingKind::Tent, 1, 0)],
+        SiteKind::Lair => &[(BuildingKind::Den, 0, 0)],
+    }
+}
+
+impl World {
+    /// Funda um local: ergue as construções na camada de construções e,
+    /// se tiver dono, reivindica o território ao redor
+    pub fn found_site(
+        &mut self,
+        name: String,
+        kind: SiteKind,
+        owner: Option<u32>,
+        center: Position,
+        population: usize,
+    ) -> u32 {
+        let r = SITE_WALL_RADIUS;
+        let mut footprint = Vec::new();
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let pos = center.moved(dx, dy);
+                if !self.is_valid_position(pos) {
+                    continue;
+                }
+                // Covis não têm muralha, apenas o chão batido
+                let construction = if kind == SiteKind::Lair || (dx.abs() < r && dy.abs() < r) {
+                    Construction::floor()
+                } else if dx == 0 && dy == r {
+                    Construction::door()
+                } else {
+                    Construction::wall()
+                };
+                self.construction_layer.insert(pos, construction);
+                self.touch(pos);
+                footprint.push(pos);
+            }
+        }
+
+        let buildings: Vec<Building> = layout_for(kind)
+            .iter()
+            .map(|(kind, dx, dy)| Building {
+                kind: *kind,
+                pos: center.moved(*dx, *dy),
+            })
+            .collect();
+        // Bens guardados em cada construção, segundo os raws
+        let raws = self.raws.clone();
+        for building in &buildings {
+            for item in raws.goods_in(building.kind) {
+                if let Some(kind) = item.class.item_kind() {
+                    self.place_item(building.pos, item.name.clone(), kind);
+                }
+            }
+        }
+
+        if let Some(faction_id) = owner {
+            let claimed: Vec<Position> = self
+                .positions_around(center, SITE_CLAIM_RADIUS)
+                .into_iter()
+                .filter(|pos| self.territory_owner(*pos).is_none())
+                .collect();
+            if let Some(faction) = self.factions.get_mut(&faction_id) {
+                faction.territory.extend(claimed);
+            }
+        }
+
+        let id = self.next_site_id;
+        self.next_site_id += 1;
+        self.sites.insert(id, Site {
+            id,
+            name,
+            kind,
+            center,
+            footprint,
+            owner,
+            population,
+            buildings,
+            state: SiteState::Thriving,
+            founded_tick: self.current_tick,
+            desolation: None,
+            history: Vec::new(),
+            stockpile: Stockpile::for_population(population),
+            roads: Vec::new(),
+            trade_volume: HashMap::new(),
+        });
+        self.refresh_member_count(owner);
+        id
+    }
+
+    /// Recalcula os membros de uma fação a partir da população dos seus locais
+    pub(crate) fn refresh_member_count(&mut self, faction_id: Option<u32>) {
+        let Some(faction_id) = faction_id else {
+            return;
+        };
+        let total = self
+            .sites
+            .values()
+            .filter(|s| s.owner == Some(faction_id))
+            .map(|s| s.population)
+            .sum();
+        if let Some(faction) = self.factions.get_mut(&faction_id) {
+            faction.member_count = total;
+        }
+    }
+
+    pub(crate) fn positions_around(&self, center: Position, radius: i32) -> Vec<Position> {
+        let mut positions = Vec::new();
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let pos = center.moved(dx, dy);
+                if self.is_valid_position(pos) {
+                    positions.push(pos);
+                }
+            }
+        }
+        positions
+    }
+
+    /// Retorna a fação dona de uma posição, se houver (a de menor ID, se
+    /// territórios se sobrepõem)
+    pub fn territory_owner(&self, pos: Position) -> Option<u32> {
+        self.factions
+            .values()
+            .filter(|f| f.territory.contains(&pos))
+            .map(|f| f.id)
+            .min()
+    }
+
+    /// Retorna local por ID
+    pub fn get_site(&self, id: u32) -> Option<&Site> {
+        self.sites.get(&id)
+    }
+
+    /// Retorna todos os locais ordenados por ID
+    pub fn get_sites(&self) -> Vec<&Site> {
+        let mut sites: Vec<&Site> = self.sites.values().collect();
+        sites.sort_by_key(|s| s.id);
+        sites
+    }
+
+    /// Retorna os locais de uma fação ordenados por ID
+    pub fn sites_of(&self, faction_id: u32) -> Vec<&Site> {
+        self.get_sites()
+            .into_iter()
+            .filter(|s| s.owner == Some(faction_id))
+            .collect()
+    }
+
+    /// Retorna o local que ocupa uma posição
+    pub fn site_at(&self, pos: Position) -> Option<&Site> {
+        self.sites.values().find(|s| s.footprint.contains(&pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_found_site_builds_constructions() {
+        let mut world = World::new(40, 40);
+        let center = Position::new(10, 10);
+        let id = world.found_site("Stonegate".to_string(), SiteKind::Town, None, center, 30);
+
+        let site = world.get_site(id).unwrap();
+        assert_eq!(site.state, SiteState::Thriving);
+        assert_eq!(site.footprint.len(), 25);
+        assert_eq!(world.get_construction(center).map(|c| c.kind), Some(ConstructionType::Floor));
+        assert_eq!(world.get_construction(center.moved(2, 0)).map(|c| c.kind), Some(ConstructionType::Wall));
+        assert_eq!(world.get_construction(center.moved(0, 2)).map(|c| c.kind), Some(ConstructionType::Door));
+        assert_eq!(world.site_at(center.moved(-2, -2)).map(|s| s.id), Some(id));
+        assert!(world.get_items(center).iter().any(|i| i.kind == ItemKind::Treasure));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{World, WorldSave};
+use common::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Quantos snapshots ficam em memória; ao capturar além disso, o mais
+/// antigo é descartado
+pub const MAX_SNAPSHOTS: usize = 16;
+
+/// Snapshots do mundo em memória, identificados pelo tick da captura,
+/// para investigar bugs da simulação e desfazer estragos
+#[derive(Default)]
+pub struct Snapshots {
+    saves: VecDeque<WorldSave>,
+}
+
+/// Resumo de um snapshot em `Snapshots::list`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SnapshotInfo {
+    pub tick: u64,
+    pub entities: usize,
+    pub events: usize,
+}
+
+/// Diferenças entre dois estados do mundo
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WorldDiff {
+    pub from_tick: u64,
+    pub to_tick: u64,
+    pub spawned: Vec<Entity>,
+    /// Entidades que saíram do mundo (mortas ou desconectadas)
+    pub despawned: Vec<Entity>,
+    pub moved: Vec<EntityMove>,
+    pub tiles: Vec<Change<Tile>>,
+    pub constructions: Vec<Change<Construction>>,
+    pub events: Vec<HistoricalEvent>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EntityMove {
+    pub id: u32,
+    pub name: String,
+    pub from: Position,
+    pub to: Position,
+}
+
+/// Conteúdo de uma posição numa camada, antes e depois
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Change<T> {
+    pub pos: Position,
+    pub before: Option<T>,
+    pub after: Option<T>,
+}
+
+impl Snapshots {
+    /// Guarda o estado atual do mundo; substitui um snapshot do mesmo tick
+    pub fn capture(&mut self, world: &World) -> u64 {
+        let tick = world.current_tick;
+        self.saves.retain(|s| s.header.current_tick != tick);
+        if self.saves.len() == MAX_SNAPSHOTS {
+            self.saves.pop_front();
+        }
+        self.saves.push_back(world.to_save());
+        tick
+    }
+
+    pub fn get(&self, tick: u64) -> Option<&WorldSave> {
+        self.saves.iter().find(|s| s.header.current_tick == tick)
+    }
+
+    /// Snapshots guardados, do mais antigo ao mais recente
+    pub fn list(&self) -> Vec<SnapshotInfo> {
+        self.saves
+            .iter()
+            .map(|s| SnapshotInfo {
+                tick: s.header.current_tick,
+                entities: s.world.entities.len(),
+                events: s.world.events.len(),
+            })
+            .collect()
+    }
+}
+
+/// Compara uma camada indexada por posição
+fn layer_changes<T: Clone + PartialEq>(before: &[(Position, T)], after: &[(Position, T)]) -> Vec<Change<T>> {
+    let old: HashMap<Position, &T> = before.iter().map(|(pos, v)| (*pos, v)).collect();
+    let new: HashMap<Position, &T> = after.iter().map(|(pos, v)| (*pos, v)).collect();
+
+    let mut changes: Vec<Change<T>> = old
+        .keys()
+        .chain(new.keys().filter(|pos| !old.contains_key(pos)))
+        .filter(|pos| old.get(pos) != new.get(pos))
+        .map(|pos| Change {
+            pos: *pos,
+            before: old.get(pos).map(|v| (*v).clone()),
+            after: new.get(pos).map(|v| (*v).clone()),
+        })
+        .collect();
+    changes.sort_by_key(|c| (c.pos.y, c.pos.x));
+    changes
+}
+
+impl WorldSave {
+    /// O que mudou deste estado até `later`
+    pub fn diff(&self, later: &WorldSave) -> WorldDiff {
+        let old: HashMap<u32, &Entity> = self.world.entities.iter().map(|e| (e.id, e)).collect();
+        let new: HashMap<u32, &Entity> = later.world.entities.iter().map(|e| (e.id, e)).collect();
+
+        let mut diff = WorldDiff {
+            from_tick: self.header.current_tick,
+            to_tick: later.header.current_tick,
+            tiles: layer_changes(&self.world.terrain, &later.world.terrain),
+            constructions: layer_changes(&self.world.constructions, &later.world.constructions),
+            ..Default::default()
+        };
+        // Entidades vêm ordenadas por ID no save
+        for entity in &later.world.entities {
+            match old.get(&entity.id) {
+                None => diff.spawned.push(entity.clone()),
+                Some(before) if before.pos != entity.pos => diff.moved.push(EntityMove {
+                    id: entity.id,
+                    name: entity.name.clone(),
+                    from: before.pos,
+                    to: entity.pos,
+                }),
+                Some(_) => {}
+            }
+        }
+        diff.despawned = self
+            .world
+            .entities
+            .iter()
+            .filter(|e| !new.contains_key(&e.id))
+            .cloned()
+            .collect();
+
+        let known = self.world.counters.event;
+        diff.events = later.world.events.iter().filter(|e| e.id >= known).cloned().collect();
+        diff
+    }
+}
+
+impl World {
+    /// Volta o mundo ao estado de um snapshot. As entidades em `keep` (os
+    /// jogadores conectados) ficam como estão agora. O diário continua
+    /// ligado, mas não descreve o salto: compacte o `WorldStore` logo em
+    /// seguida.
+    pub fn restore(&mut self, save: &WorldSave, keep: &[u32]) -> Result<(), String> {
+        let kept: Vec<Entity> = keep.iter().filter_map(|id| self.entity_layer.get(id).cloned()).collect();
+        let mut restored = World::from_save(save.clone())?;
+        for entity in kept {
+            restored.next_entity_id = restored.next_entity_id.max(entity.id + 1);
+            restored.entity_layer.insert(entity.id, entity);
+        }
+        restored.journal = self.journal.take();
+        restored.events = std::mem::take(&mut self.events);
+        restored.scheduler = std::mem::take(&mut self.scheduler);
+        restored.raws = self.raws.clone();
+        *self = restored;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_and_restore() {
+        let mut world = World::new(40, 40);
+        let mut snapshots = Snapshots::default();
+        let npc = world.spawn_entity("Goblin".to_string(), Position::new(5, 5), EntityType::NPC).unwrap();
+        let before = snapshots.capture(&world);
+
+        let player = world.spawn_player("Hero".to_string()).unwrap();
+        world.move_entity(npc, 1, 0);
+        world.construction_layer.insert(Position::new(7, 7), Construction::wall());
+        world.tick();
+        snapshots.capture(&world);
+
+        let diff = snapshots.get(before).unwrap().diff(&world.to_save());
+        assert_eq!(diff.to_tick, before + 1);
+        assert_eq!(diff.spawned.iter().map(|e| e.id).collect::<Vec<_>>(), vec![player]);
+        assert!(diff.moved.iter().any(|m| m.id == npc && m.from == Position::new(5, 5)));
+        assert_eq!(diff.constructions.len(), 1);
+        assert_eq!(diff.constructions[0].before, None);
+        assert_eq!(snapshots.list().len(), 2);
+
+        world.restore(snapshots.get(before).unwrap(), &[player]).unwrap();
+        assert_eq!(world.get_current_tick(), before);
+        assert_eq!(world.get_entity(npc).unwrap().pos, Position::new(5, 5));
+        assert!(world.get_construction(Position::new(7, 7)).is_none());
+        assert!(world.get_entity(player).is_some());
+        // Novas entidades não reaproveitam o ID de quem foi mantido
+        assert!(world.spawn_entity("Rat".to_string(), Position::new(2, 2), EntityType::NPC).unwrap() > player);
+    }
+}
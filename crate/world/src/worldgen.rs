@@ -0,0 +1,755 @@
+use crate::sites::{SiteKind, SITE_CAPACITY, SITE_CLAIM_RADIUS, SITE_WALL_RADIUS};
+use crate::economy::Stockpile;
+use crate::{Happening, World, COARSE_INTERVAL, DECAY_INTERVAL, ECONOMY_INTERVAL};
+use common::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Quantidade de ticks que representa um ano na história pré-jogo
+pub const TICKS_PER_YEAR: u64 = 1000;
+
+/// Distância mínima entre dois locais
+const SITE_SPACING: i32 = 12;
+/// População abaixo da qual um assentamento pode ser abandonado
+const ABANDON_POPULATION: usize = 15;
+/// Idade a partir da qual líderes podem morrer de velhice
+const ELDER_AGE_YEARS: u64 = 40;
+
+/// Parâmetros da fase de geração de história (fase de lendas)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryGenConfig {
+    /// Anos simulados antes da chegada dos jogadores
+    pub years: u32,
+    /// Número de civilizações fundadas no início da história
+    pub civilizations: usize,
+    /// Covis de criaturas sem fação espalhados pelo mundo
+    pub lairs: usize,
+}
+
+impl Default for HistoryGenConfig {
+    fn default() -> Self {
+        Self {
+            years: 250,
+            civilizations: 4,
+            lairs: 3,
+        }
+    }
+}
+
+/// Estado transitório da geração de história
+#[derive(Default)]
+struct Chronicle {
+    /// Líder atual de cada fação
+    leaders: HashMap<u32, u32>,
+}
+
+const BEAST_NAMES: [&str; 6] = ["Ashmaw", "Gloomfang", "Vurmox", "Skarn", "Ithrak", "Mossback"];
+const BEAST_TITLES: [&str; 4] = ["the Wyrm", "the Devourer", "the Cave Troll", "the Great Bear"];
+
+impl World {
+    /// Fase de lendas: simula séculos de história antes dos jogadores.
+    ///
+    /// Civilizações fundam assentamentos, migram, guerreiam e produzem
+    /// figuras históricas. Ao final, o mundo tem fações com território,
+    /// locais prósperos, abandonados ou em ruínas, covis de criaturas,
+    /// figuras vivas no mapa e os eventos históricos correspondentes. O
+    /// tick atual avança para o fim da história.
+    pub fn generate_history(&mut self, config: &HistoryGenConfig) {
+        let mut chronicle = Chronicle::default();
+
+        let civilizations = self.raws.civilizations();
+        for i in 0..config.civilizations {
+            let faction_type = civilizations[i % civilizations.len()].clone();
+            self.found_civilization(&mut chronicle, faction_type);
+        }
+        for _ in 0..config.lairs {
+            self.place_lair();
+        }
+
+        for _ in 0..config.years {
+            self.current_tick += TICKS_PER_YEAR;
+            self.run_economy((TICKS_PER_YEAR / ECONOMY_INTERVAL) as u32);
+            for _ in 0..TICKS_PER_YEAR / DECAY_INTERVAL {
+                self.decay_ruins();
+            }
+            // Caravanas viajam aos saltos, como nas regiões sem jogadores
+            for _ in 0..TICKS_PER_YEAR / COARSE_INTERVAL {
+                if self.caravans.is_empty() {
+                    break;
+                }
+                self.update_caravans(COARSE_INTERVAL);
+            }
+            self.resolve_shortages();
+
+            let mut faction_ids: Vec<u32> = chronicle.leaders.keys().copied().collect();
+            faction_ids.sort_unstable();
+
+            for faction_id in faction_ids {
+                if !self.faction_alive(faction_id) {
+                    continue;
+                }
+                self.age_leader(&mut chronicle, faction_id);
+                self.grow_faction(faction_id);
+                self.consider_abandonment(faction_id);
+                self.try_migration(&mut chronicle, faction_id);
+                self.drift_relations(faction_id);
+                self.wage_wars(&mut chronicle, faction_id);
+            }
+        }
+    }
+
+    fn found_civilization(&mut self, chronicle: &mut Chronicle, faction_type: FactionType) {
+        let Some(center) = self.find_site_location(None) else {
+            return;
+        };
+
+        let faction_id = self.next_faction_id;
+        self.next_faction_id += 1;
+
+        let raws = self.raws.clone();
+        let hostile = |faction_type: &FactionType| raws.faction(faction_type.id()).is_some_and(|f| f.hostile);
+        let mut relations = HashMap::new();
+        for other in self.factions.values_mut() {
+            let relation = if hostile(&faction_type) || hostile(&other.faction_type) {
+                Relation::Hostile
+            } else {
+                Relation::Neutral
+            };
+            other.relations.insert(faction_id, relation);
+            relations.insert(other.id, relation);
+        }
+
+        let name = self.faction_name_for(faction_type.id());
+        let site = self.site_kind_for(faction_type.id());
+        let site_name = self.site_name_for(faction_type.id());
+        self.factions.insert(faction_id, Faction {
+            id: faction_id,
+            name,
+            faction_type,
+            territory: Vec::new(),
+            member_count: 0,
+            relations,
+        });
+
+        self.found_site(site_name, site, Some(faction_id), center, 20);
+
+        let founder = self.spawn_figure(faction_id, center);
+        if let Some(founder) = founder {
+            chronicle.leaders.insert(faction_id, founder);
+        }
+
+        self.report_happening(Happening::Founding {
+            faction_id,
+            founder,
+            location: center,
+        });
+    }
+
+    /// Procura uma posição livre para um novo local
+    pub(crate) fn find_site_location(&mut self, near: Option<Position>) -> Option<Position> {
+        let margin = SITE_CLAIM_RADIUS;
+        for _ in 0..50 {
+            let candidate = match near {
+                Some(origin) => origin.moved(
+                    self.rng.range(-SITE_SPACING * 2, SITE_SPACING * 2 + 1),
+                    self.rng.range(-SITE_SPACING * 2, SITE_SPACING * 2 + 1),
+                ),
+                None => Position::new(
+                    self.rng.range(margin, self.width - margin),
+                    self.rng.range(margin, self.height - margin),
+                ),
+            };
+
+            if candidate.x < margin
+                || candidate.y < margin
+                || candidate.x >= self.width - margin
+                || candidate.y >= self.height - margin
+            {
+                continue;
+            }
+
+            let too_close = self.sites.values().any(|s| {
+                (s.center.x - candidate.x).abs().max((s.center.y - candidate.y).abs()) < SITE_SPACING
+            });
+            if too_close || !self.area_is_buildable(candidate, SITE_WALL_RADIUS) {
+                continue;
+            }
+
+            return Some(candidate);
+        }
+        None
+    }
+
+    fn area_is_buildable(&self, center: Position, radius: i32) -> bool {
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let pos = center.moved(dx, dy);
+                let walkable = self.terrain_layer.get(&pos).is_some_and(|t| t.walkable);
+                if !walkable || self.construction_layer.contains_key(&pos) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Conquista um local: ele é saqueado (ou queimado, por goblins) e
+    /// fica para ruir, o território passa ao conquistador e parte dos
+    /// moradores foge para outro local da fação
+    pub(crate) fn conquer_site(&mut self, site_id: u32, conqueror: u32) {
+        let Some(site) = self.sites.get(&site_id) else {
+            return;
+        };
+        let center = site.center;
+        let defeated = site.owner;
+        let refugees = site.population / 2;
+
+        let burns = self
+            .factions
+            .get(&conqueror)
+            .and_then(|f| self.raws.faction(f.faction_type.id()))
+            .is_some_and(|f| f.burns);
+        if burns {
+            self.burn_site(site_id, Some(conqueror));
+        } else {
+            let loot = self.sack_site(site_id);
+            self.store_in_home(conqueror, &loot);
+        }
+
+        let area: HashSet<Position> = self.positions_around(center, SITE_CLAIM_RADIUS).into_iter().collect();
+        let mut taken = Vec::new();
+        if let Some(faction) = defeated.and_then(|id| self.factions.get_mut(&id)) {
+            faction.territory.retain(|pos| {
+                let lost = area.contains(pos);
+                if lost {
+                    taken.push(*pos);
+                }
+                !lost
+            });
+        }
+        if let Some(faction) = self.factions.get_mut(&conqueror) {
+            faction.territory.extend(taken);
+        }
+
+        if let Some(defeated) = defeated {
+            if let Some(refuge) = self.home_site(defeated) {
+                if let Some(site) = self.sites.get_mut(&refuge) {
+                    site.population = (site.population + refugees).min(SITE_CAPACITY);
+                }
+            }
+            self.refresh_member_count(Some(defeated));
+        }
+    }
+
+    /// Coloca um covil sem dono habitado por uma criatura lendária
+    fn place_lair(&mut self) {
+        let Some(center) = self.find_site_location(None) else {
+            return;
+        };
+        let beast = format!(
+            "{} {}",
+            self.rng.pick(&BEAST_NAMES).copied().unwrap_or_default(),
+            self.rng.pick(&BEAST_TITLES).copied().unwrap_or_default()
+        );
+        let site_name = format!("The Den of {}", beast);
+        self.found_site(site_name, SiteKind::Lair, None, center, 1);
+
+        let Some(id) = self.spawn_entity(beast.clone(), center, EntityType::Animal) else {
+            return;
+        };
+        self.historical_figures.insert(id, HistoricalFigure {
+            id,
+            name: beast,
+            entity_type: EntityType::Animal,
+            faction_id: None,
+            birth_tick: self.current_tick,
+            death_tick: None,
+        });
+        self.ai_behaviors.insert(id, AIBehavior {
+            current_goal: AIGoal::Guard { pos: center },
+            memory: Vec::new(),
+            personality: Personality {
+                aggression: 0.9,
+                curiosity: 0.1,
+                sociability: 0.0,
+            },
+        });
+    }
+
+    fn spawn_figure(&mut self, faction_id: u32, near: Position) -> Option<u32> {
+        let faction_type = self.factions.get(&faction_id)?.faction_type.clone();
+        let pos = self.nearest_walkable(near, SITE_WALL_RADIUS * 2)?;
+        let name = self.person_name_for(faction_type.id());
+        let id = self.spawn_entity(name.clone(), pos, EntityType::NPC)?;
+
+        self.historical_figures.insert(id, HistoricalFigure {
+            id,
+            name,
+            entity_type: EntityType::NPC,
+            faction_id: Some(faction_id),
+            birth_tick: self.current_tick,
+            death_tick: None,
+        });
+        self.ai_behaviors.insert(id, AIBehavior {
+            current_goal: AIGoal::Guard { pos },
+            memory: Vec::new(),
+            personality: Personality {
+                aggression: self.rng.range(0, 101) as f32 / 100.0,
+                curiosity: self.rng.range(0, 101) as f32 / 100.0,
+                sociability: self.rng.range(0, 101) as f32 / 100.0,
+            },
+        });
+        Some(id)
+    }
+
+    /// Promove um herdeiro adulto a líder no assentamento principal da fação
+    fn replace_leader(&mut self, chronicle: &mut Chronicle, faction_id: u32) {
+        chronicle.leaders.remove(&faction_id);
+        let Some(home) = self.home_site(faction_id).and_then(|id| self.sites.get(&id)).map(|s| s.center) else {
+            return;
+        };
+        if let Some(heir) = self.spawn_figure(faction_id, home) {
+            let age = self.rng.range(18, 35) as u64 * TICKS_PER_YEAR;
+            if let Some(figure) = self.historical_figures.get_mut(&heir) {
+                figure.birth_tick = self.current_tick.saturating_sub(age);
+            }
+            chronicle.leaders.insert(faction_id, heir);
+            self.report_happening(Happening::Succession {
+                faction_id,
+                heir,
+                location: home,
+            });
+        }
+    }
+
+    /// Primeiro local habitado da fação (sua capital)
+    fn home_site(&self, faction_id: u32) -> Option<u32> {
+        self.sites_of(faction_id)
+            .into_iter()
+            .find(|s| s.is_inhabited())
+            .map(|s| s.id)
+    }
+
+    /// Guarda recursos no estoque da capital da fação
+    pub(crate) fn store_in_home(&mut self, faction_id: u32, goods: &Stockpile) {
+        let Some(home) = self.home_site(faction_id).and_then(|id| self.sites.get_mut(&id)) else {
+            return;
+        };
+        for resource in Resource::ALL {
+            home.stockpile.add(resource, goods.get(resource));
+        }
+    }
+
+    fn faction_alive(&self, faction_id: u32) -> bool {
+        self.home_site(faction_id).is_some()
+    }
+
+    fn age_leader(&mut self, chronicle: &mut Chronicle, faction_id: u32) {
+        let Some(leader) = chronicle.leaders.get(&faction_id).copied() else {
+            self.replace_leader(chronicle, faction_id);
+            return;
+        };
+        let Some(figure) = self.historical_figures.get(&leader) else {
+            return;
+        };
+
+        let age_years = (self.current_tick - figure.birth_tick) / TICKS_PER_YEAR;
+        if age_years >= ELDER_AGE_YEARS && self.rng.chance(0.08) {
+            self.kill_entity(leader, None);
+            self.replace_leader(chronicle, faction_id);
+        }
+    }
+
+    fn grow_faction(&mut self, faction_id: u32) {
+        let site_ids: Vec<u32> = self.sites_of(faction_id).iter().map(|s| s.id).collect();
+        for id in site_ids {
+            if let Some(site) = self.sites.get_mut(&id) {
+                // Só cresce quem tem comida guardada
+                if site.is_inhabited() && site.population < SITE_CAPACITY && !site.is_short_of(Resource::Food) {
+                    site.population = (site.population + (site.population / 20).max(1)).min(SITE_CAPACITY);
+                }
+            }
+        }
+        self.refresh_member_count(Some(faction_id));
+    }
+
+    /// Locais esvaziados pela guerra podem ser abandonados pelos moradores
+    fn consider_abandonment(&mut self, faction_id: u32) {
+        let sites: Vec<(u32, usize, Position)> = self
+            .sites_of(faction_id)
+            .iter()
+            .filter(|s| s.is_inhabited())
+            .map(|s| (s.id, s.population, s.center))
+            .collect();
+        if sites.len() < 2 {
+            return;
+        }
+
+        for (id, population, center) in sites.into_iter().skip(1) {
+            if population >= ABANDON_POPULATION || !self.rng.chance(0.1) {
+                continue;
+            }
+            let carried = self.abandon_site(id);
+            self.store_in_home(faction_id, &carried);
+            if let Some(home) = self.home_site(faction_id).and_then(|home| self.sites.get_mut(&home)) {
+                home.population = (home.population + population).min(SITE_CAPACITY);
+            }
+            self.refresh_member_count(Some(faction_id));
+            self.report_happening(Happening::Abandonment {
+                faction_id,
+                site_id: id,
+                location: center,
+            });
+        }
+    }
+
+    fn try_migration(&mut self, chronicle: &mut Chronicle, faction_id: u32) {
+        let owned: Vec<(u32, usize, Position)> = self
+            .sites_of(faction_id)
+            .iter()
+            .filter(|s| s.is_inhabited())
+            .map(|s| (s.id, s.population, s.center))
+            .collect();
+        if owned.len() >= 4 || !self.rng.chance(0.04) {
+            return;
+        }
+
+        let crowded: Vec<(u32, usize, Position)> = owned.into_iter().filter(|(_, pop, _)| *pop >= 40).collect();
+        let Some((origin, _, _)) = self.rng.pick(&crowded).copied() else {
+            return;
+        };
+        let Some(colony) = self.found_colony(faction_id, origin, 20) else {
+            return;
+        };
+        let location = self.sites[&colony].center;
+
+        let leader = chronicle.leaders.get(&faction_id).copied();
+        self.report_happening(Happening::Migration {
+            faction_id,
+            leader,
+            location,
+        });
+    }
+
+    /// Parte dos moradores de um local funda uma colônia nas redondezas,
+    /// levando sua parte do estoque
+    pub(crate) fn found_colony(&mut self, faction_id: u32, origin: u32, settlers: usize) -> Option<u32> {
+        let origin_site = self.sites.get(&origin)?;
+        if settlers == 0 || settlers >= origin_site.population {
+            return None;
+        }
+        let origin_pos = origin_site.center;
+        let center = self.find_site_location(Some(origin_pos))?;
+        let faction_type = self.factions.get(&faction_id)?.faction_type.clone();
+
+        let site = self.sites.get_mut(&origin)?;
+        let population = site.population;
+        let share = |stock: u32| (stock as usize * settlers / population) as u32;
+        let mut provisions = Stockpile::default();
+        for resource in Resource::ALL {
+            let amount = share(site.stockpile.get(resource));
+            provisions.add(resource, site.stockpile.take(resource, amount));
+        }
+        site.population -= settlers;
+
+        let site_name = self.site_name_for(faction_type.id());
+        let kind = self.site_kind_for(faction_type.id());
+        let colony = self.found_site(site_name, kind, Some(faction_id), center, settlers);
+        if let Some(site) = self.sites.get_mut(&colony) {
+            site.stockpile = provisions;
+        }
+        self.build_road(origin, colony);
+        Some(colony)
+    }
+
+    fn drift_relations(&mut self, faction_id: u32) {
+        let Some(faction) = self.factions.get(&faction_id) else {
+            return;
+        };
+        let hostile = self.raws.faction(faction.faction_type.id()).is_some_and(|f| f.hostile);
+        let mut others: Vec<(u32, Relation)> = faction.relations.iter().map(|(id, r)| (*id, *r)).collect();
+        others.sort_unstable_by_key(|(id, _)| *id);
+
+        for (other, relation) in others {
+            // Cada par é avaliado uma vez, pela fação de menor ID
+            if other < faction_id {
+                continue;
+            }
+            let next = match relation {
+                Relation::Neutral if self.rng.chance(if hostile { 0.15 } else { 0.03 }) => Relation::Hostile,
+                Relation::Neutral if self.rng.chance(0.03) => Relation::Friendly,
+                Relation::Friendly if self.rng.chance(0.03) => Relation::Allied,
+                Relation::Hostile if !hostile && self.rng.chance(0.02) => Relation::Neutral,
+                _ => relation,
+            };
+            if next == relation {
+                continue;
+            }
+
+            self.set_relation(faction_id, other, next);
+            if next == Relation::Allied {
+                let location = self.faction_seat(faction_id);
+                self.report_happening(Happening::Alliance {
+                    faction_a: faction_id,
+                    faction_b: other,
+                    location,
+                });
+            }
+        }
+    }
+
+    pub(crate) fn set_relation(&mut self, a: u32, b: u32, relation: Relation) {
+        if let Some(faction) = self.factions.get_mut(&a) {
+            faction.relations.insert(b, relation);
+        }
+        if let Some(faction) = self.factions.get_mut(&b) {
+            faction.relations.insert(a, relation);
+        }
+    }
+
+    /// Posição representativa de uma fação (centro da sua capital)
+    fn faction_seat(&self, faction_id: u32) -> Position {
+        self.home_site(faction_id)
+            .and_then(|id| self.sites.get(&id))
+            .map(|s| s.center)
+            .or_else(|| self.factions.get(&faction_id).and_then(|f| f.territory.first().copied()))
+            .unwrap_or(Position::new(0, 0))
+    }
+
+    fn wage_wars(&mut self, chronicle: &mut Chronicle, faction_id: u32) {
+        let Some(faction) = self.factions.get(&faction_id) else {
+            return;
+        };
+        let mut enemies: Vec<(u32, Relation)> = faction
+            .relations
+            .iter()
+            .filter(|(_, r)| matches!(r, Relation::Hostile | Relation::War))
+            .map(|(id, r)| (*id, *r))
+            .collect();
+        enemies.sort_unstable_by_key(|(id, _)| *id);
+
+        // A fome torna a guerra mais tentadora
+        let hungry = self.sites_of(faction_id).iter().any(|s| s.is_short_of(Resource::Food));
+        let war_chance = if hungry { 0.25 } else { 0.1 };
+
+        for (enemy, relation) in enemies {
+            if !self.faction_alive(enemy) {
+                continue;
+            }
+            match relation {
+                Relation::Hostile if self.rng.chance(war_chance) => {
+                    self.set_relation(faction_id, enemy, Relation::War);
+                    let location = self.faction_seat(enemy);
+                    self.report_happening(Happening::WarDeclared {
+                        aggressor: faction_id,
+                        defender: enemy,
+                        location,
+                    });
+                }
+                Relation::War => self.fight_battle(chronicle, faction_id, enemy),
+                _ => {}
+            }
+        }
+    }
+
+    fn fight_battle(&mut self, chronicle: &mut Chronicle, attacker: u32, defender: u32) {
+        if !self.rng.chance(0.3) {
+            if self.rng.chance(0.1) {
+                self.set_relation(attacker, defender, Relation::Hostile);
+                let location = self.faction_seat(defender);
+                self.report_happening(Happening::Peace {
+                    faction_a: attacker,
+                    faction_b: defender,
+                    location,
+                });
+            }
+            return;
+        }
+
+        let targets: Vec<(u32, Position)> = self
+            .sites_of(defender)
+            .iter()
+            .filter(|s| s.is_inhabited())
+            .map(|s| (s.id, s.center))
+            .collect();
+        let Some((target, location)) = self.rng.pick(&targets).copied() else {
+            return;
+        };
+
+        let champion = chronicle.leaders.get(&attacker).copied();
+        let guardian = chronicle.leaders.get(&defender).copied();
+        if let (Some(champion), Some(guardian)) = (champion, guardian) {
+            self.report_happening(Happening::Fight {
+                attacker: champion,
+                defender: guardian,
+                location,
+            });
+            if self.rng.chance(0.25) {
+                let (victim, killer, side) = if self.rng.chance(0.5) {
+                    (guardian, champion, defender)
+                } else {
+                    (champion, guardian, attacker)
+                };
+                self.kill_entity(victim, Some(killer));
+                self.replace_leader(chronicle, side);
+            }
+        }
+
+        if self.rng.chance(0.2) {
+            self.report_happening(Happening::Conquest {
+                conqueror_faction: attacker,
+                defeated_faction: defender,
+                location,
+            });
+            self.conquer_site(target, attacker);
+
+            if !self.faction_alive(defender) {
+                if let Some(leader) = chronicle.leaders.remove(&defender) {
+                    self.kill_entity(leader, champion);
+                }
+                self.set_relation(attacker, defender, Relation::Hostile);
+            }
+        }
+    }
+
+    /// Junta uma palavra sorteada de cada lista dos raws
+    fn compose_name(&mut self, words: &[Vec<String>; 2]) -> (String, String) {
+        (
+            self.rng.pick(&words[0]).cloned().unwrap_or_default(),
+            self.rng.pick(&words[1]).cloned().unwrap_or_default(),
+        )
+    }
+
+    fn site_kind_for(&self, faction_type: &str) -> SiteKind {
+        self.raws.faction(faction_type).map_or(SiteKind::Town, |f| f.site)
+    }
+
+    fn person_name_for(&mut self, faction_type: &str) -> String {
+        let raws = self.raws.clone();
+        let syllables = raws.faction(faction_type).map(|f| f.person_names.clone()).unwrap_or_default();
+        let (first, second) = self.compose_name(&syllables);
+        let (family, family_end) = self.compose_name(&syllables);
+        format!("{}{} {}{}", first, second, family, family_end.to_lowercase())
+    }
+
+    fn site_name_for(&mut self, faction_type: &str) -> String {
+        let raws = self.raws.clone();
+        let words = raws.faction(faction_type).map(|f| f.site_names.clone()).unwrap_or_default();
+        let (prefix, suffix) = self.compose_name(&words);
+        format!("{}{}", prefix, suffix)
+    }
+
+    fn faction_name_for(&mut self, faction_type: &str) -> String {
+        let raws = self.raws.clone();
+        let words = raws.faction(faction_type).map(|f| f.faction_names.clone()).unwrap_or_default();
+        let (adjective, noun) = self.compose_name(&words);
+        format!("The {} {}", adjective, noun)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generated_world(seed: u64) -> World {
+        let mut world = World::with_seed(80, 80, seed);
+        world.generate_history(&HistoryGenConfig::default());
+        world
+    }
+
+    #[test]
+    fn test_history_fills_factions_and_events() {
+        let world = generated_world(1);
+        let config = HistoryGenConfig::default();
+
+        assert_eq!(world.get_factions().len(), config.civilizations);
+        assert!(world
+            .get_factions()
+            .iter()
+            .filter(|f| f.member_count > 0)
+            .all(|f| !f.territory.is_empty()));
+        assert_eq!(world.get_current_tick(), config.years as u64 * TICKS_PER_YEAR);
+
+        let events = world.get_historical_events(usize::MAX);
+        let foundings = events.iter().filter(|e| e.event_type == EventType::FactionFounded).count();
+        assert_eq!(foundings, config.civilizations);
+        assert!(events.len() > foundings);
+    }
+
+    #[test]
+    fn test_history_leaves_figures_on_map() {
+        let world = generated_world(2);
+
+        let living: Vec<_> = world
+            .get_historical_figures()
+            .into_iter()
+            .filter(|f| f.death_tick.is_none())
+            .collect();
+        assert!(!living.is_empty());
+        for figure in living {
+            let entity = world.get_entity(figure.id).unwrap();
+            assert_eq!(entity.name, figure.name);
+        }
+    }
+
+    #[test]
+    fn test_history_is_deterministic() {
+        let a = generated_world(3);
+        let b = generated_world(3);
+
+        let describe = |w: &World| -> Vec<String> {
+            w.get_historical_events(usize::MAX)
+                .into_iter()
+                .map(|e| e.description)
+                .collect()
+        };
+        assert_eq!(describe(&a), describe(&b));
+    }
+
+    #[test]
+    fn test_conquest_leaves_ruins() {
+        let conquered_long_ago = |w: &World| {
+            w.get_historical_events(usize::MAX).into_iter().find(|e| {
+                e.event_type == EventType::TerritoryConquered
+                    && e.tick + 10 * TICKS_PER_YEAR <= w.get_current_tick()
+            })
+        };
+        let world = (0..20)
+            .map(generated_world)
+            .find(|w| conquered_long_ago(w).is_some())
+            .expect("nenhuma conquista antiga em 20 sementes");
+
+        let conquest = conquered_long_ago(&world).unwrap();
+        let ruin = world.get_construction(conquest.location.moved(SITE_WALL_RADIUS, 0));
+        assert_eq!(ruin.map(|c| c.kind), Some(ConstructionType::Ruin));
+
+        let site = world.site_at(conquest.location).unwrap();
+        assert_eq!(site.state, crate::SiteState::Ruined);
+        assert!(site.history.contains(&conquest.id));
+        assert!(world
+            .site_history(site.id)
+            .iter()
+            .any(|e| e.event_type == EventType::SiteRuined));
+    }
+
+    #[test]
+    fn test_history_creates_sites_and_lairs() {
+        let world = generated_world(4);
+        let config = HistoryGenConfig::default();
+
+        let lairs = world.get_sites().iter().filter(|s| s.kind == SiteKind::Lair).count();
+        assert_eq!(lairs, config.lairs);
+        for faction in world.get_factions() {
+            let population: usize = world.sites_of(faction.id).iter().map(|s| s.population).sum();
+            assert_eq!(faction.member_count, population);
+        }
+
+        // Colônias ficam ligadas por estrada a quem as fundou
+        let roads = world.query_history(&crate::HistoryQuery {
+            event_type: Some(EventType::RoadBuilt),
+            ..Default::default()
+        });
+        assert!(roads.total > 0);
+        assert!(world.get_sites().iter().any(|s| !s.roads.is_empty()));
+    }
+}
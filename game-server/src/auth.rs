@@ -131,6 +131,12 @@ impl AuthState {
         self.users.get(&session.user_id)
     }
 
+    /// Se o token é de uma sessão válida de administrador
+    pub fn is_admin(&mut self, token: uuid::Uuid) -> bool {
+        self.validate_session(token).is_some_and(|user| user.role == UserRole::Admin)
+    }
+
+    #[allow(dead_code)]
     pub fn logout(&mut self, token: uuid::Uuid) {
         self.sessions.remove(&token);
     }
@@ -1,27 +1,51 @@
 mod auth;
+mod sync;
 
 use warp::Filter;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
-use tokio::sync::broadcast;
+use std::time::Duration;
+use tokio::sync::watch;
 use common::*;
-use world::World;
+use world::Raws;
 use auth::{AuthState, SharedAuthState};
-use ipc::{EventHub, GameEvent, SharedEventHub};
+use sync::{SyncMessage, ViewportSync};
+use ipc::{
+    ActReply, Acted, AdminCommand, EventHub, GameEvent, HistoryParams, RpcError, SharedEventHub, ViewportView,
+    WorldClient, WORLD_ADDR,
+};
 
-pub type SharedGameState = Arc<Mutex<GameState>>;
+pub type SharedGameState = Arc<GameState>;
 
+/// Intervalo entre tentativas de falar com o backend
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+/// Raio da visão de cada jogador, em tiles
+const VIEW_RANGE: i32 = 15;
+/// Origens do painel de administração, as únicas que podem mudar o mundo
+/// pela API
+const ADMIN_PANEL_ORIGINS: [&str; 2] = ["http://127.0.0.1:3030", "http://localhost:3030"];
+
+/// Gateway dos jogadores. O mundo vive no backend; daqui saem as ações
+/// dos jogadores e chegam as camadas para desenhar.
 pub struct GameState {
-    pub world: World,
-    pub players: HashMap<uuid::Uuid, PlayerSession>,
-    pub tick_update_tx: broadcast::Sender<GameUpdate>,
+    /// Conexão com o backend, dividida por todas as sessões
+    pub backend: WorldClient,
+    /// Raws do mundo, recebidos do backend, para desenhar as camadas
+    pub raws: Raws,
+    pub players: Mutex<HashMap<uuid::Uuid, PlayerSession>>,
+    /// Último tick do mundo anunciado pelo backend
+    pub world_tick: watch::Sender<u64>,
 }
 
 pub struct PlayerSession {
     pub user_id: uuid::Uuid,
     pub entity_id: u32,
     pub name: String,
+    /// Visão mais recente do jogador, empurrada a cada tick. Só a última
+    /// fica guardada: um cliente lento pula atualizações em vez de
+    /// atrasar o tick.
+    pub updates: watch::Sender<Option<GameUpdate>>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -30,7 +54,7 @@ pub struct GameUpdate {
     pub viewport: ViewportData,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ViewportData {
     pub tiles: Vec<TileData>,
     pub entities: Vec<EntityData>,
@@ -39,7 +63,7 @@ pub struct ViewportData {
     pub height: i32,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct TileData {
     pub x: i32,
     pub y: i32,
@@ -48,8 +72,9 @@ pub struct TileData {
     pub bg_color: String,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct EntityData {
+    pub id: u32,
     pub x: i32,
     pub y: i32,
     pub glyph: char,
@@ -58,98 +83,364 @@ pub struct EntityData {
 }
 
 impl GameState {
-    pub fn new() -> Self {
-        let (tick_tx, _) = broadcast::channel(100);
+    /// Conecta ao backend e recebe dele os raws do mundo
+    pub async fn connect(addr: &str) -> Result<Self, String> {
+        let backend = WorldClient::connect(addr).await.map_err(|e| e.to_string())?;
+        let raws = backend.hello().await.map_err(|e| e.to_string())?;
+        Ok(Self::with_backend(backend, raws))
+    }
+
+    pub fn with_backend(backend: WorldClient, raws: Raws) -> Self {
         Self {
-            world: World::new(50, 50),
-            players: HashMap::new(),
-            tick_update_tx: tick_tx,
+            backend,
+            raws,
+            players: Mutex::new(HashMap::new()),
+            world_tick: watch::Sender::new(0),
         }
     }
 
-    pub fn spawn_player(&mut self, user_id: uuid::Uuid, name: String) -> Option<u32> {
-        if let Some(entity_id) = self.world.spawn_entity(
-            name.clone(),
-            Position::new(10, 10),
-            EntityType::Player,
-        ) {
-            self.players.insert(user_id, PlayerSession {
-                user_id,
-                entity_id,
-                name,
-            });
-            Some(entity_id)
-        } else {
-            None
+    /// Cria o personagem do jogador; o receptor traz a visão dele a cada tick
+    pub async fn spawn_player(
+        &self,
+        user_id: uuid::Uuid,
+        name: String,
+    ) -> Option<(Entity, watch::Receiver<Option<GameUpdate>>)> {
+        let entity = match self.backend.spawn_player(name.clone()).await {
+            Ok(entity) => entity?,
+            Err(e) => {
+                eprintln!("⚠️  {}", e);
+                return None;
+            }
+        };
+        let (updates, receiver) = watch::channel(None);
+        self.players.lock().unwrap().insert(user_id, PlayerSession {
+            user_id,
+            entity_id: entity.id,
+            name,
+            updates,
+        });
+        Some((entity, receiver))
+    }
+
+    /// O jogador saiu: o personagem deixa o mundo
+    pub async fn leave(&self, user_id: uuid::Uuid) {
+        let session = self.players.lock().unwrap().remove(&user_id);
+        if let Some(session) = session {
+            if let Err(e) = self.backend.despawn_player(session.entity_id).await {
+                eprintln!("⚠️  {}", e);
+            }
         }
     }
 
-    pub fn get_viewport(&self, entity_id: u32, view_range: i32) -> Option<ViewportData> {
-        let entity = self.world.get_entity(entity_id)?;
-        let center = entity.pos;
+    pub async fn get_viewport(&self, entity_id: u32, view_range: i32) -> Option<GameUpdate> {
+        match self.backend.viewport(entity_id, view_range).await {
+            Ok(view) => view.map(|view| self.render_viewport(&view)),
+            Err(e) => {
+                eprintln!("⚠️  {}", e);
+                None
+            }
+        }
+    }
 
-        let mut tiles = Vec::new();
-        let mut entities_data = Vec::new();
+    /// Busca de uma vez a visão de todos os jogadores conectados e a põe
+    /// no canal de cada sessão. Nada aqui espera pelos clientes.
+    pub async fn push_viewports(&self) {
+        let entity_ids: Vec<u32> = self.players.lock().unwrap().values().map(|s| s.entity_id).collect();
+        if entity_ids.is_empty() {
+            return;
+        }
+        let views = match self.backend.viewports(entity_ids, VIEW_RANGE).await {
+            Ok(views) => views,
+            Err(e) => {
+                eprintln!("⚠️  {}", e);
+                return;
+            }
+        };
+        let updates: HashMap<u32, GameUpdate> =
+            views.iter().map(|(id, view)| (*id, self.render_viewport(view))).collect();
+        for session in self.players.lock().unwrap().values() {
+            if let Some(update) = updates.get(&session.entity_id) {
+                session.updates.send_replace(Some(update.clone()));
+            }
+        }
+    }
 
-        for y in (center.y - view_range)..=(center.y + view_range) {
-            for x in (center.x - view_range)..=(center.x + view_range) {
-                let pos = Position::new(x, y);
-                if let Some(tile) = self.world.get_tile(pos) {
-                    let (glyph, fg, bg) = tile_to_glyph(&tile);
-                    tiles.push(TileData { x, y, glyph, fg_color: fg, bg_color: bg });
+    /// Desenha as camadas vistas por uma entidade
+    pub fn render_viewport(&self, view: &ViewportView) -> GameUpdate {
+        let tiles = view.tiles.iter().map(|tile| self.render_tile(tile)).collect();
+        let entities = view
+            .entities
+            .iter()
+            .map(|entity| {
+                let (glyph, color) = entity_to_glyph(&self.raws, entity);
+                EntityData {
+                    id: entity.id,
+                    x: entity.pos.x,
+                    y: entity.pos.y,
+                    glyph,
+                    color,
+                    name: entity.name.clone(),
                 }
-            }
+            })
+            .collect();
+        GameUpdate {
+            tick: view.tick,
+            viewport: ViewportData {
+                tiles,
+                entities,
+                player_pos: view.center,
+                width: view.radius * 2 + 1,
+                height: view.radius * 2 + 1,
+            },
         }
+    }
 
-        let visible_entities = self.world.get_entities_in_region(center, view_range);
-        for entity in visible_entities {
-            let (glyph, color) = entity_to_glyph(entity);
-            entities_data.push(EntityData {
-                x: entity.pos.x,
-                y: entity.pos.y,
-                glyph,
-                color,
-                name: entity.name.clone(),
-            });
+    fn render_tile(&self, view: &ipc::TileView) -> TileData {
+        let (glyph, fg, bg) = layered_glyph(
+            &self.raws,
+            &view.tile,
+            view.construction.as_ref(),
+            view.surface.as_deref(),
+            &view.items,
+        );
+        TileData {
+            x: view.pos.x,
+            y: view.pos.y,
+            glyph,
+            fg_color: fg,
+            bg_color: bg,
         }
+    }
+}
 
-        Some(ViewportData {
-            tiles,
-            entities: entities_data,
-            player_pos: center,
-            width: view_range * 2 + 1,
-            height: view_range * 2 + 1,
-        })
+/// Parâmetros de consulta de `GET /api/admin/snapshots/{tick}/diff`
+#[derive(Deserialize, Debug, Default)]
+pub struct DiffParams {
+    /// Snapshot final; sem ele, compara com o mundo atual
+    pub to: Option<u64>,
+}
+
+/// Resposta HTTP a um pedido ao backend: o JSON pedido, 404 quando o
+/// backend recusa e 502 quando ele não responde
+fn world_reply<T: Serialize>(response: Result<T, RpcError>) -> warp::reply::WithStatus<warp::reply::Json> {
+    use warp::http::StatusCode;
+    let (message, status) = match response {
+        Ok(value) => return warp::reply::with_status(warp::reply::json(&value), StatusCode::OK),
+        Err(RpcError::Refused(message)) => (message, StatusCode::NOT_FOUND),
+        Err(RpcError::Transport(message)) => (message, StatusCode::BAD_GATEWAY),
+    };
+    warp::reply::with_status(
+        warp::reply::json(&ServerMessage::ActionResult { success: false, message }),
+        status,
+    )
+}
+
+async fn admin_reply(
+    command: AdminCommand,
+    game: SharedGameState,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, std::convert::Infallible> {
+    let backend = &game.backend;
+    Ok(match command {
+        AdminCommand::Sites => world_reply(backend.sites().await),
+        AdminCommand::Market { site_id } => world_reply(backend.market(site_id).await),
+        AdminCommand::History(params) => world_reply(backend.history(params).await),
+        AdminCommand::Replay => world_reply(backend.replay().await),
+        AdminCommand::Systems => world_reply(backend.systems().await),
+        AdminCommand::Snapshots => world_reply(backend.snapshots().await),
+        AdminCommand::CaptureSnapshot => world_reply(backend.capture_snapshot().await),
+        AdminCommand::SnapshotDiff { from, to } => world_reply(backend.snapshot_diff(from, to).await),
+        AdminCommand::Rollback { tick } => {
+            let reply = match backend.rollback(tick).await {
+                Ok(()) => ServerMessage::ActionResult {
+                    success: true,
+                    message: format!("World rolled back to tick {}", tick),
+                },
+                Err(RpcError::Refused(message)) => ServerMessage::ActionResult { success: false, message },
+                Err(e) => return Ok(world_reply::<()>(Err(e))),
+            };
+            world_reply(Ok(reply))
+        }
+    })
+}
+
+/// Comandos que mudam o mundo: só para administradores logados, com o token
+/// da sessão em `Authorization: Bearer <token>`
+async fn admin_action(
+    command: AdminCommand,
+    authorization: Option<String>,
+    auth: SharedAuthState,
+    game: SharedGameState,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, std::convert::Infallible> {
+    let token = authorization
+        .as_deref()
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .and_then(|token| uuid::Uuid::parse_str(token.trim()).ok());
+    if !token.is_some_and(|token| auth.lock().unwrap().is_admin(token)) {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ServerMessage::ActionResult {
+                success: false,
+                message: "Requer login de administrador".to_string(),
+            }),
+            warp::http::StatusCode::UNAUTHORIZED,
+        ));
+    }
+    admin_reply(command, game).await
+}
+
+/// Captura e rollback de snapshots. Além do login de administrador, só
+/// aceitam pedidos de navegador vindos do painel de administração.
+fn admin_actions(
+    auth: SharedAuthState,
+    game: SharedGameState,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let auth_filter = warp::any().map(move || auth.clone());
+    let game_filter = warp::any().map(move || game.clone());
+
+    // POST /api/admin/snapshots - Captura o estado atual do mundo
+    let capture_snapshot = warp::path!("api" / "admin" / "snapshots")
+        .and(warp::post())
+        .map(|| AdminCommand::CaptureSnapshot);
+
+    // POST /api/admin/snapshots/{tick}/rollback - Volta o mundo ao snapshot
+    let rollback = warp::path!("api" / "admin" / "snapshots" / u64 / "rollback")
+        .and(warp::post())
+        .map(|tick: u64| AdminCommand::Rollback { tick });
+
+    let cors = warp::cors()
+        .allow_origins(ADMIN_PANEL_ORIGINS)
+        .allow_methods(vec!["POST", "OPTIONS"])
+        .allow_headers(vec!["Content-Type", "Authorization"]);
+
+    capture_snapshot
+        .or(rollback)
+        .unify()
+        .and(warp::header::optional::<String>("authorization"))
+        .and(auth_filter)
+        .and(game_filter)
+        .and_then(admin_action)
+        .with(cors)
+}
+
+/// Acompanha o tick do mundo pelos eventos do backend, assinando de novo
+/// quando a conexão cai. Os eventos do mundo chegam ao painel de admin
+/// direto do backend; aqui só interessa o tick.
+async fn follow_world_ticks(game: SharedGameState) {
+    loop {
+        match game.backend.subscribe().await {
+            Ok(mut events) => {
+                while let Some(event) = events.next().await {
+                    if let GameEvent::WorldTick { tick, .. } = event {
+                        game.world_tick.send_replace(tick);
+                    }
+                }
+                eprintln!("⚠️  Fluxo de eventos do backend interrompido");
+            }
+            Err(e) => eprintln!("⚠️  {}", e),
+        }
+        tokio::time::sleep(RETRY_DELAY).await;
+    }
+}
+
+/// Atualiza os jogadores a cada tick do mundo. Ticks que chegam enquanto
+/// uma atualização está em curso viram um só.
+async fn push_tick_updates(game: SharedGameState) {
+    let mut ticks = game.world_tick.subscribe();
+    while ticks.changed().await.is_ok() {
+        game.push_viewports().await;
+    }
+}
+
+/// Próxima visão empurrada para a sessão; sem sessão, nunca chega
+async fn next_update(updates: &mut Option<watch::Receiver<Option<GameUpdate>>>) -> Option<GameUpdate> {
+    let Some(updates) = updates else {
+        return std::future::pending().await;
+    };
+    match updates.changed().await {
+        Ok(()) => updates.borrow_and_update().clone(),
+        Err(_) => std::future::pending().await,
     }
 }
 
 const DEBUG_MODE: bool = true; // ← Modo debug
 
-fn tile_to_glyph(tile: &Tile) -> (char, String, String) {
-    match tile.terrain {
-        TerrainType::Grass => ('░', "#4a4".to_string(), "#232".to_string()),
-        TerrainType::Water => ('≈', "#24a".to_string(), "#012".to_string()),
-        TerrainType::Stone => ('█', "#888".to_string(), "#444".to_string()),
-        TerrainType::Sand => ('·', "#dc6".to_string(), "#a94".to_string()),
+fn tile_to_glyph(raws: &Raws, tile: &Tile) -> (char, String, String) {
+    // Terreno que os raws do backend não definem: aparece, mas destacado
+    let Some(def) = raws.terrain(tile.terrain.id()) else {
+        return ('?', "#f0f".to_string(), "#000".to_string());
+    };
+    (def.glyph.ch, def.glyph.fg.clone(), def.glyph.bg.clone().unwrap_or_else(|| "#000".to_string()))
+}
+
+fn construction_to_glyph(construction: &Construction) -> (char, String) {
+    match construction.kind {
+        ConstructionType::Wall => ('#', "#bbb".to_string()),
+        ConstructionType::Door => ('+', "#a73".to_string()),
+        ConstructionType::Floor => ('.', "#999".to_string()),
+        ConstructionType::Ruin => ('%', "#766".to_string()),
     }
 }
 
-fn entity_to_glyph(entity: &Entity) -> (char, String) {
-    match entity.entity_type {
-        EntityType::Player => ('@', "#ff0".to_string()),
-        EntityType::NPC => ('H', "#0af".to_string()),
-        EntityType::Animal => ('d', "#fa0".to_string()),
+fn item_to_glyph(raws: &Raws, item: &Item) -> (char, String) {
+    let glyph = &raws.item_of(item).glyph;
+    (glyph.ch, glyph.fg.clone())
+}
+
+/// Combina as camadas: itens cobrem superfícies, que cobrem construções,
+/// que cobrem o terreno
+fn layered_glyph(
+    raws: &Raws,
+    tile: &Tile,
+    construction: Option<&Construction>,
+    surface: Option<&str>,
+    items: &[Item],
+) -> (char, String, String) {
+    let (glyph, fg, bg) = tile_to_glyph(raws, tile);
+    if let Some(item) = items.last() {
+        let (glyph, fg) = item_to_glyph(raws, item);
+        return (glyph, fg, bg);
     }
+    if let Some(surface) = surface.and_then(|id| raws.surface(id)) {
+        return (surface.glyph.ch, surface.glyph.fg.clone(), bg);
+    }
+    match construction {
+        Some(construction) => {
+            let (glyph, fg) = construction_to_glyph(construction);
+            (glyph, fg, bg)
+        }
+        None => (glyph, fg, bg),
+    }
+}
+
+fn entity_to_glyph(raws: &Raws, entity: &Entity) -> (char, String) {
+    let glyph = &raws.creature_of(entity).glyph;
+    (glyph.ch, glyph.fg.clone())
 }
 
 #[tokio::main]
 async fn main() {
     println!("🎮 MM Game Server Starting...");
 
+    // O mundo vive no backend; sem ele não há o que servir
+    let game_state = loop {
+        match GameState::connect(WORLD_ADDR).await {
+            Ok(game) => break game,
+            Err(e) => {
+                eprintln!("⚠️  {}; nova tentativa em {}s", e, RETRY_DELAY.as_secs());
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+    };
+    println!("🔗 Conectado ao backend em {}", WORLD_ADDR);
+    for m in &game_state.raws.mods {
+        println!("🧩 Mod {}", m);
+    }
     let auth_state: SharedAuthState = Arc::new(Mutex::new(AuthState::new()));
-    let game_state: SharedGameState = Arc::new(Mutex::new(GameState::new()));
     let event_hub: SharedEventHub = Arc::new(Mutex::new(EventHub::new()));
+    let game_state: SharedGameState = Arc::new(game_state);
+    tokio::spawn(follow_world_ticks(game_state.clone()));
+    tokio::spawn(push_tick_updates(game_state.clone()));
 
+    let admin_routes = admin_actions(auth_state.clone(), game_state.clone());
     let auth_filter = warp::any().map(move || auth_state.clone());
     let game_filter = warp::any().map(move || game_state.clone());
     let event_filter = warp::any().map(move || event_hub.clone());
@@ -206,7 +497,7 @@ async fn main() {
             ws.on_upgrade(move |socket| handle_game_websocket(socket, auth, game, events))
         });
 
-    // Endpoint para admin-server se conectar
+    // Endpoint para admin-server se conectar: eventos das sessões de jogo
     let event_stream = warp::path!("api" / "events" / "stream")
         .and(warp::ws())
         .and(event_filter.clone())
@@ -223,98 +514,173 @@ async fn main() {
     let api_world_map = warp::path!("api" / "world" / "map")
         .and(warp::get())
         .and(game_filter.clone())
-        .map(|game: SharedGameState| {
-            let game = game.lock().unwrap();
-            let (width, height) = game.world.dimensions();
-            
-            let mut tiles = Vec::new();
-            for y in 0..height {
-                for x in 0..width {
-                    let pos = Position::new(x, y);
-                    if let Some(tile) = game.world.get_tile(pos) {
-                        let (glyph, fg, bg) = tile_to_glyph(&tile);
-                        tiles.push(TileData {
-                            x, y, glyph,
-                            fg_color: fg,
-                            bg_color: bg,
-                        });
-                    }
-                }
-            }
-            
-            warp::reply::json(&serde_json::json!({
-                "width": width,
-                "height": height,
-                "tiles": tiles
-            }))
+        .and_then(|game: SharedGameState| async move {
+            let response = game.backend.map().await.map(|map| {
+                let tiles: Vec<TileData> = map.tiles.iter().map(|tile| game.render_tile(tile)).collect();
+                serde_json::json!({
+                    "width": map.width,
+                    "height": map.height,
+                    "tiles": tiles
+                })
+            });
+            Ok::<_, std::convert::Infallible>(world_reply(response))
         });
     
     // GET /api/players - Retorna lista de jogadores online
     let api_players = warp::path!("api" / "players")
         .and(warp::get())
         .and(game_filter.clone())
-        .map(|game: SharedGameState| {
-            let game = game.lock().unwrap();
-            
-            let players: Vec<_> = game.players.iter().map(|(user_id, session)| {
-                let entity = game.world.get_entity(session.entity_id);
-                serde_json::json!({
-                    "id": user_id.to_string(),
-                    "name": session.name,
-                    "entity_id": session.entity_id,
-                    "position": entity.map(|e| serde_json::json!({
-                        "x": e.pos.x,
-                        "y": e.pos.y
-                    }))
-                })
-            }).collect();
-            
-            warp::reply::json(&players)
+        .and_then(|game: SharedGameState| async move {
+            let response = game.backend.entities().await.map(|entities| {
+                let players: Vec<_> = game.players.lock().unwrap().iter().map(|(user_id, session)| {
+                    let entity = entities.iter().find(|e| e.id == session.entity_id);
+                    serde_json::json!({
+                        "id": user_id.to_string(),
+                        "name": session.name,
+                        "entity_id": session.entity_id,
+                        "position": entity.map(|e| serde_json::json!({
+                            "x": e.pos.x,
+                            "y": e.pos.y
+                        }))
+                    })
+                }).collect();
+                serde_json::json!(players)
+            });
+            Ok::<_, std::convert::Infallible>(world_reply(response))
         });
     
     // GET /api/entities - Retorna todas as entidades
     let api_entities = warp::path!("api" / "entities")
         .and(warp::get())
         .and(game_filter.clone())
-        .map(|game: SharedGameState| {
-            let game = game.lock().unwrap();
-            
-            let entities: Vec<_> = game.world.get_all_entities()
-                .iter()
-                .map(|entity| {
-                    serde_json::json!({
-                        "id": entity.id,
-                        "name": entity.name,
-                        "type": format!("{:?}", entity.entity_type),
-                        "position": {
-                            "x": entity.pos.x,
-                            "y": entity.pos.y
-                        }
+        .and_then(|game: SharedGameState| async move {
+            let response = game.backend.entities().await.map(|entities| {
+                let entities: Vec<_> = entities
+                    .iter()
+                    .map(|entity| {
+                        serde_json::json!({
+                            "id": entity.id,
+                            "name": entity.name,
+                            "type": format!("{:?}", entity.entity_type),
+                            "position": {
+                                "x": entity.pos.x,
+                                "y": entity.pos.y
+                            }
+                        })
                     })
-                })
-                .collect();
-            
-            warp::reply::json(&entities)
+                    .collect();
+                serde_json::json!(entities)
+            });
+            Ok::<_, std::convert::Infallible>(world_reply(response))
         });
 
-    let routes = register_route
+    // GET /api/sites - Retorna os locais do mundo (cidades, covis, ruínas)
+    let api_sites = warp::path!("api" / "sites")
+        .and(warp::get())
+        .map(|| AdminCommand::Sites)
+        .and(game_filter.clone())
+        .and_then(admin_reply);
+
+    // GET /api/sites/{id}/market - Estoque e preços locais
+    let api_site_market = warp::path!("api" / "sites" / u32 / "market")
+        .and(warp::get())
+        .map(|site_id: u32| AdminCommand::Market { site_id })
+        .and(game_filter.clone())
+        .and_then(admin_reply);
+
+    // GET /api/history - Consulta a história (filtros via query string)
+    let api_history = warp::path!("api" / "history")
+        .and(warp::get())
+        .and(warp::query::<HistoryParams>())
+        .map(AdminCommand::History)
+        .and(game_filter.clone())
+        .and_then(admin_reply);
+
+    // GET /api/history/entity/{id} - Tudo o que aconteceu a uma entidade
+    let api_entity_history = warp::path!("api" / "history" / "entity" / u32)
+        .and(warp::get())
+        .and(warp::query::<HistoryParams>())
+        .map(|entity_id: u32, params: HistoryParams| {
+            AdminCommand::History(HistoryParams {
+                participant: Some(entity_id),
+                ..params
+            })
+        })
+        .and(game_filter.clone())
+        .and_then(admin_reply);
+
+    // GET /api/replay - Sessão gravada, para reproduzir com `backend replay`
+    let api_replay = warp::path!("api" / "replay")
+        .and(warp::get())
+        .map(|| AdminCommand::Replay)
+        .and(game_filter.clone())
+        .and_then(admin_reply);
+
+    // GET /api/admin/systems - Tempo gasto por cada sistema da simulação
+    let api_systems = warp::path!("api" / "admin" / "systems")
+        .and(warp::get())
+        .map(|| AdminCommand::Systems)
+        .and(game_filter.clone())
+        .and_then(admin_reply);
+
+    // GET /api/admin/snapshots - Snapshots em memória
+    let api_snapshots = warp::path!("api" / "admin" / "snapshots")
+        .and(warp::get())
+        .map(|| AdminCommand::Snapshots)
+        .and(game_filter.clone())
+        .and_then(admin_reply);
+
+    // GET /api/admin/snapshots/{tick}/diff?to={tick} - Diferenças entre dois
+    // snapshots, ou entre um snapshot e o mundo atual
+    let api_snapshot_diff = warp::path!("api" / "admin" / "snapshots" / u64 / "diff")
+        .and(warp::get())
+        .and(warp::query::<DiffParams>())
+        .map(|from: u64, params: DiffParams| AdminCommand::SnapshotDiff { from, to: params.to })
+        .and(game_filter.clone())
+        .and_then(admin_reply);
+
+    let public_routes = register_route
         .or(login_route)
         .or(api_world_map)
         .or(api_players)
         .or(api_entities)
+        .or(api_sites)
+        .or(api_history)
+        .or(api_entity_history)
+        .or(api_site_market)
+        .or(api_replay)
+        .or(api_systems)
+        .or(api_snapshots)
+        .or(api_snapshot_diff)
         .or(ws_game)
         .or(event_stream)
         .or(static_files)
-        .with(cors);  // ← Adiciona CORS a todas as rotas
+        .with(cors);  // ← Adiciona CORS às rotas públicas
+    let routes = admin_routes.or(public_routes);
 
     println!("🎮 Game Server: http://127.0.0.1:8080");
     println!("📡 API endpoints:");
     println!("   GET /api/world/map");
     println!("   GET /api/players");
     println!("   GET /api/entities");
-    println!("✅ CORS habilitado para todas as origens");
+    println!("   GET /api/sites");
+    println!("   GET /api/sites/{{id}}/market");
+    println!("   GET /api/history");
+    println!("   GET /api/history/entity/{{id}}");
+    println!("   GET /api/replay");
+    println!("   GET /api/admin/systems");
+    println!("   GET /api/admin/snapshots");
+    println!("   GET /api/admin/snapshots/{{tick}}/diff[?to={{tick}}]");
+    println!("   POST /api/admin/snapshots (admin)");
+    println!("   POST /api/admin/snapshots/{{tick}}/rollback (admin)");
+    println!("✅ CORS habilitado para todas as origens, exceto nas rotas de admin");
     
-    warp::serve(routes).run(([127, 0, 0, 1], 8080)).await;
+    let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(([127, 0, 0, 1], 8080), async {
+        tokio::signal::ctrl_c().await.ok();
+    });
+    server.await;
+    // O mundo fica no backend, que o salva ao encerrar
+    println!("🛑 Game Server encerrado");
 }
 
 async fn handle_game_websocket(
@@ -329,131 +695,128 @@ async fn handle_game_websocket(
     let mut user_id: Option<uuid::Uuid> = None;
     let mut entity_id: Option<u32> = None;
     let mut player_name: String = String::new();
+    let mut updates: Option<watch::Receiver<Option<GameUpdate>>> = None;
+    // Tick da última visão enviada; uma visão empurrada do mesmo tick
+    // pode ser de antes do último movimento
+    let mut shown_tick = 0u64;
+    // As visões saem como diferenças sobre o que o cliente já tem
+    let mut sync = ViewportSync::new();
 
-    while let Some(result) = ws_rx.next().await {
+    loop {
+        let result = tokio::select! {
+            result = ws_rx.next() => match result {
+                Some(result) => result,
+                None => break,
+            },
+            // 🔔 ATUALIZAÇÃO DO TICK
+            Some(update) = next_update(&mut updates) => {
+                if update.tick > shown_tick {
+                    shown_tick = update.tick;
+                    let json = serde_json::to_string(&sync.encode(&update)).unwrap();
+                    if ws_tx.send(warp::ws::Message::text(json)).await.is_err() {
+                        break;
+                    }
+                }
+                continue;
+            }
+        };
         if let Ok(msg) = result {
             if let Ok(text) = msg.to_str() {
+
+                // CONFIRMAÇÕES E PEDIDOS DE RESYNC
+                if let Ok(control) = serde_json::from_str::<SyncMessage>(text) {
+                    match control {
+                        SyncMessage::Ack { seq } => sync.ack(seq),
+                        SyncMessage::Resync => {
+                            if let Some(frame) = sync.resync(shown_tick) {
+                                let json = serde_json::to_string(&frame).unwrap();
+                                let _ = ws_tx.send(warp::ws::Message::text(json)).await;
+                            }
+                        }
+                    }
+                    continue;
+                }
                 
                 // LOGIN
                 if DEBUG_MODE && user_id.is_none() {
-                    if let Ok(simple_msg) = serde_json::from_str::<ClientMessage>(text) {
-                        if let ClientMessage::Login { player_name: name } = simple_msg {
-                            println!("🔧 DEBUG: Login sem autenticação: {}", name);
-                            
-                            let uid = uuid::Uuid::new_v4();
-                            user_id = Some(uid);
-                            player_name = name.clone();
-                            
-                            let response = {
-                                let mut game = game_state.lock().unwrap();
-                                if let Some(eid) = game.spawn_player(uid, name.clone()) {
-                                    entity_id = Some(eid);
-                                    
-                                    // 🔔 BROADCAST EVENTO
-                                    let pos = game.world.get_entity(eid)
-                                        .map(|e| e.pos)
-                                        .unwrap_or(Position::new(0, 0));
-                                    
-                                    event_hub.lock().unwrap().broadcast(GameEvent::PlayerConnected {
-                                        name: name.clone(),
-                                        id: uid.to_string(),
-                                    });
-                                    
-                                    event_hub.lock().unwrap().broadcast(GameEvent::PlayerSpawned {
-                                        name: name,
-                                        pos,
-                                    });
-                                    
-                                    game.get_viewport(eid, 15).map(|viewport| {
-                                        GameUpdate {
-                                            tick: game.world.get_current_tick(),
-                                            viewport,
-                                        }
-                                    })
-                                } else {
-                                    None
-                                }
-                            };
-                            
-                            if let Some(update) = response {
-                                let json = serde_json::to_string(&update).unwrap();
-                                let _ = ws_tx.send(warp::ws::Message::text(json)).await;
+                    if let Ok(ClientMessage::Login { player_name: name }) = serde_json::from_str::<ClientMessage>(text) {
+                        println!("🔧 DEBUG: Login sem autenticação: {}", name);
+                        
+                        let uid = uuid::Uuid::new_v4();
+                        user_id = Some(uid);
+                        player_name = name.clone();
+                        
+                        let response = match game_state.spawn_player(uid, name.clone()).await {
+                            Some((entity, receiver)) => {
+                                entity_id = Some(entity.id);
+                                updates = Some(receiver);
+                                
+                                // 🔔 BROADCAST EVENTO
+                                event_hub.lock().unwrap().broadcast(GameEvent::PlayerConnected {
+                                    name: name.clone(),
+                                    id: uid.to_string(),
+                                });
+                                
+                                event_hub.lock().unwrap().broadcast(GameEvent::PlayerSpawned {
+                                    name,
+                                    pos: entity.pos,
+                                });
+                                
+                                game_state.get_viewport(entity.id, VIEW_RANGE).await
                             }
-                            continue;
+                            None => None,
+                        };
+                        
+                        if let Some(update) = response {
+                            shown_tick = update.tick;
+                            let json = serde_json::to_string(&sync.encode(&update)).unwrap();
+                            let _ = ws_tx.send(warp::ws::Message::text(json)).await;
                         }
+                        continue;
                     }
                 }
                 
-                // MOVIMENTO
-                if user_id.is_some() && entity_id.is_some() {
-                    let eid = entity_id.unwrap();
-                    
-                    if DEBUG_MODE {
-                        if let Ok(simple_msg) = serde_json::from_str::<ClientMessage>(text) {
-                            let response = {
-                                let mut game = game_state.lock().unwrap();
-                                match simple_msg {
-                                    ClientMessage::Move { dx, dy } => {
-                                        let old_pos = game.world.get_entity(eid)
-                                            .map(|e| e.pos)
-                                            .unwrap_or(Position::new(0, 0));
-                                        
-                                        game.world.move_entity(eid, dx, dy);
-                                        
-                                        let new_pos = game.world.get_entity(eid)
-                                            .map(|e| e.pos)
-                                            .unwrap_or(Position::new(0, 0));
-                                        
-                                        // 🔔 BROADCAST MOVIMENTO
-                                        if old_pos.x != new_pos.x || old_pos.y != new_pos.y {
-                                            event_hub.lock().unwrap().broadcast(GameEvent::PlayerMoved {
-                                                name: player_name.clone(),
-                                                from: old_pos,
-                                                to: new_pos,
-                                            });
-                                        }
-                                        
-                                        game.get_viewport(eid, 15).map(|viewport| {
-                                            GameUpdate {
-                                                tick: game.world.get_current_tick(),
-                                                viewport,
-                                            }
-                                        })
-                                    }
-                                    _ => None,
-                                }
-                            };
-
-                            if let Some(update) = response {
-                                let json = serde_json::to_string(&update).unwrap();
-                                let _ = ws_tx.send(warp::ws::Message::text(json)).await;
+                // AÇÕES
+                if let (Some(_), Some(eid)) = (user_id, entity_id) {
+                    // Em DEBUG a mensagem vem crua; em PRODUÇÃO, autenticada
+                    let message = if DEBUG_MODE {
+                        serde_json::from_str::<ClientMessage>(text).ok()
+                    } else {
+                        serde_json::from_str::<AuthenticatedMessage>(text).ok().map(|m| m.message)
+                    };
+                    let Some(message) = message else {
+                        continue;
+                    };
+                    let moving = matches!(message, ClientMessage::Move { .. });
+
+                    let response = match game_state.backend.act(eid, message).await {
+                        Ok(Acted { from, to, .. }) if moving => {
+                            // 🔔 BROADCAST MOVIMENTO
+                            if from != to {
+                                event_hub.lock().unwrap().broadcast(GameEvent::PlayerMoved {
+                                    name: player_name.clone(),
+                                    from,
+                                    to,
+                                });
                             }
+                            game_state.get_viewport(eid, VIEW_RANGE).await.map(|update| {
+                                shown_tick = update.tick;
+                                serde_json::to_string(&sync.encode(&update)).unwrap()
+                            })
                         }
-                    } 
-                    // Modo PRODUÇÃO: mensagem autenticada
-                    else {
-                        if let Ok(auth_msg) = serde_json::from_str::<AuthenticatedMessage>(text) {
-                            let response = {
-                                let mut game = game_state.lock().unwrap();
-                                match auth_msg.message {
-                                    ClientMessage::Move { dx, dy } => {
-                                        game.world.move_entity(eid, dx, dy);
-                                        game.get_viewport(eid, 15).map(|viewport| {
-                                            GameUpdate {
-                                                tick: game.world.get_current_tick(),
-                                                viewport,
-                                            }
-                                        })
-                                    }
-                                    _ => None,
-                                }
-                            }; // Lock LIBERADO AQUI
-
-                            if let Some(update) = response {
-                                let json = serde_json::to_string(&update).unwrap();
-                                let _ = ws_tx.send(warp::ws::Message::text(json)).await;
-                            }
+                        // O cliente recebe a resposta sem o envelope do RPC
+                        Ok(Acted { reply, .. }) => reply.map(|reply| match reply {
+                            ActReply::Lore(lore) => serde_json::to_string(&lore).unwrap(),
+                            ActReply::Message(message) => serde_json::to_string(&message).unwrap(),
+                        }),
+                        Err(e) => {
+                            eprintln!("⚠️  {}", e);
+                            None
                         }
+                    };
+
+                    if let Some(json) = response {
+                        let _ = ws_tx.send(warp::ws::Message::text(json)).await;
                     }
                 }
             }
@@ -461,10 +824,11 @@ async fn handle_game_websocket(
     }
     
     // 🔔 BROADCAST DESCONEXÃO
-    if user_id.is_some() {
+    if let Some(uid) = user_id {
+        game_state.leave(uid).await;
         event_hub.lock().unwrap().broadcast(GameEvent::PlayerDisconnected {
             name: player_name,
-            id: user_id.unwrap().to_string(),
+            id: uid.to_string(),
         });
     }
 }
@@ -490,7 +854,182 @@ async fn stream_events_to_admin(
     });
     
     // Mantém conexão aberta
-    while let Some(_) = ws_rx.next().await {
+    while ws_rx.next().await.is_some() {
         // Admin pode enviar comandos aqui no futuro
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_history_params_from_query_string() {
+        let params: HistoryParams = parse_params("faction=2&x=10&y=12&radius=4&event_type=Death&limit=5");
+        assert_eq!(params, HistoryParams {
+            faction: Some(2),
+            x: Some(10),
+            y: Some(12),
+            radius: Some(4),
+            event_type: Some(EventType::Death),
+            limit: Some(5),
+            ..Default::default()
+        });
+    }
+
+    #[tokio::test]
+    async fn test_viewport_draws_items_over_surfaces_over_constructions() {
+        // Um backend que não responde nada: o desenho é todo local
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let _connection = listener.accept().await;
+            std::future::pending::<()>().await;
+        });
+        let backend = WorldClient::connect(&addr).await.unwrap();
+        let game = GameState::with_backend(backend, Raws::builtin().as_ref().clone());
+
+        let tile = Tile::new(TerrainType::GRASS, true);
+        let wall = Construction::new(ConstructionType::Wall, false);
+        let spear = Item::new(1, "a crude spear".to_string(), ItemKind::Weapon);
+        let view = |x, construction: Option<Construction>, surface: Option<&str>, items: Vec<Item>| ipc::TileView {
+            pos: Position::new(x, 0),
+            tile: tile.clone(),
+            construction,
+            surface: surface.map(str::to_string),
+            items,
+        };
+        let update = game.render_viewport(&ViewportView {
+            tick: 9,
+            center: Position::new(1, 0),
+            radius: 1,
+            tiles: vec![
+                view(0, Some(wall.clone()), None, vec![]),
+                view(1, Some(wall.clone()), Some("blood"), vec![]),
+                view(2, Some(wall), Some("blood"), vec![spear.clone()]),
+            ],
+            entities: vec![],
+        });
+
+        let glyphs: Vec<char> = update.viewport.tiles.iter().map(|t| t.glyph).collect();
+        let blood = game.raws.surface("blood").unwrap().glyph.ch;
+        let spear_glyph = game.raws.item_of(&spear).glyph.ch;
+        assert_eq!(glyphs, vec!['#', blood, spear_glyph]);
+        assert_eq!((update.tick, update.viewport.player_pos, update.viewport.width), (9, Position::new(1, 0), 3));
+    }
+
+    /// Backend de mentira: cria jogadores e devolve uma visão por entidade
+    #[derive(Default)]
+    struct FakeWorld {
+        spawned: u32,
+    }
+
+    impl ipc::Service for FakeWorld {
+        fn handle(&mut self, _gateway: u64, request: ipc::WorldRequest) -> ipc::WorldResponse {
+            match request {
+                ipc::WorldRequest::SpawnPlayer { name } => {
+                    self.spawned += 1;
+                    let entity = Entity::new(self.spawned, name, Position::new(5, 5), EntityType::Player);
+                    ipc::WorldResponse::Spawned { entity: Some(entity) }
+                }
+                ipc::WorldRequest::Viewports { entity_ids, radius } => ipc::WorldResponse::Viewports(
+                    entity_ids
+                        .into_iter()
+                        .map(|id| (id, ViewportView {
+                            tick: 3,
+                            center: Position::new(id as i32, 0),
+                            radius,
+                            tiles: vec![],
+                            entities: vec![],
+                        }))
+                        .collect(),
+                ),
+                ipc::WorldRequest::Admin(AdminCommand::CaptureSnapshot) => {
+                    ipc::WorldResponse::Admin(ipc::AdminReply::Snapshot(None))
+                }
+                _ => ipc::WorldResponse::Error("não suportado".to_string()),
+            }
+        }
+
+        fn events(&self) -> tokio::sync::broadcast::Receiver<GameEvent> {
+            EventHub::new().subscribe()
+        }
+
+        fn attach(&mut self, _gateway: u64) {}
+
+        fn detach(&mut self, _gateway: u64) {}
+    }
+
+    #[tokio::test]
+    async fn test_each_player_gets_its_own_viewport_every_tick() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(ipc::serve(listener, Arc::new(Mutex::new(FakeWorld::default()))));
+        let backend = WorldClient::connect(&addr).await.unwrap();
+        let game = Arc::new(GameState::with_backend(backend, Raws::builtin().as_ref().clone()));
+        tokio::spawn(push_tick_updates(game.clone()));
+
+        let (hero, mut hero_updates) = game.spawn_player(uuid::Uuid::new_v4(), "Hero".to_string()).await.unwrap();
+        let (_, mut rival_updates) = game.spawn_player(uuid::Uuid::new_v4(), "Rival".to_string()).await.unwrap();
+        // O backend anuncia o tick; cada jogador recebe a própria visão
+        game.world_tick.send_replace(3);
+
+        hero_updates.changed().await.unwrap();
+        let update = hero_updates.borrow_and_update().clone().unwrap();
+        assert_eq!((update.tick, update.viewport.player_pos), (3, Position::new(hero.id as i32, 0)));
+        rival_updates.changed().await.unwrap();
+        let update = rival_updates.borrow_and_update().clone().unwrap();
+        assert_eq!(update.viewport.player_pos, Position::new(2, 0));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_actions_need_an_admin_from_the_panel() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(ipc::serve(listener, Arc::new(Mutex::new(FakeWorld::default()))));
+        let backend = WorldClient::connect(&addr).await.unwrap();
+        let game = Arc::new(GameState::with_backend(backend, Raws::builtin().as_ref().clone()));
+        let auth: SharedAuthState = Arc::new(Mutex::new(AuthState::new()));
+        let routes = admin_actions(auth.clone(), game);
+
+        let admin = auth.lock().unwrap().login("admin".to_string(), "admin123".to_string(), "test".to_string());
+        auth.lock().unwrap().register_user("Urist".to_string(), "picareta".to_string()).unwrap();
+        let player = auth.lock().unwrap().login("Urist".to_string(), "picareta".to_string(), "test".to_string());
+        let capture = |token: Option<uuid::Uuid>, origin: &str| {
+            let request = warp::test::request()
+                .method("POST")
+                .path("/api/admin/snapshots")
+                .header("origin", origin);
+            match token {
+                Some(token) => request.header("authorization", format!("Bearer {}", token)),
+                None => request,
+            }
+        };
+
+        let panel = ADMIN_PANEL_ORIGINS[0];
+        for token in [None, Some(uuid::Uuid::new_v4()), player.token] {
+            let response = capture(token, panel).reply(&routes).await;
+            assert_eq!(response.status(), warp::http::StatusCode::UNAUTHORIZED);
+        }
+        // Outra página não chega a pedir, nem com o token do admin
+        let response = capture(admin.token, "http://example.com").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::FORBIDDEN);
+        let rollback = warp::test::request()
+            .method("POST")
+            .path("/api/admin/snapshots/1/rollback")
+            .header("origin", "http://example.com")
+            .reply(&routes)
+            .await;
+        assert_eq!(rollback.status(), warp::http::StatusCode::FORBIDDEN);
+
+        let response = capture(admin.token, panel).reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+    }
+
+    fn parse_params(query: &str) -> HistoryParams {
+        let filter = warp::query::<HistoryParams>();
+        let request = warp::test::request().path(&format!("/?{}", query));
+        futures::executor::block_on(request.filter(&filter)).unwrap()
+    }
+}
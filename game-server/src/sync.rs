@@ -0,0 +1,321 @@
+use crate::{EntityData, GameUpdate, TileData, ViewportData};
+use common::Position;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Mensagens entre uma visão completa e a seguinte
+pub const KEYFRAME_INTERVAL: u64 = 30;
+/// Mensagens que podem ficar sem confirmação antes de o servidor voltar a
+/// mandar visões completas
+pub const MAX_UNACKED: u64 = 60;
+
+/// Atualização da visão enviada ao cliente
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ViewportMessage {
+    /// A visão inteira; o cliente descarta o que tinha
+    Keyframe {
+        seq: u64,
+        tick: u64,
+        viewport: ViewportData,
+    },
+    Delta(ViewportDelta),
+}
+
+/// O que mudou desde a visão `base`, a última que o cliente confirmou, e
+/// não necessariamente a última que ele recebeu. Tiles que saíram do
+/// alcance não são listados: o cliente os descarta pela nova posição do
+/// jogador.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ViewportDelta {
+    pub seq: u64,
+    pub base: u64,
+    pub tick: u64,
+    pub player_pos: Position,
+    /// Tiles que mudaram ou entraram no alcance
+    pub tiles: Vec<TileData>,
+    /// Entidades que apareceram ou mudaram além da posição
+    pub spawned: Vec<EntityData>,
+    pub moved: Vec<EntityMove>,
+    /// Entidades que morreram ou saíram do alcance
+    pub despawned: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EntityMove {
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Controle do fluxo de visões, enviado pelo cliente
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum SyncMessage {
+    /// O cliente aplicou a visão `seq`
+    Ack { seq: u64 },
+    /// O cliente perdeu uma visão e precisa de uma completa
+    Resync,
+}
+
+/// O que um cliente já tem da sua visão. Cada atualização sai como
+/// diferença sobre a última visão que o cliente confirmou, então uma
+/// mensagem perdida não deixa o cliente sem base; visões completas saem de
+/// tempos em tempos, quando o cliente pede, enquanto ele não confirmou
+/// nenhuma ou quando ele para de confirmar.
+#[derive(Debug, Default)]
+pub struct ViewportSync {
+    seq: u64,
+    /// Visões enviadas e ainda não confirmadas, em ordem, até `MAX_UNACKED`
+    unacked: VecDeque<(u64, ViewportData)>,
+    /// Última visão confirmada, base da próxima diferença
+    acked: Option<(u64, ViewportData)>,
+    last_keyframe: u64,
+}
+
+impl ViewportSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn encode(&mut self, update: &GameUpdate) -> ViewportMessage {
+        self.seq += 1;
+        let acked = self.acked.as_ref().map_or(0, |(seq, _)| *seq);
+        let keyframe = self.seq - self.last_keyframe >= KEYFRAME_INTERVAL || self.seq - acked > MAX_UNACKED;
+        let message = match self.acked.as_ref() {
+            Some((base, view)) if !keyframe => ViewportMessage::Delta(diff(*base, self.seq, update, view)),
+            _ => {
+                self.last_keyframe = self.seq;
+                ViewportMessage::Keyframe {
+                    seq: self.seq,
+                    tick: update.tick,
+                    viewport: update.viewport.clone(),
+                }
+            }
+        };
+        self.remember(update.viewport.clone());
+        message
+    }
+
+    /// O cliente aplicou a visão `seq`, que passa a ser a base. Confirmações
+    /// atrasadas ou de visões esquecidas não mudam nada.
+    pub fn ack(&mut self, seq: u64) {
+        let Some(index) = self.unacked.iter().position(|(sent, _)| *sent == seq) else {
+            return;
+        };
+        self.acked = self.unacked.drain(..=index).next_back();
+    }
+
+    /// A última visão enviada, inteira, para um cliente que se perdeu. Até
+    /// ele confirmar uma visão nova, não há base para diferenças.
+    pub fn resync(&mut self, tick: u64) -> Option<ViewportMessage> {
+        let (_, viewport) = self.unacked.back().or(self.acked.as_ref())?;
+        let viewport = viewport.clone();
+        self.acked = None;
+        self.seq += 1;
+        self.last_keyframe = self.seq;
+        self.remember(viewport.clone());
+        Some(ViewportMessage::Keyframe {
+            seq: self.seq,
+            tick,
+            viewport,
+        })
+    }
+
+    fn remember(&mut self, viewport: ViewportData) {
+        if self.unacked.len() as u64 == MAX_UNACKED {
+            self.unacked.pop_front();
+        }
+        self.unacked.push_back((self.seq, viewport));
+    }
+}
+
+fn diff(base: u64, seq: u64, update: &GameUpdate, acked: &ViewportData) -> ViewportDelta {
+    let old_tiles: HashMap<(i32, i32), &TileData> = acked.tiles.iter().map(|t| ((t.x, t.y), t)).collect();
+    let tiles = update
+        .viewport
+        .tiles
+        .iter()
+        .filter(|t| old_tiles.get(&(t.x, t.y)) != Some(t))
+        .cloned()
+        .collect();
+
+    let old_entities: HashMap<u32, &EntityData> = acked.entities.iter().map(|e| (e.id, e)).collect();
+    let mut spawned = Vec::new();
+    let mut moved = Vec::new();
+    for entity in &update.viewport.entities {
+        match old_entities.get(&entity.id) {
+            Some(old) if *old == entity => {}
+            Some(old) if (old.glyph, &old.color, &old.name) == (entity.glyph, &entity.color, &entity.name) => moved
+                .push(EntityMove {
+                    id: entity.id,
+                    x: entity.x,
+                    y: entity.y,
+                }),
+            _ => spawned.push(entity.clone()),
+        }
+    }
+    let mut despawned: Vec<u32> = acked
+        .entities
+        .iter()
+        .map(|e| e.id)
+        .filter(|id| !update.viewport.entities.iter().any(|e| e.id == *id))
+        .collect();
+    despawned.sort_unstable();
+
+    ViewportDelta {
+        seq,
+        base,
+        tick: update.tick,
+        player_pos: update.viewport.player_pos,
+        tiles,
+        spawned,
+        moved,
+        despawned,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(x: i32, glyph: char) -> TileData {
+        TileData {
+            x,
+            y: 0,
+            glyph,
+            fg_color: "#fff".to_string(),
+            bg_color: "#000".to_string(),
+        }
+    }
+
+    fn entity(id: u32, x: i32) -> EntityData {
+        EntityData {
+            id,
+            x,
+            y: 0,
+            glyph: 'g',
+            color: "#0f0".to_string(),
+            name: format!("Goblin {}", id),
+        }
+    }
+
+    fn update(tick: u64, tiles: Vec<TileData>, entities: Vec<EntityData>) -> GameUpdate {
+        GameUpdate {
+            tick,
+            viewport: ViewportData {
+                tiles,
+                entities,
+                player_pos: Position::new(1, 0),
+                width: 3,
+                height: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn test_delta_carries_only_what_changed() {
+        let mut sync = ViewportSync::new();
+        let first = update(
+            1,
+            vec![tile(0, '.'), tile(1, '.'), tile(2, '.')],
+            vec![entity(1, 0), entity(2, 1)],
+        );
+        assert!(matches!(sync.encode(&first), ViewportMessage::Keyframe { seq: 1, .. }));
+        sync.ack(1);
+
+        let second = update(
+            2,
+            vec![tile(0, '.'), tile(1, '~'), tile(2, '.')],
+            vec![entity(1, 1), entity(3, 2)],
+        );
+        let ViewportMessage::Delta(delta) = sync.encode(&second) else {
+            panic!("expected a delta");
+        };
+        assert_eq!((delta.seq, delta.base, delta.tick), (2, 1, 2));
+        assert_eq!(delta.tiles, vec![tile(1, '~')]);
+        assert_eq!(delta.moved, vec![EntityMove { id: 1, x: 1, y: 0 }]);
+        assert_eq!(delta.spawned, vec![entity(3, 2)]);
+        assert_eq!(delta.despawned, vec![2]);
+    }
+
+    #[test]
+    fn test_keyframes_are_periodic_and_follow_missing_acks() {
+        let view = update(1, vec![tile(0, '.')], vec![]);
+        let mut sync = ViewportSync::new();
+        let keyframes: Vec<u64> = (0..2 * KEYFRAME_INTERVAL)
+            .filter_map(|_| match sync.encode(&view) {
+                ViewportMessage::Keyframe { seq, .. } => {
+                    sync.ack(seq);
+                    Some(seq)
+                }
+                ViewportMessage::Delta(delta) => {
+                    sync.ack(delta.seq);
+                    None
+                }
+            })
+            .collect();
+        assert_eq!(keyframes, vec![1, KEYFRAME_INTERVAL + 1]);
+
+        // Um cliente que não confirma nada passa a receber visões completas
+        let mut silent = ViewportSync::new();
+        let last = (0..=MAX_UNACKED).map(|_| silent.encode(&view)).last().unwrap();
+        assert!(matches!(last, ViewportMessage::Keyframe { .. }));
+    }
+
+    #[test]
+    fn test_resync_resends_the_last_view_whole() {
+        let mut sync = ViewportSync::new();
+        assert!(sync.resync(0).is_none());
+
+        let view = update(4, vec![tile(0, '.')], vec![entity(1, 0)]);
+        sync.encode(&view);
+        sync.encode(&view);
+        let resent = sync.resync(4).unwrap();
+        assert_eq!(
+            resent,
+            ViewportMessage::Keyframe {
+                seq: 3,
+                tick: 4,
+                viewport: view.viewport.clone(),
+            }
+        );
+        // Até confirmar a visão reenviada o cliente não tem base
+        assert!(matches!(sync.encode(&view), ViewportMessage::Keyframe { seq: 4, .. }));
+        sync.ack(3);
+        let ViewportMessage::Delta(delta) = sync.encode(&view) else {
+            panic!("expected a delta");
+        };
+        assert_eq!((delta.base, delta.tiles.len()), (3, 0));
+    }
+
+    #[test]
+    fn test_delta_survives_a_lost_message() {
+        let mut sync = ViewportSync::new();
+        let first = update(1, vec![tile(0, '.'), tile(1, '.'), tile(2, '.')], vec![entity(1, 0)]);
+        sync.encode(&first);
+        sync.ack(1);
+
+        // A visão 2 se perde antes de o cliente confirmá-la
+        let second = update(2, vec![tile(0, '.'), tile(1, '~'), tile(2, '.')], vec![entity(1, 1)]);
+        let ViewportMessage::Delta(lost) = sync.encode(&second) else {
+            panic!("expected a delta");
+        };
+        assert_eq!(lost.base, 1);
+
+        // A 3 ainda parte da 1 e traz também o que mudou na 2
+        let third = update(3, vec![tile(0, '.'), tile(1, '~'), tile(2, '#')], vec![entity(1, 2)]);
+        let ViewportMessage::Delta(delta) = sync.encode(&third) else {
+            panic!("expected a delta");
+        };
+        assert_eq!((delta.seq, delta.base), (3, 1));
+        assert_eq!(delta.tiles, vec![tile(1, '~'), tile(2, '#')]);
+        assert_eq!(delta.moved, vec![EntityMove { id: 1, x: 2, y: 0 }]);
+
+        // Confirmada a 3, uma confirmação atrasada da 2 não volta a base
+        sync.ack(3);
+        sync.ack(2);
+        let ViewportMessage::Delta(delta) = sync.encode(&third) else {
+            panic!("expected a delta");
+        };
+        assert_eq!((delta.base, delta.tiles.len()), (3, 0));
+    }
+}
@@ -6,6 +6,11 @@ let ws = null;
 let sessionToken = null;
 let canvas, ctx;
 let viewport = null;
+// Número da última visão aplicada; null enquanto espera uma completa
+let viewportSeq = null;
+// Visões aplicadas por número: um Delta parte da última que o servidor viu
+// confirmada, que pode ser anterior à mais recente
+let viewports = new Map();
 let playerName = '';
 
 const TILE_SIZE = 16;
@@ -84,6 +89,8 @@ function connectWebSocket() {
     
     ws.onopen = () => {
         console.log('✅ WebSocket conectado');
+        viewportSeq = null;
+        viewports.clear();
         
         if (DEBUG_MODE) {
             // Em modo debug, envia mensagem de login direto
@@ -109,16 +116,15 @@ function connectWebSocket() {
             if (!DEBUG_MODE) {
                 window.location.reload();
             }
-        } else {
-            viewport = data.viewport;
-            document.getElementById('tick').textContent = data.tick;
-            
-            if (viewport) {
-                document.getElementById('position').textContent = 
-                    `@(${viewport.player_pos.x},${viewport.player_pos.y})`;
-                renderViewport();
-                updateNearbyList();
-            }
+        } else if ('lore' in data) {
+            showLore(data.lore);
+        } else if (data.ActionResult) {
+            const result = data.ActionResult;
+            addMessage(result.message, result.success ? 'info' : 'combat');
+        } else if (data.Keyframe) {
+            applyKeyframe(data.Keyframe);
+        } else if (data.Delta) {
+            applyDelta(data.Delta);
         }
     };
     
@@ -140,6 +146,67 @@ function connectWebSocket() {
     };
 }
 
+// Visão completa: substitui tudo o que havia
+function applyKeyframe(frame) {
+    viewport = frame.viewport;
+    viewportSeq = frame.seq;
+    viewports.set(frame.seq, viewport);
+    showViewport(frame.tick);
+}
+
+// Só o que mudou desde a visão `base`
+function applyDelta(delta) {
+    if (viewportSeq === null) return;
+    const base = viewports.get(delta.base);
+    if (!base) {
+        // O cliente não tem a base: pede a visão inteira
+        viewportSeq = null;
+        viewports.clear();
+        ws.send(JSON.stringify('Resync'));
+        return;
+    }
+
+    const range = (base.width - 1) / 2;
+    const inView = (x, y) =>
+        Math.abs(x - delta.player_pos.x) <= range && Math.abs(y - delta.player_pos.y) <= range;
+
+    const tiles = new Map(base.tiles.map(t => [`${t.x},${t.y}`, t]));
+    delta.tiles.forEach(t => tiles.set(`${t.x},${t.y}`, t));
+
+    const entities = new Map(base.entities.map(e => [e.id, e]));
+    delta.despawned.forEach(id => entities.delete(id));
+    delta.moved.forEach(m => {
+        const entity = entities.get(m.id);
+        if (entity) {
+            entities.set(m.id, { ...entity, x: m.x, y: m.y });
+        }
+    });
+    delta.spawned.forEach(e => entities.set(e.id, e));
+
+    viewport = {
+        ...base,
+        tiles: [...tiles.values()].filter(t => inView(t.x, t.y)),
+        entities: [...entities.values()],
+        player_pos: delta.player_pos,
+    };
+    viewportSeq = delta.seq;
+    // O servidor não parte mais de visões anteriores à base
+    for (const seq of viewports.keys()) {
+        if (seq < delta.base) viewports.delete(seq);
+    }
+    viewports.set(delta.seq, viewport);
+    showViewport(delta.tick);
+}
+
+function showViewport(tick) {
+    ws.send(JSON.stringify({ Ack: { seq: viewportSeq } }));
+    document.getElementById('tick').textContent = tick;
+    document.getElementById('position').textContent =
+        `@(${viewport.player_pos.x},${viewport.player_pos.y})`;
+    renderViewport();
+    updateNearbyList();
+}
+
 function handleKeyPress(e) {
     if (!ws || !viewport) return;
     
@@ -152,6 +219,10 @@ function handleKeyPress(e) {
         case 'a': case 'h': case 'arrowleft': dx = -1; break;
         case 'd': case 'l': case 'arrowright': dx = 1; break;
         case '.': return; // Wait
+        case 'r': sendMessage('ReadHistory'); e.preventDefault(); return;
+        case 'e': sendMessage('EscortCaravan'); e.preventDefault(); return;
+        case 'x': sendMessage('RobCaravan'); e.preventDefault(); return;
+        case 't': promptTrade(); e.preventDefault(); return;
         default: return;
     }
     
@@ -166,6 +237,40 @@ function handleKeyPress(e) {
     }
 }
 
+function sendMessage(message) {
+    const msg = DEBUG_MODE ? message : { token: sessionToken, message };
+    ws.send(JSON.stringify(msg));
+}
+
+const RESOURCES = ['Food', 'Wood', 'Stone', 'Metal'];
+
+function promptTrade() {
+    const answer = prompt('Trocar com a caravana: <dar> <levar> <quantidade> (ex: Metal Food 5)');
+    if (!answer) return;
+
+    const [give, take, amount] = answer.trim().split(/\s+/);
+    const normalize = name => RESOURCES.find(r => r.toLowerCase() === (name || '').toLowerCase());
+    const trade = { give: normalize(give), take: normalize(take), amount: parseInt(amount, 10) };
+    if (!trade.give || !trade.take || !(trade.amount > 0)) {
+        addMessage('Troca inválida.', 'combat');
+        return;
+    }
+    sendMessage({ TradeWithCaravan: trade });
+}
+
+function showLore(lore) {
+    if (!lore) {
+        addMessage('Nada aqui guarda memórias.', 'info');
+        return;
+    }
+    if (lore.entries.length === 0) {
+        addMessage('Nada de notável aconteceu aqui.', 'info');
+    }
+    // O log mostra a mensagem mais recente no topo
+    [...lore.entries].reverse().forEach(entry => addMessage(entry, 'info'));
+    addMessage(`~ ${lore.site} (${lore.state}) ~`, 'info');
+}
+
 function resizeCanvas() {
     const container = canvas.parentElement;
     canvas.width = container.clientWidth;